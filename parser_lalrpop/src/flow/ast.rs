@@ -2,112 +2,391 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
+use lalrpop_util::{ErrorRecovery, ParseError};
+use num_bigint::BigUint;
+use paracell_parser_sem::span::Span;
 use paracell_util_macro::{AsVariant, ToLiteral};
+use std::fmt::Display;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error)]
+pub enum SyntaxError {
+    #[error("invalid token")]
+    InvalidToken,
+    #[error("unexpected end of file")]
+    UnrecognizedEof { expected: Vec<String> },
+    #[error("unexpected token `{found}`")]
+    UnrecognizedToken { found: String, expected: Vec<String> },
+    #[error("extra token `{found}`")]
+    ExtraToken { found: String },
+    #[error("invalid number literal `{lit}`")]
+    InvalidNat { lit: String },
+    #[error("unterminated block comment")]
+    UnterminatedComment,
+}
+
+impl SyntaxError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SyntaxError::InvalidToken => "E0002",
+            SyntaxError::UnrecognizedEof { .. } => "E0003",
+            SyntaxError::UnrecognizedToken { .. } => "E0004",
+            SyntaxError::ExtraToken { .. } => "E0005",
+            SyntaxError::InvalidNat { .. } => "E0006",
+            SyntaxError::UnterminatedComment => "E0009",
+        }
+    }
+}
+
+/// Terminals standing for a class of tokens rather than a fixed lexeme.
+const TOKEN_CLASSES: &[&str] = &["identifier", "number", "doc comment"];
+
+/// Unquotes the token class terminals LALRPOP reports, so they read as what they stand for.
+fn describe_expected(expected: Vec<String>) -> Vec<String> {
+    expected.into_iter().map(|terminal| {
+        match terminal.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            Some(class) if TOKEN_CLASSES.contains(&class) => class.to_string(),
+            _ => terminal,
+        }
+    }).collect()
+}
+
+/// Placeholder left in the tree where the parser recovered from a syntax error.
+#[derive(Clone, Debug)]
+pub struct ErrorItem {
+    pub error: SyntaxError,
+    pub span: Span,
+}
+
+impl ErrorItem {
+    pub fn from_parse_error<T: Display>(error: ParseError<usize, T, ErrorItem>) -> ErrorItem {
+        match error {
+            ParseError::InvalidToken { location } => ErrorItem {
+                error: SyntaxError::InvalidToken,
+                span: Span::new(location, location + 1),
+            },
+            ParseError::UnrecognizedEof { location, expected } => ErrorItem {
+                error: SyntaxError::UnrecognizedEof { expected: describe_expected(expected) },
+                span: Span::new(location, location),
+            },
+            ParseError::UnrecognizedToken { token: (l, token, r), expected } => ErrorItem {
+                error: SyntaxError::UnrecognizedToken { found: token.to_string(), expected: describe_expected(expected) },
+                span: Span::new(l, r),
+            },
+            ParseError::ExtraToken { token: (l, token, r) } => ErrorItem {
+                error: SyntaxError::ExtraToken { found: token.to_string() },
+                span: Span::new(l, r),
+            },
+            ParseError::User { error } => error,
+        }
+    }
+
+    /// The reported span covers the offending token and every token dropped while recovering.
+    pub fn from_recovery<T: Display>(recovery: ErrorRecovery<usize, T, ErrorItem>) -> ErrorItem {
+        let mut item = ErrorItem::from_parse_error(recovery.error);
+        if let Some((_, _, r)) = recovery.dropped_tokens.last() {
+            item.span = item.span.to(Span::new(*r, *r));
+        }
+        item
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Nat {
-    pub val: u128,
+    pub val: BigUint,
+    pub width: Option<u32>,
+    pub span: Span,
+}
+
+impl Nat {
+    /// Parses a literal lexeme: decimal, or hexadecimal, octal and binary with `0x`, `0o` and `0b`,
+    /// with `_` between digits and an optional width in bits after a `'`, as in `0xFFFF_0000'32`.
+    pub fn from_lit(lit: &str, span: Span) -> Result<Nat, ErrorItem> {
+        let invalid = || ErrorItem { error: SyntaxError::InvalidNat { lit: lit.to_string() }, span };
+        let (number, width) = match lit.split_once('\'') {
+            Some((number, bits)) => match bits.parse::<u32>() {
+                Ok(width) if width > 0 && bits.starts_with(|c: char| c.is_ascii_digit() && c != '0') => (number, Some(width)),
+                _ => return Err(invalid()),
+            },
+            None => (lit, None),
+        };
+        let (digits, radix) = match number.get(..2) {
+            Some("0x") => (&number[2..], 16),
+            Some("0o") => (&number[2..], 8),
+            Some("0b") => (&number[2..], 2),
+            _ if number.len() > 1 && number.starts_with('0') => return Err(invalid()),
+            _ => (number, 10),
+        };
+        let mut nat = Nat::from_digits(lit, digits, radix, span)?;
+        nat.width = width;
+        Ok(nat)
+    }
+
+    /// Parses the digits of a literal, `lit` being the whole literal as written.
+    /// A `_` may stand between two digits.
+    pub fn from_digits(lit: &str, digits: &str, radix: u32, span: Span) -> Result<Nat, ErrorItem> {
+        let valid = digits.starts_with(|c: char| c.is_digit(radix))
+            && !digits.ends_with('_')
+            && digits.chars().all(|c| c == '_' || c.is_digit(radix));
+        let digits = digits.replace('_', "");
+        match BigUint::parse_bytes(digits.as_bytes(), radix) {
+            Some(val) if valid => Ok(Nat { val, width: None, span }),
+            _ => Err(ErrorItem { error: SyntaxError::InvalidNat { lit: lit.to_string() }, span }),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Ident {
     pub lit: String,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct IdentItem {
     pub ident: Ident,
     pub item: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct RecordType {
     pub fields: Vec<IdentItem>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct UnionType {
     pub variants: Vec<IdentItem>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct FuncType {
     pub param_tuple: Tuple,
-    pub result_ty: Item,
+    /// Left to inference when omitted, as in `fun (a, b) { a + b }`.
+    pub result_ty: Option<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Block {
     pub elems: Vec<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Func {
     pub ty: FuncType,
     pub block: Block,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct RecordExpr {
     pub fields: Vec<IdentItem>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct ApplyExpr {
     pub func: Item,
     pub params: Tuple,
+    pub span: Span,
+}
+
+/// `Nat[8]`
+#[derive(Clone, Debug)]
+pub struct ApplyType {
+    pub ty: Item,
+    pub params: TypeTuple,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub segments: Vec<Ident>,
+    pub span: Span,
+}
+
+// Patterns
+
+#[derive(Clone, Debug)]
+pub struct WildcardPattern {
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct VariantPattern {
+    pub path: Path,
+    pub payload: Option<TuplePattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldPattern {
+    pub ident: Option<Ident>,
+    pub pattern: Pattern,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct TuplePattern {
+    pub fields: Vec<FieldPattern>,
+    /// Whether the last field is followed by `,`, making `(x,)` a record rather than a group.
+    pub trailing: bool,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct OrPattern {
+    pub alts: Vec<Pattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct RangePattern {
+    pub lo: Nat,
+    pub hi: Nat,
+    pub inclusive: bool,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, AsVariant)]
+pub enum Pattern {
+    Wildcard(WildcardPattern),
+    Nat(Nat),
+    Bind(Ident),
+    Variant(Box<VariantPattern>),
+    Tuple(Box<TuplePattern>),
+    Or(Box<OrPattern>),
+    Range(Box<RangePattern>),
+
+    Error(Box<ErrorItem>),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(v) => v.span,
+            Pattern::Nat(v) => v.span,
+            Pattern::Bind(v) => v.span,
+            Pattern::Variant(v) => v.span,
+            Pattern::Tuple(v) => v.span,
+            Pattern::Or(v) => v.span,
+            Pattern::Range(v) => v.span,
+            Pattern::Error(v) => v.span,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Case {
-    pub pattern: Item,
+    pub pattern: Pattern,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Match {
     pub expr: Item,
     pub cases: Vec<Case>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct LetDecl {
+    /// Lines of the `///` comments right before the declaration.
+    pub docs: Vec<String>,
     pub ident: Ident,
+    /// `[T, N: Nat]` after the name, the generic parameters.
+    pub generics: Option<TypeTuple>,
+    pub ty: Option<Item>,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct VarDecl {
+    pub docs: Vec<String>,
     pub ident: Ident,
+    pub ty: Option<Item>,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct TypeAliasDecl {
+    pub docs: Vec<String>,
     pub ident: Ident,
+    pub generics: Option<TypeTuple>,
     pub ty: Item,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct FuncDecl {
+    pub docs: Vec<String>,
+    pub ident: Ident,
+    pub generics: Option<TypeTuple>,
+    pub func: Func,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModDecl {
+    pub docs: Vec<String>,
+    pub ident: Ident,
+    pub span: Span,
+}
+
+/// `import alu::ALU`, whose path may have a single segment.
+#[derive(Clone, Debug)]
+pub struct ImportDecl {
+    pub path: Path,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assign {
+    pub target: Item,
+    pub expr: Item,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct While {
+    pub cond: Item,
+    pub block: Block,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Tuple {
     pub elems: Vec<Item>,
+    /// Whether the last element is followed by `,`, making `(x,)` a record rather than a group.
+    pub trailing: bool,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct TypeTuple {
     pub elems: Vec<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Select {
     pub expr: Item,
     pub ident: Ident,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Pipe {
     pub from: Item,
     pub to: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, ToLiteral)]
@@ -134,12 +413,35 @@ pub enum BinaryOperator {
     And,
     #[literal = "|"]
     Or,
+    #[literal = "^"]
+    Xor,
+    #[literal = "<<"]
+    Shl,
+    #[literal = ">>"]
+    Shr,
+    #[literal = "=="]
+    Eq,
+    #[literal = "!="]
+    Ne,
+    #[literal = "<"]
+    Lt,
+    #[literal = "<="]
+    Le,
+    #[literal = ">"]
+    Gt,
+    #[literal = ">="]
+    Ge,
+    #[literal = "&&"]
+    LogicAnd,
+    #[literal = "||"]
+    LogicOr,
 }
 
 #[derive(Clone, Debug)]
 pub struct UnaryOpExpr {
     pub op: UnaryOperator,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -147,12 +449,14 @@ pub struct BinaryOpExpr {
     pub op: BinaryOperator,
     pub left: Item,
     pub right: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, AsVariant)]
 pub enum Item {
     Nat(Nat),
     Ident(Ident),
+    Path(Path),
     Tuple(Tuple),
     Block(Box<Block>),
     Func(Box<Func>),
@@ -162,6 +466,7 @@ pub enum Item {
     RecordType(Box<RecordType>),
     UnionType(Box<UnionType>),
     FuncType(Box<FuncType>),
+    ApplyType(Box<ApplyType>),
 
     UnaryOpExpr(Box<UnaryOpExpr>),
     BinaryOpExpr(Box<BinaryOpExpr>),
@@ -175,9 +480,85 @@ pub enum Item {
     LetDecl(Box<LetDecl>),
     VarDecl(Box<VarDecl>),
     TypeAliasDecl(Box<TypeAliasDecl>),
+    FuncDecl(Box<FuncDecl>),
+    ModDecl(Box<ModDecl>),
+    ImportDecl(Box<ImportDecl>),
+
+    Assign(Box<Assign>),
+    While(Box<While>),
+
+    Error(Box<ErrorItem>),
+}
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Nat(v) => v.span,
+            Item::Ident(v) => v.span,
+            Item::Path(v) => v.span,
+            Item::Tuple(v) => v.span,
+            Item::Block(v) => v.span,
+            Item::Func(v) => v.span,
+            Item::Match(v) => v.span,
+            Item::TypeTuple(v) => v.span,
+            Item::RecordType(v) => v.span,
+            Item::UnionType(v) => v.span,
+            Item::FuncType(v) => v.span,
+            Item::ApplyType(v) => v.span,
+            Item::UnaryOpExpr(v) => v.span,
+            Item::BinaryOpExpr(v) => v.span,
+            Item::ApplyExpr(v) => v.span,
+            Item::Select(v) => v.span,
+            Item::Pipe(v) => v.span,
+            Item::IdentItem(v) => v.span,
+            Item::LetDecl(v) => v.span,
+            Item::VarDecl(v) => v.span,
+            Item::TypeAliasDecl(v) => v.span,
+            Item::FuncDecl(v) => v.span,
+            Item::ModDecl(v) => v.span,
+            Item::ImportDecl(v) => v.span,
+            Item::Assign(v) => v.span,
+            Item::While(v) => v.span,
+            Item::Error(v) => v.span,
+        }
+    }
+
+    /// Human-readable name of the node kind, used in diagnostics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Item::Nat(_) => "number literal",
+            Item::Ident(_) => "identifier",
+            Item::Path(_) => "path",
+            Item::Tuple(_) => "tuple",
+            Item::Block(_) => "block",
+            Item::Func(_) => "function",
+            Item::Match(_) => "match expression",
+            Item::TypeTuple(_) => "type tuple",
+            Item::RecordType(_) => "record type",
+            Item::UnionType(_) => "union type",
+            Item::FuncType(_) => "function type",
+            Item::ApplyType(_) => "type application",
+            Item::UnaryOpExpr(_) => "unary expression",
+            Item::BinaryOpExpr(_) => "binary expression",
+            Item::ApplyExpr(_) => "application",
+            Item::Select(_) => "field selection",
+            Item::Pipe(_) => "pipe",
+            Item::IdentItem(_) => "named item",
+            Item::LetDecl(_) => "`let` declaration",
+            Item::VarDecl(_) => "`var` declaration",
+            Item::TypeAliasDecl(_) => "type alias",
+            Item::FuncDecl(_) => "function declaration",
+            Item::ModDecl(_) => "`mod` declaration",
+            Item::ImportDecl(_) => "`import`",
+            Item::Assign(_) => "assignment",
+            Item::While(_) => "`while` loop",
+            Item::Error(_) => "syntax error",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct SourceFile {
     pub items: Vec<Item>,
+    pub span: Span,
 }
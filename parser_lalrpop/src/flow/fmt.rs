@@ -0,0 +1,529 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Formats flow source. Unlike `print`, which starts from a `sem` tree, it works on the
+//! syntax tree, so comments, number spellings and parentheses survive.
+//!
+//! - Bodies of blocks, `match`, and multi-line `record` and `union` types get one element per line, indented by four spaces.
+//! - The `=>` of the arms of a `match` line up.
+//! - Runs of blank lines shrink to one, and blank lines right after `{` go away.
+//! - A comment stays on the line of the element it follows, or on its own line before the next one.
+//!   Within an element, it stays next to the token before it, and a line comment there carries the
+//!   rest of the element over to the next line, one level deeper.
+//!
+//! Formatting formatted source changes nothing.
+
+use crate::flow::ast::*;
+use crate::flow::grammar::SourceFileParser;
+use crate::flow::lexer::{Comment, Lexer};
+use crate::flow::sem::{SemanticError, SemanticErrors, SemanticResult};
+use paracell_parser_sem::span::Span;
+
+const INDENT: &str = "    ";
+
+/// Formats a source file, or reports its syntax errors.
+pub fn format(src: &str) -> SemanticResult<String> {
+    let mut lexer = Lexer::new(src);
+    let file = SourceFileParser::new()
+        .parse(&mut lexer)
+        .map_err(|e| SemanticError::from(&ErrorItem::from_parse_error(e)))?;
+    let mut formatter = Formatter {
+        src,
+        comments: lexer.comments(),
+        next: 0,
+        last: 0,
+        fresh: true,
+        out: String::new(),
+        errors: Vec::new(),
+    };
+    formatter.source_file(&file);
+    if formatter.errors.is_empty() {
+        Ok(formatter.out)
+    } else {
+        Err(SemanticErrors { errors: formatter.errors })
+    }
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    comments: &'a [Comment<'a>],
+    /// Index of the first comment not written yet.
+    next: usize,
+    /// Source offset where the last written element or comment ends.
+    last: usize,
+    /// Whether nothing has been written in the innermost body yet.
+    fresh: bool,
+    out: String,
+    errors: Vec<SemanticError>,
+}
+
+/// Spelling of a binary operator in flow, where `&` and `|` are words.
+fn spelling(op: &BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::And => "and",
+        BinaryOperator::Or => "or",
+        op => op.to_literal(),
+    }
+}
+
+/// Whether an element of a block keeps its `;` even when it comes last.
+fn terminated(item: &Item) -> bool {
+    matches!(item, Item::LetDecl(_) | Item::VarDecl(_) | Item::TypeAliasDecl(_) | Item::Assign(_))
+}
+
+impl Formatter<'_> {
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Starts a line at `depth`, keeping one blank line if the source has any before `pos`.
+    fn break_line(&mut self, pos: usize, depth: usize) {
+        let blank = self.src.get(self.last..pos).is_some_and(|gap| gap.matches('\n').count() > 1);
+        if !self.out.is_empty() {
+            if blank && !self.fresh {
+                self.out.push('\n');
+            }
+            self.out.push('\n');
+        }
+        self.indent(depth);
+        self.fresh = false;
+    }
+
+    /// Writes each pending comment that starts before `pos` on a line of its own.
+    fn comments_before(&mut self, pos: usize, depth: usize) {
+        while let Some(comment) = self.comments.get(self.next)
+            && comment.span.start < pos
+        {
+            self.break_line(comment.span.start, depth);
+            self.out.push_str(comment.text);
+            self.last = comment.span.end;
+            self.next += 1;
+        }
+    }
+
+    /// Writes each pending comment that starts before `pos` where the output stands, as it lies
+    /// within an element. A line comment ends the line, which goes on one level deeper.
+    fn inline(&mut self, pos: usize, depth: usize) {
+        while let Some(comment) = self.comments.get(self.next)
+            && comment.span.start < pos
+        {
+            if comment.text.starts_with("//") {
+                self.out.truncate(self.out.trim_end().len());
+                self.out.push(' ');
+                self.out.push_str(comment.text);
+                self.out.push('\n');
+                self.indent(depth + 1);
+            } else {
+                if !self.out.ends_with([' ', '(', '[']) {
+                    self.out.push(' ');
+                }
+                self.out.push_str(comment.text);
+                self.out.push(' ');
+            }
+            self.last = comment.span.end;
+            self.next += 1;
+        }
+    }
+
+    /// Drops the space after a block comment, but not the indent after a line comment.
+    fn settle(&mut self) {
+        if !self.out.rsplit('\n').next().unwrap_or_default().trim().is_empty() {
+            self.out.truncate(self.out.trim_end().len());
+        }
+    }
+
+    /// Writes the `)` or `]` ending at `end`, after the comments before it.
+    fn close_list(&mut self, closer: char, end: usize, depth: usize) {
+        self.inline(end - 1, depth);
+        self.settle();
+        self.out.push(closer);
+    }
+
+    /// Writes the infix operator `op` that follows an operand ending at `pos`, after the comments
+    /// between them.
+    fn operator(&mut self, op: &str, mut pos: usize, depth: usize) {
+        for comment in &self.comments[self.next..] {
+            match self.src.get(pos..comment.span.start) {
+                Some(gap) if gap.trim().is_empty() => pos = comment.span.end,
+                _ => break,
+            }
+        }
+        self.inline(pos, depth);
+        self.settle();
+        if !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+        self.out.push_str(op);
+        self.out.push(' ');
+    }
+
+    /// Starts the line of an element beginning at `pos`, after the comments before it.
+    fn line(&mut self, pos: usize, depth: usize) {
+        self.comments_before(pos, depth);
+        self.break_line(pos, depth);
+    }
+
+    /// Appends the comments that follow the last element on its source line.
+    fn trailing(&mut self) {
+        while let Some(comment) = self.comments.get(self.next)
+            && let Some(gap) = self.src.get(self.last..comment.span.start)
+            && gap.chars().all(|c| c == ';' || c == ',' || c != '\n' && c.is_whitespace())
+        {
+            self.out.push(' ');
+            self.out.push_str(comment.text);
+            self.last = comment.span.end;
+            self.next += 1;
+        }
+    }
+
+    /// Writes `{` for a body starting at `pos`, with a comment that follows it on the same line.
+    fn open(&mut self, pos: usize) {
+        self.out.push('{');
+        self.last = pos + 1;
+        self.fresh = true;
+        self.trailing();
+    }
+
+    /// Writes the `}` ending at `end`, after the comments left in the body.
+    fn close(&mut self, end: usize, depth: usize) {
+        self.comments_before(end - 1, depth + 1);
+        self.out.push('\n');
+        self.indent(depth);
+        self.out.push('}');
+        self.last = end;
+        self.fresh = false;
+    }
+
+    /// Whether a comment lies within `span`, so the node cannot be written on one line.
+    fn has_comment(&self, span: Span) -> bool {
+        self.comments[self.next..].iter().any(|comment| span.start < comment.span.start && comment.span.start < span.end)
+    }
+
+    fn lexeme(&self, span: Span) -> &str {
+        &self.src[span.start..span.end]
+    }
+
+    fn error(&mut self, error: &ErrorItem) {
+        self.errors.push(SemanticError::from(error));
+    }
+
+    fn list(&mut self, items: &[Item], depth: usize) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.item(item, depth);
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        for (i, segment) in path.segments.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str("::");
+            }
+            self.out.push_str(&segment.lit);
+        }
+    }
+
+    fn tuple(&mut self, tuple: &Tuple, depth: usize) {
+        self.out.push('(');
+        self.list(&tuple.elems, depth);
+        // Kept after a lone element, where it tells a record from a group.
+        if tuple.trailing && tuple.elems.len() == 1 {
+            self.out.push(',');
+        }
+        self.close_list(')', tuple.span.end, depth);
+    }
+
+    fn type_tuple(&mut self, tuple: &TypeTuple, depth: usize) {
+        self.out.push('[');
+        self.list(&tuple.elems, depth);
+        self.close_list(']', tuple.span.end, depth);
+    }
+
+    /// The generic parameters after the name of a declaration, if any.
+    fn generics(&mut self, generics: Option<&TypeTuple>, depth: usize) {
+        if let Some(generics) = generics {
+            self.type_tuple(generics, depth);
+        }
+    }
+
+    fn func_type(&mut self, ty: &FuncType, depth: usize) {
+        self.tuple(&ty.param_tuple, depth);
+        if let Some(result_ty) = &ty.result_ty {
+            self.out.push_str(" -> ");
+            self.item(result_ty, depth);
+        }
+    }
+
+    fn annotation(&mut self, ty: Option<&Item>, depth: usize) {
+        if let Some(ty) = ty {
+            self.out.push_str(": ");
+            self.item(ty, depth);
+        }
+    }
+
+    fn block(&mut self, block: &Block, depth: usize) {
+        if block.elems.is_empty() && !self.has_comment(block.span) {
+            self.out.push_str("{}");
+            self.last = block.span.end;
+            return;
+        }
+        self.open(block.span.start);
+        for (i, elem) in block.elems.iter().enumerate() {
+            self.line(elem.span().start, depth + 1);
+            self.item(elem, depth + 1);
+            self.last = elem.span().end;
+            if i + 1 < block.elems.len() || terminated(elem) {
+                self.out.push(';');
+            }
+            self.trailing();
+        }
+        self.close(block.span.end, depth);
+    }
+
+    /// `record { .. }` and `union { .. }`, on one line if the source has them on one line.
+    fn fields(&mut self, keyword: &str, fields: &[IdentItem], span: Span, depth: usize) {
+        self.out.push_str(keyword);
+        self.out.push(' ');
+        if fields.is_empty() && !self.has_comment(span) {
+            self.out.push_str("{}");
+            return;
+        }
+        if !self.lexeme(span).contains('\n') && !self.has_comment(span) {
+            self.out.push_str("{ ");
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+                self.ident_item(field, depth);
+            }
+            self.out.push_str(" }");
+            return;
+        }
+        let brace = span.start + self.lexeme(span).find('{').unwrap();
+        self.open(brace);
+        for (i, field) in fields.iter().enumerate() {
+            self.line(field.span.start, depth + 1);
+            self.ident_item(field, depth + 1);
+            self.last = field.span.end;
+            if i + 1 < fields.len() {
+                self.out.push(',');
+            }
+            self.trailing();
+        }
+        self.close(span.end, depth);
+    }
+
+    fn ident_item(&mut self, v: &IdentItem, depth: usize) {
+        self.out.push_str(&v.ident.lit);
+        self.out.push_str(": ");
+        self.item(&v.item, depth);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> String {
+        match pattern {
+            Pattern::Wildcard(_) => "_".to_string(),
+            Pattern::Nat(v) => self.lexeme(v.span).to_string(),
+            Pattern::Bind(v) => v.lit.clone(),
+            Pattern::Variant(v) => {
+                let path = v.path.segments.iter().map(|segment| segment.lit.as_str()).collect::<Vec<_>>().join("::");
+                match &v.payload {
+                    None => path,
+                    Some(payload) => path + &self.tuple_pattern(payload),
+                }
+            }
+            Pattern::Tuple(v) => self.tuple_pattern(v),
+            Pattern::Or(v) => v.alts.iter().map(|alt| self.pattern(alt)).collect::<Vec<_>>().join(" | "),
+            Pattern::Range(v) => {
+                let op = if v.inclusive { "..=" } else { ".." };
+                format!("{}{}{}", self.lexeme(v.lo.span), op, self.lexeme(v.hi.span))
+            }
+            Pattern::Error(v) => {
+                self.error(v);
+                String::new()
+            }
+        }
+    }
+
+    fn tuple_pattern(&mut self, tuple: &TuplePattern) -> String {
+        let fields = tuple.fields.iter().map(|field| {
+            let pattern = self.pattern(&field.pattern);
+            match &field.ident {
+                Some(ident) => format!("{}: {}", ident.lit, pattern),
+                None => pattern,
+            }
+        }).collect::<Vec<_>>();
+        if tuple.trailing && fields.len() == 1 {
+            format!("({},)", fields[0])
+        } else {
+            format!("({})", fields.join(", "))
+        }
+    }
+
+    fn match_expr(&mut self, v: &Match, depth: usize) {
+        self.out.push_str("match ");
+        self.item(&v.expr, depth);
+        self.out.push(' ');
+        if v.cases.is_empty() && !self.has_comment(v.span) {
+            self.out.push_str("{}");
+            return;
+        }
+        let patterns = v.cases.iter().map(|case| self.pattern(&case.pattern)).collect::<Vec<_>>();
+        let width = patterns.iter().map(|pattern| pattern.chars().count()).max().unwrap_or(0);
+        let brace = v.expr.span().end + self.src[v.expr.span().end..].find('{').unwrap();
+        self.open(brace);
+        for (i, (case, pattern)) in v.cases.iter().zip(&patterns).enumerate() {
+            self.line(case.span.start, depth + 1);
+            self.out.push_str(&format!("{:width$} => ", pattern));
+            self.item(&case.expr, depth + 1);
+            self.last = case.span.end;
+            if i + 1 < v.cases.len() {
+                self.out.push(',');
+            }
+            self.trailing();
+        }
+        self.close(v.span.end, depth);
+    }
+
+    fn docs(&mut self, docs: &[String], depth: usize) {
+        for line in docs {
+            if line.is_empty() {
+                self.out.push_str("///");
+            } else {
+                self.out.push_str("/// ");
+                self.out.push_str(line);
+            }
+            self.out.push('\n');
+            self.indent(depth);
+        }
+    }
+
+    fn item(&mut self, item: &Item, depth: usize) {
+        self.inline(item.span().start, depth);
+        match item {
+            Item::Nat(v) => {
+                let lit = self.lexeme(v.span).to_string();
+                self.out.push_str(&lit);
+            }
+            Item::Ident(v) => self.out.push_str(&v.lit),
+            Item::Path(v) => self.path(v),
+            Item::Tuple(v) => self.tuple(v, depth),
+            Item::Block(v) => self.block(v, depth),
+            Item::Func(v) => {
+                self.out.push_str("fun ");
+                self.func_type(&v.ty, depth);
+                self.out.push(' ');
+                self.block(&v.block, depth);
+            }
+            Item::Match(v) => self.match_expr(v, depth),
+            Item::TypeTuple(v) => self.type_tuple(v, depth),
+            Item::RecordType(v) => self.fields("record", &v.fields, v.span, depth),
+            Item::UnionType(v) => self.fields("union", &v.variants, v.span, depth),
+            Item::FuncType(v) => self.func_type(v, depth),
+            Item::ApplyType(v) => {
+                self.item(&v.ty, depth);
+                self.type_tuple(&v.params, depth);
+            }
+            Item::UnaryOpExpr(v) => {
+                self.out.push_str(v.op.to_literal());
+                self.item(&v.expr, depth);
+            }
+            Item::BinaryOpExpr(v) => {
+                self.item(&v.left, depth);
+                self.operator(spelling(&v.op), v.left.span().end, depth);
+                self.item(&v.right, depth);
+            }
+            Item::ApplyExpr(v) => {
+                self.item(&v.func, depth);
+                self.tuple(&v.params, depth);
+            }
+            Item::Select(v) => {
+                self.item(&v.expr, depth);
+                self.out.push('.');
+                self.out.push_str(&v.ident.lit);
+            }
+            Item::Pipe(v) => {
+                self.item(&v.from, depth);
+                self.operator("|>", v.from.span().end, depth);
+                self.item(&v.to, depth);
+            }
+            Item::IdentItem(v) => self.ident_item(v, depth),
+            Item::LetDecl(v) => {
+                self.docs(&v.docs, depth);
+                self.out.push_str("let ");
+                self.out.push_str(&v.ident.lit);
+                self.generics(v.generics.as_ref(), depth);
+                self.annotation(v.ty.as_ref(), depth);
+                self.out.push_str(" = ");
+                self.item(&v.expr, depth);
+            }
+            Item::VarDecl(v) => {
+                self.docs(&v.docs, depth);
+                self.out.push_str("var ");
+                self.out.push_str(&v.ident.lit);
+                self.annotation(v.ty.as_ref(), depth);
+                self.out.push_str(" = ");
+                self.item(&v.expr, depth);
+            }
+            Item::TypeAliasDecl(v) => {
+                self.docs(&v.docs, depth);
+                self.out.push_str("type ");
+                self.out.push_str(&v.ident.lit);
+                self.generics(v.generics.as_ref(), depth);
+                self.out.push_str(" = ");
+                self.item(&v.ty, depth);
+            }
+            Item::FuncDecl(v) => {
+                self.docs(&v.docs, depth);
+                self.out.push_str("fun ");
+                self.out.push_str(&v.ident.lit);
+                self.generics(v.generics.as_ref(), depth);
+                self.func_type(&v.func.ty, depth);
+                self.out.push(' ');
+                self.block(&v.func.block, depth);
+            }
+            Item::ModDecl(v) => {
+                self.docs(&v.docs, depth);
+                self.out.push_str("mod ");
+                self.out.push_str(&v.ident.lit);
+            }
+            Item::ImportDecl(v) => {
+                self.out.push_str("import ");
+                self.path(&v.path);
+            }
+            Item::Assign(v) => {
+                self.item(&v.target, depth);
+                self.out.push_str(" = ");
+                self.item(&v.expr, depth);
+            }
+            Item::While(v) => {
+                self.out.push_str("while ");
+                self.item(&v.cond, depth);
+                self.out.push(' ');
+                self.block(&v.block, depth);
+            }
+            Item::Error(v) => self.error(v),
+        }
+    }
+
+    fn source_file(&mut self, file: &SourceFile) {
+        for item in &file.items {
+            self.line(item.span().start, 0);
+            self.item(item, 0);
+            self.last = item.span().end;
+            // A function declaration ends with its block and needs no `;`.
+            if !matches!(item, Item::FuncDecl(_)) {
+                self.out.push(';');
+            }
+            self.trailing();
+        }
+        self.comments_before(self.src.len(), 0);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+}
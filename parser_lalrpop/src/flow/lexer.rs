@@ -0,0 +1,264 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use crate::flow::ast::{ErrorItem, SyntaxError};
+use paracell_parser_sem::span::Span;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'input> {
+    Ident(&'input str),
+    Nat(&'input str),
+    /// `/// text`, holding the text after the slashes.
+    DocComment(&'input str),
+    /// A character no token starts with. The parser rejects it and recovers.
+    Invalid(&'input str),
+
+    Let,
+    Var,
+    Type,
+    Fun,
+    Match,
+    While,
+    Record,
+    Union,
+    And,
+    Or,
+    Mod,
+    Import,
+
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Semi,
+    Colon,
+    ColonColon,
+    Dot,
+    DotDot,
+    DotDotEq,
+    Arrow,
+    FatArrow,
+    Assign,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Tilde,
+    Bang,
+    AndAnd,
+    OrOr,
+    Bar,
+    Pipe,
+}
+
+const KEYWORDS: &[(&str, Token<'static>)] = &[
+    ("let", Token::Let),
+    ("var", Token::Var),
+    ("type", Token::Type),
+    ("fun", Token::Fun),
+    ("match", Token::Match),
+    ("while", Token::While),
+    ("record", Token::Record),
+    ("union", Token::Union),
+    ("and", Token::And),
+    ("or", Token::Or),
+    ("mod", Token::Mod),
+    ("import", Token::Import),
+];
+
+/// Longer punctuation first, so the first prefix match is the longest one.
+const PUNCTUATION: &[(&str, Token<'static>)] = &[
+    ("..=", Token::DotDotEq),
+    ("::", Token::ColonColon),
+    ("..", Token::DotDot),
+    ("->", Token::Arrow),
+    ("=>", Token::FatArrow),
+    ("==", Token::Eq),
+    ("!=", Token::Ne),
+    ("<=", Token::Le),
+    (">=", Token::Ge),
+    ("<<", Token::Shl),
+    (">>", Token::Shr),
+    ("&&", Token::AndAnd),
+    ("||", Token::OrOr),
+    ("|>", Token::Pipe),
+    ("(", Token::LParen),
+    (")", Token::RParen),
+    ("[", Token::LBracket),
+    ("]", Token::RBracket),
+    ("{", Token::LBrace),
+    ("}", Token::RBrace),
+    (",", Token::Comma),
+    (";", Token::Semi),
+    (":", Token::Colon),
+    (".", Token::Dot),
+    ("=", Token::Assign),
+    ("<", Token::Lt),
+    (">", Token::Gt),
+    ("+", Token::Plus),
+    ("-", Token::Minus),
+    ("*", Token::Star),
+    ("/", Token::Slash),
+    ("%", Token::Percent),
+    ("^", Token::Caret),
+    ("~", Token::Tilde),
+    ("!", Token::Bang),
+    ("|", Token::Bar),
+];
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(lit) | Token::Nat(lit) | Token::Invalid(lit) => write!(f, "{}", lit),
+            Token::DocComment(text) => write!(f, "///{}", text),
+            token => {
+                let (lit, _) = KEYWORDS.iter().chain(PUNCTUATION)
+                    .find(|(_, t)| t == token)
+                    .expect("every fixed token is listed");
+                write!(f, "{}", lit)
+            }
+        }
+    }
+}
+
+pub type Spanned<'input> = Result<(usize, Token<'input>, usize), ErrorItem>;
+
+/// A `//` or `/* */` comment the parser never sees, kept for the formatter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Comment<'input> {
+    /// The whole comment as written, slashes included.
+    pub text: &'input str,
+    pub span: Span,
+}
+
+/// Tokenizer for flow sources. Skips whitespace, `//` line comments and nested `/* */` block comments.
+pub struct Lexer<'input> {
+    src: &'input str,
+    pos: usize,
+    comments: Vec<Comment<'input>>,
+}
+
+impl<'input> Lexer<'input> {
+    pub fn new(src: &'input str) -> Lexer<'input> {
+        Lexer { src, pos: 0, comments: Vec::new() }
+    }
+
+    /// The comments skipped so far, in source order.
+    pub fn comments(&self) -> &[Comment<'input>] {
+        &self.comments
+    }
+
+    fn push_comment(&mut self, start: usize) {
+        let text = self.src[start..self.pos].trim_end_matches('\r');
+        self.comments.push(Comment { text, span: Span::new(start, start + text.len()) });
+    }
+
+    fn rest(&self) -> &'input str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_while(&mut self, f: impl Fn(char) -> bool) {
+        let rest = self.rest();
+        self.pos += rest.find(|c| !f(c)).unwrap_or(rest.len());
+    }
+
+    /// Skips a block comment starting at the cursor, honoring nesting.
+    fn skip_block_comment(&mut self) -> Result<(), ErrorItem> {
+        let start = self.pos;
+        let mut depth = 0;
+        while self.pos < self.src.len() {
+            let rest = self.rest();
+            if rest.starts_with("/*") {
+                depth += 1;
+                self.pos += 2;
+            } else if rest.starts_with("*/") {
+                depth -= 1;
+                self.pos += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else {
+                self.pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        Err(ErrorItem {
+            error: SyntaxError::UnterminatedComment,
+            span: Span::new(start, start + 2),
+        })
+    }
+}
+
+impl<'input> Iterator for Lexer<'input> {
+    type Item = Spanned<'input>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.skip_while(char::is_whitespace);
+            let rest = self.rest();
+            let start = self.pos;
+
+            if rest.starts_with("///") && !rest.starts_with("////") {
+                self.skip_while(|c| c != '\n');
+                let text = &self.src[start + 3..self.pos];
+                return Some(Ok((start, Token::DocComment(text.trim_end_matches('\r')), self.pos)));
+            }
+            if rest.starts_with("//") {
+                self.skip_while(|c| c != '\n');
+                self.push_comment(start);
+                continue;
+            }
+            if rest.starts_with("/*") {
+                if let Err(e) = self.skip_block_comment() {
+                    self.pos = self.src.len();
+                    return Some(Err(e));
+                }
+                self.push_comment(start);
+                continue;
+            }
+
+            let c = rest.chars().next()?;
+
+            if c.is_ascii_alphabetic() || c == '_' {
+                self.skip_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                let lit = &self.src[start..self.pos];
+                let token = KEYWORDS.iter()
+                    .find(|(keyword, _)| *keyword == lit)
+                    .map_or(Token::Ident(lit), |(_, token)| *token);
+                return Some(Ok((start, token, self.pos)));
+            }
+
+            if c.is_ascii_digit() {
+                self.skip_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                // The width after a `'`, as in `0xFF'8`.
+                if self.rest().starts_with('\'') {
+                    self.pos += 1;
+                    self.skip_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                }
+                return Some(Ok((start, Token::Nat(&self.src[start..self.pos]), self.pos)));
+            }
+
+            if let Some((lit, token)) = PUNCTUATION.iter().find(|(lit, _)| rest.starts_with(lit)) {
+                self.pos += lit.len();
+                return Some(Ok((start, *token, self.pos)));
+            }
+
+            self.pos += c.len_utf8();
+            return Some(Ok((start, Token::Invalid(&self.src[start..self.pos]), self.pos)));
+        }
+    }
+}
@@ -3,6 +3,39 @@
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
 pub mod ast;
+pub mod fmt;
+pub mod lexer;
+pub mod print;
 pub mod sem;
 
 lalrpop_util::lalrpop_mod!(pub grammar, "/flow/grammar.rs");
+
+use crate::flow::ast::ErrorItem;
+use crate::flow::lexer::Lexer;
+use crate::flow::sem::{SemanticError, SemanticResult, ToSemantic};
+use paracell_parser_sem::diag::Source;
+use paracell_parser_sem::module::{LoadErrors, Program};
+use std::fs;
+use std::path::Path;
+
+/// Parses and lowers a source file, reporting every syntax and semantic error found.
+pub fn parse(src: &str) -> SemanticResult<paracell_parser_sem::sem::SourceFile> {
+    match grammar::SourceFileParser::new().parse(Lexer::new(src)) {
+        Ok(file) => file.to_semantic(),
+        Err(e) => Err(SemanticError::from(&ErrorItem::from_parse_error(e)).into()),
+    }
+}
+
+/// Loads the program whose root module is `root`, finding module `alu::adder` at `alu/adder.flow` under `dir`.
+pub fn load(root: Source, dir: &Path) -> Result<Program, LoadErrors> {
+    paracell_parser_sem::module::load(
+        root,
+        |path| {
+            let mut file = dir.join(path.segments.join("/"));
+            file.set_extension("flow");
+            let text = fs::read_to_string(&file).ok()?;
+            Some(Source::new(file.display().to_string(), text))
+        },
+        |source| parse(&source.text).map_err(|errors| errors.to_diagnostics()),
+    )
+}
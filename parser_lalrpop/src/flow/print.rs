@@ -0,0 +1,480 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Prints `sem` trees as canonical flow source, which parses back to an equal tree.
+
+use paracell_parser_sem::sem::*;
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+
+const INDENT: &str = "    ";
+
+/// Displays a `sem` node as flow source.
+pub struct Print<'a, T>(pub &'a T);
+
+/// Binding power of a binary operator literal, following the precedence levels in the grammar.
+pub fn binary_level(lit: &str) -> Option<u8> {
+    Some(match lit {
+        "*" | "/" | "%" => 3,
+        "+" | "-" => 4,
+        "<<" | ">>" => 5,
+        "&" => 6,
+        "^" => 7,
+        "|" => 8,
+        "==" | "!=" | "<" | "<=" | ">" | ">=" => 9,
+        "&&" => 10,
+        "||" => 11,
+        _ => return None,
+    })
+}
+
+/// An application the frontends lowered from an operator.
+pub enum Operator<'a> {
+    Unary(&'a str, &'a Expr),
+    Binary(&'a str, &'a Expr, &'a Expr),
+}
+
+impl Operator<'_> {
+    pub fn from_apply(apply: &ApplyExpr) -> Option<Operator<'_>> {
+        let func = match &apply.func {
+            Expr::Ident(func) => func.lit.as_str(),
+            _ => return None,
+        };
+        match apply.params.fields.as_slice() {
+            [a] if a.ident == "0" && matches!(func, "~" | "!") => Some(Operator::Unary(func, &a.expr)),
+            [a, b] if a.ident == "0" && b.ident == "1" && binary_level(func).is_some() => {
+                Some(Operator::Binary(func, &a.expr, &b.expr))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Operators spelled differently from their literal.
+fn spelling(lit: &str) -> &str {
+    match lit {
+        "&" => "and",
+        "|" => "or",
+        lit => lit,
+    }
+}
+
+fn level(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Apply(v) => match Operator::from_apply(v) {
+            Some(Operator::Unary(..)) => 2,
+            Some(Operator::Binary(lit, ..)) => binary_level(lit).unwrap(),
+            None => 1,
+        },
+        Expr::Select(_) => 1,
+        Expr::Pipe(_) => 12,
+        _ => 0,
+    }
+}
+
+/// Whether the fields are named `0`, `1`, .. in order, as written without names.
+fn positional<'a>(idents: impl Iterator<Item = &'a str>) -> bool {
+    idents.enumerate().all(|(i, ident)| ident == i.to_string())
+}
+
+fn newline(f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+    f.write_char('\n')?;
+    for _ in 0..depth {
+        f.write_str(INDENT)?;
+    }
+    Ok(())
+}
+
+fn list<T>(f: &mut Formatter<'_>, items: &[T], mut each: impl FnMut(&mut Formatter<'_>, &T) -> fmt::Result) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        each(f, item)?;
+    }
+    Ok(())
+}
+
+fn write_type(f: &mut Formatter<'_>, ty: &Type) -> fmt::Result {
+    match ty {
+        Type::Ident(v) => f.write_str(&v.lit),
+        Type::Apply(v) => {
+            write!(f, "{}[", v.ty.lit)?;
+            list(f, &v.params, |f, param| match param {
+                TypeParam::Nat(v) => write!(f, "{}", v),
+                TypeParam::Type(v) => write_type(f, v),
+            })?;
+            f.write_char(']')
+        }
+        Type::Record(v) if positional(v.fields.iter().map(|field| field.ident.as_str())) => {
+            f.write_char('(')?;
+            list(f, &v.fields, |f, field| write_type(f, &field.ty))?;
+            f.write_char(')')
+        }
+        Type::Record(v) => {
+            f.write_str("record { ")?;
+            list(f, &v.fields, |f, field| {
+                write!(f, "{}: ", field.ident)?;
+                write_type(f, &field.ty)
+            })?;
+            f.write_str(" }")
+        }
+        Type::Union(v) => {
+            f.write_str("union { ")?;
+            list(f, &v.variants, |f, variant| {
+                write!(f, "{}: ", variant.ident)?;
+                write_type(f, &variant.ty)
+            })?;
+            f.write_str(" }")
+        }
+        // Written in full, since `(a)` alone is a tuple.
+        Type::Func(v) => {
+            f.write_char('(')?;
+            list(f, &v.params.fields, |f, field| {
+                write!(f, "{}: ", field.ident)?;
+                write_type(f, &field.ty)
+            })?;
+            f.write_str(") -> ")?;
+            write_type(f, &v.result)
+        }
+        Type::Infer(_) => f.write_char('_'),
+    }
+}
+
+/// `(a: Nat, b: Nat) -> Nat` of a function literal, leaving out the types left to inference.
+fn write_func_type(f: &mut Formatter<'_>, ty: &FuncType) -> fmt::Result {
+    f.write_char('(')?;
+    list(f, &ty.params.fields, |f, field| {
+        f.write_str(&field.ident)?;
+        if let Type::Infer(_) = field.ty {
+            return Ok(());
+        }
+        f.write_str(": ")?;
+        write_type(f, &field.ty)
+    })?;
+    f.write_char(')')?;
+    if let Type::Infer(_) = ty.result {
+        return Ok(());
+    }
+    f.write_str(" -> ")?;
+    write_type(f, &ty.result)
+}
+
+/// `: T` of an annotated `let` or `var`.
+fn write_annotation(f: &mut Formatter<'_>, ty: Option<&Type>) -> fmt::Result {
+    match ty {
+        Some(ty) => {
+            f.write_str(": ")?;
+            write_type(f, ty)
+        }
+        None => Ok(()),
+    }
+}
+
+fn write_pattern(f: &mut Formatter<'_>, pattern: &Pattern) -> fmt::Result {
+    match pattern {
+        Pattern::Wildcard(_) => f.write_char('_'),
+        Pattern::Nat(v) => write!(f, "{}", v),
+        Pattern::Bind(v) => f.write_str(&v.lit),
+        Pattern::Variant(v) => {
+            f.write_str(&v.path.segments.join("::"))?;
+            match &v.payload {
+                None => Ok(()),
+                Some(Pattern::Record(record)) => write_record_pattern(f, record),
+                Some(payload) => {
+                    f.write_char('(')?;
+                    write_pattern(f, payload)?;
+                    f.write_char(')')
+                }
+            }
+        }
+        Pattern::Record(v) => write_record_pattern(f, v),
+        Pattern::Or(v) => {
+            for (i, alt) in v.alts.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" | ")?;
+                }
+                match alt {
+                    Pattern::Or(_) => {
+                        f.write_char('(')?;
+                        write_pattern(f, alt)?;
+                        f.write_char(')')?;
+                    }
+                    alt => write_pattern(f, alt)?,
+                }
+            }
+            Ok(())
+        }
+        Pattern::Range(v) => write!(f, "{}{}{}", v.lo, if v.inclusive { "..=" } else { ".." }, v.hi),
+    }
+}
+
+fn write_record_pattern(f: &mut Formatter<'_>, record: &RecordPattern) -> fmt::Result {
+    let named = !positional(record.fields.iter().map(|field| field.ident.as_str()));
+    f.write_char('(')?;
+    list(f, &record.fields, |f, field| {
+        if named {
+            write!(f, "{}: ", field.ident)?;
+        }
+        write_pattern(f, &field.pattern)
+    })?;
+    // `(x)` would only group.
+    if !named && record.fields.len() == 1 {
+        f.write_char(',')?;
+    }
+    f.write_char(')')
+}
+
+/// Writes `expr`, parenthesized if it binds looser than `max` allows.
+fn write_operand(f: &mut Formatter<'_>, expr: &Expr, max: u8, depth: usize) -> fmt::Result {
+    if level(expr) > max {
+        f.write_char('(')?;
+        write_expr(f, expr, depth)?;
+        f.write_char(')')
+    } else {
+        write_expr(f, expr, depth)
+    }
+}
+
+/// Writes the fields of a record expression, with a `,` after a lone positional field if `trailing`,
+/// as `(x)` only groups where it is not the arguments of an application.
+fn write_record_expr(f: &mut Formatter<'_>, record: &RecordExpr, trailing: bool, depth: usize) -> fmt::Result {
+    let named = !positional(record.fields.iter().map(|field| field.ident.as_str()));
+    f.write_char('(')?;
+    list(f, &record.fields, |f, field| {
+        if named {
+            write!(f, "{}: ", field.ident)?;
+        }
+        write_expr(f, &field.expr, depth)
+    })?;
+    if trailing && !named && record.fields.len() == 1 {
+        f.write_char(',')?;
+    }
+    f.write_char(')')
+}
+
+fn write_block(f: &mut Formatter<'_>, block: &Block, depth: usize) -> fmt::Result {
+    if block.stmts.is_empty() {
+        return f.write_str("{}");
+    }
+    f.write_char('{')?;
+    for (i, stmt) in block.stmts.iter().enumerate() {
+        newline(f, depth + 1)?;
+        write_stmt(f, stmt, depth + 1)?;
+        if i + 1 < block.stmts.len() {
+            f.write_char(';')?;
+        }
+    }
+    newline(f, depth)?;
+    f.write_char('}')
+}
+
+fn write_expr(f: &mut Formatter<'_>, expr: &Expr, depth: usize) -> fmt::Result {
+    match expr {
+        Expr::Nat(v) => write!(f, "{}", v),
+        Expr::Ident(v) => f.write_str(&v.lit),
+        Expr::Path(v) => f.write_str(&v.segments.join("::")),
+        Expr::Block(v) => write_block(f, v, depth),
+        Expr::Func(v) => {
+            f.write_str("fun ")?;
+            write_func_type(f, &v.ty)?;
+            f.write_char(' ')?;
+            write_block(f, &v.block, depth)
+        }
+        Expr::Record(v) => write_record_expr(f, v, true, depth),
+        Expr::Apply(v) => match Operator::from_apply(v) {
+            Some(Operator::Unary(lit, operand)) => {
+                f.write_str(lit)?;
+                write_operand(f, operand, 2, depth)
+            }
+            Some(Operator::Binary(lit, left, right)) => {
+                let level = binary_level(lit).unwrap();
+                // Comparisons do not associate, so neither side may be another comparison.
+                let left_max = if level == 9 { level - 1 } else { level };
+                write_operand(f, left, left_max, depth)?;
+                write!(f, " {} ", spelling(lit))?;
+                write_operand(f, right, level - 1, depth)
+            }
+            None => {
+                write_operand(f, &v.func, 1, depth)?;
+                write_record_expr(f, &v.params, false, depth)
+            }
+        },
+        Expr::Match(v) => {
+            f.write_str("match ")?;
+            write_expr(f, &v.expr, depth)?;
+            if v.cases.is_empty() {
+                return f.write_str(" {}");
+            }
+            f.write_str(" {")?;
+            for (i, case) in v.cases.iter().enumerate() {
+                newline(f, depth + 1)?;
+                write_pattern(f, &case.pattern)?;
+                f.write_str(" => ")?;
+                write_expr(f, &case.expr, depth + 1)?;
+                if i + 1 < v.cases.len() {
+                    f.write_char(',')?;
+                }
+            }
+            newline(f, depth)?;
+            f.write_char('}')
+        }
+        Expr::Select(v) => {
+            write_operand(f, &v.expr, 1, depth)?;
+            write!(f, ".{}", v.ident)
+        }
+        Expr::Pipe(v) => {
+            write_operand(f, &v.from, 12, depth)?;
+            f.write_str(" |> ")?;
+            write_operand(f, &v.to, 11, depth)
+        }
+    }
+}
+
+fn write_doc(f: &mut Formatter<'_>, doc: Option<&str>, depth: usize) -> fmt::Result {
+    for line in doc.unwrap_or_default().lines() {
+        if line.is_empty() {
+            f.write_str("///")?;
+        } else {
+            write!(f, "/// {}", line)?;
+        }
+        newline(f, depth)?;
+    }
+    Ok(())
+}
+
+fn write_generics(f: &mut Formatter<'_>, generics: &[GenericParam]) -> fmt::Result {
+    if generics.is_empty() {
+        return Ok(());
+    }
+    f.write_char('[')?;
+    for (i, param) in generics.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        match param {
+            GenericParam::Type(v) => f.write_str(&v.lit)?,
+            GenericParam::Width(v) => write!(f, "{}: Nat", v.lit)?,
+        }
+    }
+    f.write_char(']')
+}
+
+/// The declaration and the function it binds, if it prints as `fun`, which needs no `;` after
+/// it. Functions bound by `let` print so unless annotated, as `fun` has no place for that.
+fn func_decl(decl: &Decl) -> Option<(&LetDecl, &Func)> {
+    match decl {
+        Decl::Let(v @ LetDecl { ty: None, expr: Expr::Func(func), .. }) => Some((v, func)),
+        _ => None,
+    }
+}
+
+fn write_decl(f: &mut Formatter<'_>, decl: &Decl, depth: usize) -> fmt::Result {
+    write_doc(f, decl.doc(), depth)?;
+    if let Some((v, func)) = func_decl(decl) {
+        write!(f, "fun {}", v.ident)?;
+        write_generics(f, &v.generics)?;
+        write_func_type(f, &func.ty)?;
+        f.write_char(' ')?;
+        return write_block(f, &func.block, depth);
+    }
+    match decl {
+        Decl::Let(v) => {
+            write!(f, "let {}", v.ident)?;
+            write_generics(f, &v.generics)?;
+            write_annotation(f, v.ty.as_ref())?;
+            f.write_str(" = ")?;
+            write_expr(f, &v.expr, depth)
+        }
+        Decl::Var(v) => {
+            write!(f, "var {}", v.ident)?;
+            write_annotation(f, v.ty.as_ref())?;
+            f.write_str(" = ")?;
+            write_expr(f, &v.expr, depth)
+        }
+        Decl::TypeAlias(v) => {
+            write!(f, "type {}", v.ident)?;
+            write_generics(f, &v.generics)?;
+            f.write_str(" = ")?;
+            write_type(f, &v.ty)
+        }
+    }
+}
+
+fn write_stmt(f: &mut Formatter<'_>, stmt: &Stmt, depth: usize) -> fmt::Result {
+    match stmt {
+        Stmt::Decl(v) => write_decl(f, v, depth),
+        Stmt::Expr(v) => write_expr(f, v, depth),
+        Stmt::Assign(v) => {
+            write!(f, "{} = ", v.target.lit)?;
+            write_expr(f, &v.expr, depth)
+        }
+        Stmt::While(v) => {
+            f.write_str("while ")?;
+            write_expr(f, &v.cond, depth)?;
+            f.write_char(' ')?;
+            write_block(f, &v.block, depth)
+        }
+    }
+}
+
+impl Display for Print<'_, SourceFile> {
+    /// `mod` and `import` declarations come first, one per line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for v in &self.0.mods {
+            write_doc(f, v.doc.as_deref(), 0)?;
+            writeln!(f, "mod {};", v.ident)?;
+        }
+        for v in &self.0.imports {
+            writeln!(f, "import {};", v.path)?;
+        }
+        let header = !self.0.mods.is_empty() || !self.0.imports.is_empty();
+        if header && !self.0.decls.is_empty() {
+            f.write_char('\n')?;
+        }
+        for (i, decl) in self.0.decls.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n\n")?;
+            }
+            write_decl(f, decl, 0)?;
+            if func_decl(decl).is_none() {
+                f.write_char(';')?;
+            }
+        }
+        if !self.0.decls.is_empty() {
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Print<'_, Decl> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_decl(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Stmt> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_stmt(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Expr> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_expr(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Type> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.0)
+    }
+}
+
+impl Display for Print<'_, Pattern> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_pattern(f, self.0)
+    }
+}
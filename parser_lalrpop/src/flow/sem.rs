@@ -3,25 +3,218 @@
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
 use crate::flow::ast;
-use crate::flow::ast::Item;
-use crate::flow::sem::SemanticError::UnexpectedNode;
+use crate::flow::ast::{ErrorItem, Item, SyntaxError};
+use paracell_parser_sem::diag::{Diagnostic, Source, render_all};
 use paracell_parser_sem::sem;
+use paracell_parser_sem::span::Span;
+use std::fmt;
 use thiserror::Error;
 
+/// The syntactic role a node was expected to play.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Type,
+    Expr,
+    Decl,
+    Stmt,
+    Field,
+    Place,
+    Pattern,
+    Generic,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Category::Type => write!(f, "type"),
+            Category::Expr => write!(f, "expression"),
+            Category::Decl => write!(f, "declaration"),
+            Category::Stmt => write!(f, "statement"),
+            Category::Field => write!(f, "named field"),
+            Category::Place => write!(f, "assignment target"),
+            Category::Pattern => write!(f, "pattern"),
+            Category::Generic => write!(f, "generic parameter"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Error)]
-pub enum SemanticError<'a> {
-    #[error("unexpected node")]
-    UnexpectedNode { have: &'a Item }
+pub enum SemanticError {
+    #[error("expected {expected}, found {found}")]
+    UnexpectedNode { expected: Category, found: &'static str, span: Span },
+    #[error("{error}")]
+    Syntax { error: SyntaxError, span: Span },
+    #[error("cannot mix named and positional fields")]
+    MixedFields { span: Span },
+    #[error("malformed `{form}`")]
+    Malformed { form: &'static str, usage: &'static str, span: Span },
+}
+
+impl SemanticError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticError::UnexpectedNode { .. } => "E0001",
+            SemanticError::Syntax { error, .. } => error.code(),
+            SemanticError::MixedFields { .. } => "E0008",
+            SemanticError::Malformed { .. } => "E0010",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            SemanticError::UnexpectedNode { span, .. }
+            | SemanticError::Syntax { span, .. }
+            | SemanticError::MixedFields { span }
+            | SemanticError::Malformed { span, .. } => *span,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            SemanticError::UnexpectedNode { expected, found, span } => {
+                let diag = Diagnostic::error(self.code(), self.to_string())
+                    .with_primary(*span, format!("this {found} is not a {expected}"));
+                match expected {
+                    Category::Type => diag.with_help("a type is a name, a tuple of types, `record { .. }`, `union { .. }` or `(a: T) -> T`"),
+                    Category::Expr => diag.with_help("types and declarations cannot be used as values"),
+                    Category::Decl => diag.with_help("top-level items must be `let`, `var`, `type`, `mod` or `import` declarations"),
+                    Category::Stmt => diag.with_help("a block holds declarations and expressions separated by `;`"),
+                    Category::Field => diag.with_help("write each parameter as `name: Type`"),
+                    Category::Place => diag.with_help("only a name bound by `var` can be assigned"),
+                    Category::Pattern => diag.with_help("a pattern is `_`, a name, a number, a variant path or a record of patterns"),
+                    Category::Generic => diag.with_help("a generic parameter is a type `T`, or a width `N: Nat`"),
+                }
+            }
+            SemanticError::Syntax { error, span } => {
+                let diag = Diagnostic::error(self.code(), self.to_string());
+                match error {
+                    SyntaxError::InvalidToken => diag.with_primary(*span, "not a valid token"),
+                    SyntaxError::UnrecognizedEof { expected } | SyntaxError::UnrecognizedToken { expected, .. } => {
+                        let diag = diag.with_primary(*span, "unexpected here");
+                        if expected.is_empty() {
+                            diag
+                        } else {
+                            diag.with_help(format!("expected one of {}", expected.join(", ")))
+                        }
+                    }
+                    SyntaxError::ExtraToken { .. } => diag.with_primary(*span, "nothing may follow here"),
+                    SyntaxError::InvalidNat { .. } => diag
+                        .with_primary(*span, "not a number")
+                        .with_help("write digits after an optional `0x`, `0o` or `0b` prefix, without leading zeros, then an optional width as in `0xFF'8`"),
+                    SyntaxError::UnterminatedComment => diag
+                        .with_primary(*span, "this comment is never closed")
+                        .with_help("block comments nest, so every `/*` needs its own `*/`"),
+                }
+            }
+            SemanticError::MixedFields { span } => Diagnostic::error(self.code(), self.to_string())
+                .with_primary(*span, "this field differs from the first one")
+                .with_help("name every field as `name: pattern` or none of them"),
+            SemanticError::Malformed { usage, span, .. } => Diagnostic::error(self.code(), self.to_string())
+                .with_primary(*span, "not in the expected shape")
+                .with_help(format!("write it as `{usage}`")),
+        }
+    }
+}
+
+/// Every error found while lowering a tree, in source order.
+#[derive(Clone, Debug, Default, Error)]
+pub struct SemanticErrors {
+    pub errors: Vec<SemanticError>,
+}
+
+impl fmt::Display for SemanticErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<SemanticError> for SemanticErrors {
+    fn from(error: SemanticError) -> Self {
+        SemanticErrors { errors: vec![error] }
+    }
+}
+
+impl From<&ErrorItem> for SemanticError {
+    fn from(item: &ErrorItem) -> Self {
+        SemanticError::Syntax { error: item.error.clone(), span: item.span }
+    }
+}
+
+impl SemanticErrors {
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors.iter().map(SemanticError::to_diagnostic).collect()
+    }
+
+    pub fn render(&self, source: &Source) -> String {
+        render_all(&self.to_diagnostics(), source)
+    }
+}
+
+pub type SemanticResult<T> = Result<T, SemanticErrors>;
+
+/// Collects every element, or every error of every failed element.
+pub(crate) fn collect<T>(iter: impl Iterator<Item = SemanticResult<T>>) -> SemanticResult<Vec<T>> {
+    let mut vals = Vec::new();
+    let mut errors = Vec::new();
+    for result in iter {
+        match result {
+            Ok(v) => vals.push(v),
+            Err(e) => errors.extend(e.errors),
+        }
+    }
+    if errors.is_empty() { Ok(vals) } else { Err(SemanticErrors { errors }) }
+}
+
+/// Combines two independent results, keeping the errors of both.
+pub(crate) fn join<A, B>(a: SemanticResult<A>, b: SemanticResult<B>) -> SemanticResult<(A, B)> {
+    match (a, b) {
+        (Ok(a), Ok(b)) => Ok((a, b)),
+        (Err(e), Ok(_)) | (Ok(_), Err(e)) => Err(e),
+        (Err(mut a), Err(b)) => {
+            a.errors.extend(b.errors);
+            Err(a)
+        }
+    }
+}
+
+fn unexpected<T>(item: &Item, expected: Category) -> SemanticResult<T> {
+    Err(SemanticError::UnexpectedNode { expected, found: item.kind(), span: item.span() }.into())
+}
+
+/// `[T, N: Nat]` after the name of a declaration, none if it is not generic.
+fn expect_semantic_generics(generics: Option<&ast::TypeTuple>) -> SemanticResult<Vec<sem::GenericParam>> {
+    let Some(generics) = generics else { return Ok(vec![]) };
+    collect(generics.elems.iter().map(|param| match param {
+        Item::Ident(v) => Ok(sem::GenericParam::Type(v.to_semantic()?)),
+        Item::IdentItem(v) if matches!(&v.item, Item::Ident(ty) if ty.lit == "Nat") => {
+            Ok(sem::GenericParam::Width(v.ident.to_semantic()?))
+        }
+        _ => unexpected(param, Category::Generic),
+    }))
+}
+
+pub(crate) fn join_docs(docs: &[String]) -> Option<String> {
+    if docs.is_empty() {
+        None
+    } else {
+        Some(docs.join("\n"))
+    }
 }
 
 pub trait ToSemantic<T> {
-    fn to_semantic(&self) -> Result<T, SemanticError>;
+    fn to_semantic(&self) -> SemanticResult<T>;
 }
 
 macro_rules! def_semantic {
     ($self: ident : $ast: ty => $sem: ty $body: block) => {
         impl ToSemantic<$sem> for $ast {
-            fn to_semantic($self: &Self) -> Result<$sem, SemanticError> {
+            fn to_semantic($self: &Self) -> SemanticResult<$sem> {
                 Ok($body)
             }
         }
@@ -30,36 +223,137 @@ macro_rules! def_semantic {
 
 def_semantic! { self: ast::Nat => sem::Nat {
     sem::Nat {
-        val: self.val,
+        val: self.val.clone(),
+        width: self.width,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::Ident => sem::Ident {
+    sem::Ident {
+        lit: self.lit.clone(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::Path => sem::Path {
+    sem::Path {
+        segments: self.segments.iter().map(|ident| ident.lit.clone()).collect(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::TuplePattern => sem::RecordPattern {
+    let named = self.fields.first().is_some_and(|field| field.ident.is_some());
+    sem::RecordPattern {
+        fields: collect(self.fields.iter().enumerate().map(|(i, field)| {
+            if field.ident.is_some() != named {
+                return Err(SemanticError::MixedFields { span: field.span }.into());
+            }
+            Ok(sem::FieldPattern {
+                ident: match &field.ident {
+                    Some(ident) => ident.lit.clone(),
+                    None => i.to_string(),
+                },
+                pattern: field.pattern.to_semantic()?,
+                span: field.span,
+            })
+        }))?,
+        span: self.span,
+    }
+}}
+
+impl ToSemantic<sem::Pattern> for ast::TuplePattern {
+    /// A single positional field only groups, as in expressions, unless followed by `,`.
+    fn to_semantic(&self) -> SemanticResult<sem::Pattern> {
+        match self.fields.as_slice() {
+            [field] if field.ident.is_none() && !self.trailing => field.pattern.to_semantic(),
+            _ => Ok(sem::Pattern::Record(Box::from(ToSemantic::<sem::RecordPattern>::to_semantic(self)?))),
+        }
+    }
+}
+
+def_semantic! { self: ast::Pattern => sem::Pattern {
+    match self {
+        ast::Pattern::Wildcard(v) => sem::Pattern::Wildcard(sem::WildcardPattern { span: v.span }),
+        ast::Pattern::Nat(v) => sem::Pattern::Nat(v.to_semantic()?),
+        ast::Pattern::Bind(v) => sem::Pattern::Bind(v.to_semantic()?),
+        ast::Pattern::Variant(v) => sem::Pattern::Variant(Box::from(sem::VariantPattern {
+            path: v.path.to_semantic()?,
+            payload: match &v.payload {
+                Some(payload) => Some(payload.to_semantic()?),
+                None => None,
+            },
+            span: v.span,
+        })),
+        ast::Pattern::Tuple(v) => v.to_semantic()?,
+        ast::Pattern::Or(v) => sem::Pattern::Or(Box::from(sem::OrPattern {
+            alts: collect(v.alts.iter().map(ast::Pattern::to_semantic))?,
+            span: v.span,
+        })),
+        ast::Pattern::Range(v) => sem::Pattern::Range(Box::from(sem::RangePattern {
+            lo: v.lo.to_semantic()?,
+            hi: v.hi.to_semantic()?,
+            inclusive: v.inclusive,
+            span: v.span,
+        })),
+        ast::Pattern::Error(v) => return Err(SemanticError::from(v.as_ref()).into()),
     }
 }}
 
 def_semantic! { self: ast::RecordType => sem::RecordType {
     sem::RecordType {
-        fields: self.fields.iter().map(|field| {
+        fields: collect(self.fields.iter().map(|field| {
             Ok(sem::Field {
                 ident: field.ident.lit.clone(),
                 ty: field.item.expect_semantic_type()?,
+                span: field.span,
             })
-        }).collect::<Result<Vec<_>, _>>()?,
+        }))?,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::UnionType => sem::UnionType {
     sem::UnionType {
-        variants: self.variants.iter().map(|variant| {
+        variants: collect(self.variants.iter().map(|variant| {
             Ok(sem::Variant {
                 ident: variant.ident.lit.clone(),
                 ty: variant.item.expect_semantic_type()?,
+                span: variant.span,
             })
-        }).collect::<Result<Vec<_>, _>>()?,
+        }))?,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::FuncType => sem::FuncType {
+    let result = match &self.result_ty {
+        Some(ty) => ty.expect_semantic_type(),
+        None => Ok(sem::Type::Infer(sem::InferType { span: Span::new(self.span.end, self.span.end) })),
+    };
+    let (params, result) = join(self.param_tuple.expect_semantic_func_tuple(), result)?;
     sem::FuncType {
-        params: self.param_tuple.expect_semantic_func_tuple()?,
-        result: self.result_ty.expect_semantic_type()?,
+        params,
+        result,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::ApplyType => sem::ApplyType {
+    let ty = match &self.ty {
+        Item::Ident(v) => v.to_semantic(),
+        ty => unexpected(ty, Category::Type),
+    };
+    let params = collect(self.params.elems.iter().map(|param| match param {
+        Item::Nat(v) => Ok(sem::TypeParam::Nat(v.to_semantic()?)),
+        _ => Ok(sem::TypeParam::Type(param.expect_semantic_type()?)),
+    }));
+    let (ty, params) = join(ty, params)?;
+    sem::ApplyType {
+        ty,
+        params,
+        span: self.span,
     }
 }}
 
@@ -67,166 +361,289 @@ def_semantic! { self: ast::Select => sem::Select {
     sem::Select {
         expr: self.expr.expect_semantic_expr()?,
         ident: self.ident.lit.clone(),
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::Pipe => sem::Pipe {
+    let (from, to) = join(self.from.expect_semantic_expr(), self.to.expect_semantic_expr())?;
     sem::Pipe {
-        from: self.from.expect_semantic_expr()?,
-        to: self.to.expect_semantic_expr()?,
+        from,
+        to,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::Block => sem::Block {
     sem::Block {
-        stmts: self.elems.iter().map(Item::expect_semantic_stmt).collect::<Result<Vec<_>, _>>()?,
+        stmts: collect(self.elems.iter().map(Item::expect_semantic_stmt))?,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::Func => sem::Func {
+    let (ty, block) = join(self.ty.to_semantic(), self.block.to_semantic())?;
     sem::Func {
-        ty: self.ty.to_semantic()?,
-        block: self.block.to_semantic()?,
+        ty,
+        block,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::RecordExpr => sem::RecordExpr {
     sem::RecordExpr {
-        fields: self.fields.iter().map(|field| {
+        fields: collect(self.fields.iter().map(|field| {
             Ok(sem::FieldFill {
                 ident: field.ident.lit.clone(),
                 expr: field.item.expect_semantic_expr()?,
+                span: field.span,
             })
-        }).collect::<Result<Vec<_>, _>>()?,
+        }))?,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::ApplyExpr => sem::ApplyExpr {
+    let (func, params) = join(
+        self.func.expect_semantic_expr(),
+        self.params.expect_semantic_func_param_tuple(),
+    )?;
     sem::ApplyExpr {
-        func: self.func.expect_semantic_expr()?,
-        params: self.params.expect_semantic_func_param_tuple()?,
+        func,
+        params,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::UnaryOpExpr => sem::ApplyExpr {
     sem::ApplyExpr {
-        func: sem::Expr::Ident(self.op.to_literal().to_string()),
+        func: sem::Expr::Ident(sem::Ident { lit: self.op.to_literal().to_string(), span: self.span }),
         params: sem::RecordExpr {
-            fields: vec![sem::FieldFill { ident: 0.to_string(), expr: self.expr.expect_semantic_expr()? }],
+            fields: vec![sem::FieldFill { ident: 0.to_string(), expr: self.expr.expect_semantic_expr()?, span: self.expr.span() }],
+            span: self.span,
         },
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::BinaryOpExpr => sem::ApplyExpr {
+    let (left, right) = join(self.left.expect_semantic_expr(), self.right.expect_semantic_expr())?;
     sem::ApplyExpr {
-        func: sem::Expr::Ident(self.op.to_literal().to_string()),
+        func: sem::Expr::Ident(sem::Ident { lit: self.op.to_literal().to_string(), span: self.span }),
         params: sem::RecordExpr {
             fields: vec![
-                sem::FieldFill { ident: 0.to_string(), expr: self.left.expect_semantic_expr()? },
-                sem::FieldFill { ident: 1.to_string(), expr: self.right.expect_semantic_expr()? },
+                sem::FieldFill { ident: 0.to_string(), expr: left, span: self.left.span() },
+                sem::FieldFill { ident: 1.to_string(), expr: right, span: self.right.span() },
             ],
+            span: self.span,
         },
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::Case => sem::Case {
+    let (pattern, expr) = join(self.pattern.to_semantic(), self.expr.expect_semantic_expr())?;
     sem::Case {
-        pattern: self.pattern.expect_semantic_expr()?,
-        expr: self.expr.expect_semantic_expr()?,
+        pattern,
+        expr,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::Match => sem::Match {
+    let (expr, cases) = join(
+        self.expr.expect_semantic_expr(),
+        collect(self.cases.iter().map(ast::Case::to_semantic)),
+    )?;
     sem::Match {
-        cases: self.cases.iter().map(ast::Case::to_semantic).collect::<Result<Vec<_>, _>>()?,
+        expr,
+        cases,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::LetDecl => sem::LetDecl {
+    let (generics, (ty, expr)) = join(
+        expect_semantic_generics(self.generics.as_ref()),
+        join(self.ty.as_ref().map(Item::expect_semantic_type).transpose(), self.expr.expect_semantic_expr()),
+    )?;
     sem::LetDecl {
+        doc: join_docs(&self.docs),
         ident: self.ident.lit.clone(),
-        expr: self.expr.expect_semantic_expr()?,
+        generics,
+        ty,
+        expr,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::VarDecl => sem::VarDecl {
+    let (ty, expr) = join(self.ty.as_ref().map(Item::expect_semantic_type).transpose(), self.expr.expect_semantic_expr())?;
     sem::VarDecl {
+        doc: join_docs(&self.docs),
         ident: self.ident.lit.clone(),
-        expr: self.expr.expect_semantic_expr()?,
+        ty,
+        expr,
+        span: self.span,
     }
 }}
 
 def_semantic! { self: ast::TypeAliasDecl => sem::TypeAliasDecl {
+    let (generics, ty) = join(expect_semantic_generics(self.generics.as_ref()), self.ty.expect_semantic_type())?;
     sem::TypeAliasDecl {
+        doc: join_docs(&self.docs),
         ident: self.ident.lit.clone(),
-        ty: self.ty.expect_semantic_type()?,
+        generics,
+        ty,
+        span: self.span,
     }
 }}
 
-def_semantic! { self: ast::SourceFile => sem::SourceFile {
-    sem::SourceFile {
-        decls: self.items.iter().map(Item::expect_semantic_decl).collect::<Result<Vec<_>, _>>()?,
+def_semantic! { self: ast::FuncDecl => sem::LetDecl {
+    let (generics, func) = join(expect_semantic_generics(self.generics.as_ref()), self.func.to_semantic())?;
+    sem::LetDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        generics,
+        ty: None,
+        expr: sem::Expr::Func(Box::from(func)),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::Assign => sem::Assign {
+    let target = match &self.target {
+        Item::Ident(v) => v.to_semantic(),
+        _ => unexpected(&self.target, Category::Place),
+    };
+    let (target, expr) = join(target, self.expr.expect_semantic_expr())?;
+    sem::Assign {
+        target,
+        expr,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::While => sem::While {
+    let (cond, block) = join(self.cond.expect_semantic_expr(), self.block.to_semantic())?;
+    sem::While {
+        cond,
+        block,
+        span: self.span,
     }
 }}
 
+def_semantic! { self: ast::ModDecl => sem::ModDecl {
+    sem::ModDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::ImportDecl => sem::ImportDecl {
+    sem::ImportDecl {
+        path: sem::ModulePath {
+            segments: self.path.segments.iter().map(|ident| ident.lit.clone()).collect(),
+            span: self.path.span,
+        },
+        span: self.span,
+    }
+}}
+
+impl ToSemantic<sem::SourceFile> for ast::SourceFile {
+    /// `mod` and `import` may come anywhere among the top-level declarations.
+    fn to_semantic(&self) -> SemanticResult<sem::SourceFile> {
+        let mut mods = Vec::new();
+        let mut imports = Vec::new();
+        let mut decls = Vec::new();
+        for item in &self.items {
+            match item {
+                Item::ModDecl(v) => mods.push(v.to_semantic()),
+                Item::ImportDecl(v) => imports.push(v.to_semantic()),
+                item => decls.push(item.expect_semantic_decl()),
+            }
+        }
+        let (mods, (imports, decls)) = join(
+            collect(mods.into_iter()),
+            join(collect(imports.into_iter()), collect(decls.into_iter())),
+        )?;
+        Ok(sem::SourceFile { mods, imports, decls })
+    }
+}
+
 impl ast::Tuple {
-    pub fn expect_semantic_type_tuple(&self) -> Result<sem::RecordType, SemanticError> {
+    pub fn expect_semantic_type_tuple(&self) -> SemanticResult<sem::RecordType> {
         Ok(sem::RecordType {
-            fields: self.elems.iter().enumerate().map(|(i, field)| {
+            fields: collect(self.elems.iter().enumerate().map(|(i, field)| {
                 Ok(sem::Field {
                     ident: i.to_string(),
                     ty: field.expect_semantic_type()?,
+                    span: field.span(),
                 })
-            }).collect::<Result<Vec<_>, _>>()?,
+            }))?,
+            span: self.span,
         })
     }
 
-    pub fn expect_semantic_func_tuple(&self) -> Result<sem::RecordType, SemanticError> {
+    pub fn expect_semantic_func_tuple(&self) -> SemanticResult<sem::RecordType> {
         Ok(sem::RecordType {
-            fields: self.elems.iter().map(|field| {
+            fields: collect(self.elems.iter().map(|field| {
                 match field {
                     Item::IdentItem(field) => {
                         Ok(sem::Field {
                             ident: field.ident.lit.clone(),
                             ty: field.item.expect_semantic_type()?,
+                            span: field.span,
                         })
                     }
-                    _ => Err(UnexpectedNode { have: field }),
+                    // A parameter without a type, left to inference.
+                    Item::Ident(v) => Ok(sem::Field {
+                        ident: v.lit.clone(),
+                        ty: sem::Type::Infer(sem::InferType { span: v.span }),
+                        span: v.span,
+                    }),
+                    _ => unexpected(field, Category::Field),
                 }
-            }).collect::<Result<Vec<_>, _>>()?,
+            }))?,
+            span: self.span,
         })
     }
 
-    pub fn expect_semantic_expr_tuple(&self) -> Result<sem::RecordExpr, SemanticError> {
+    pub fn expect_semantic_expr_tuple(&self) -> SemanticResult<sem::RecordExpr> {
         Ok(sem::RecordExpr {
-            fields: self.elems.iter().enumerate().map(|(i, field)| {
+            fields: collect(self.elems.iter().enumerate().map(|(i, field)| {
                 Ok(sem::FieldFill {
                     ident: i.to_string(),
                     expr: field.expect_semantic_expr()?,
+                    span: field.span(),
                 })
-            }).collect::<Result<Vec<_>, _>>()?,
+            }))?,
+            span: self.span,
         })
     }
 
-    pub fn expect_semantic_field_fill_tuple(&self) -> Result<sem::RecordExpr, SemanticError> {
+    pub fn expect_semantic_field_fill_tuple(&self) -> SemanticResult<sem::RecordExpr> {
         Ok(sem::RecordExpr {
-            fields: self.elems.iter().map(|field| {
+            fields: collect(self.elems.iter().map(|field| {
                 match field {
                     Item::IdentItem(field) => {
                         Ok(sem::FieldFill {
                             ident: field.ident.lit.clone(),
                             expr: field.item.expect_semantic_expr()?,
+                            span: field.span,
                         })
                     }
-                    _ => Err(UnexpectedNode { have: field }),
+                    _ => unexpected(field, Category::Field),
                 }
-            }).collect::<Result<Vec<_>, _>>()?,
+            }))?,
+            span: self.span,
         })
     }
 
-    pub fn expect_semantic_func_param_tuple(&self) -> Result<sem::RecordExpr, SemanticError> {
-        if self.elems.len() == 0 {
-            Ok(sem::RecordExpr { fields: vec![] })
+    pub fn expect_semantic_func_param_tuple(&self) -> SemanticResult<sem::RecordExpr> {
+        if self.elems.is_empty() {
+            Ok(sem::RecordExpr { fields: vec![], span: self.span })
         } else {
             match self.elems[0] {
                 Item::IdentItem(_) => self.expect_semantic_field_fill_tuple(),
@@ -237,15 +654,20 @@ impl ast::Tuple {
 }
 
 impl Item {
-    pub fn expect_semantic_type(&self) -> Result<sem::Type, SemanticError> {
+    pub fn expect_semantic_type(&self) -> SemanticResult<sem::Type> {
         Ok(match self {
-            Item::Ident(v) => sem::Type::Ident(v.lit.to_string()),
+            Item::Error(v) => return Err(SemanticError::from(v.as_ref()).into()),
+
+            Item::Ident(v) if v.lit == "_" => sem::Type::Infer(sem::InferType { span: v.span }),
+            Item::Ident(v) => sem::Type::Ident(v.to_semantic()?),
             Item::Tuple(v) => sem::Type::Record(Box::from(v.expect_semantic_type_tuple()?)),
             Item::RecordType(v) => sem::Type::Record(Box::from(v.to_semantic()?)),
             Item::UnionType(v) => sem::Type::Union(Box::from(v.to_semantic()?)),
             Item::FuncType(v) => sem::Type::Func(Box::from(v.to_semantic()?)),
+            Item::ApplyType(v) => sem::Type::Apply(Box::from(v.to_semantic()?)),
 
             Item::Nat(_)
+            | Item::Path(_)
             | Item::Block(_)
             | Item::Func(_)
             | Item::Match(_)
@@ -258,14 +680,24 @@ impl Item {
             | Item::TypeAliasDecl(_)
             | Item::Select(_)
             | Item::Pipe(_)
-            | Item::IdentItem(_) => return Err(UnexpectedNode { have: self }),
+            | Item::IdentItem(_)
+            | Item::FuncDecl(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::Assign(_)
+            | Item::While(_) => return unexpected(self, Category::Type),
         })
     }
 
-    pub fn expect_semantic_expr(&self) -> Result<sem::Expr, SemanticError> {
+    pub fn expect_semantic_expr(&self) -> SemanticResult<sem::Expr> {
         Ok(match self {
+            Item::Error(v) => return Err(SemanticError::from(v.as_ref()).into()),
+
             Item::Nat(v) => sem::Expr::Nat(v.to_semantic()?),
-            Item::Ident(v) => sem::Expr::Ident(v.lit.to_string()),
+            Item::Ident(v) => sem::Expr::Ident(v.to_semantic()?),
+            Item::Path(v) => sem::Expr::Path(v.to_semantic()?),
+            // A parenthesized positional expression only groups, unless followed by `,`.
+            Item::Tuple(v) if v.elems.len() == 1 && !v.trailing && !matches!(v.elems[0], Item::IdentItem(_)) => v.elems[0].expect_semantic_expr()?,
             Item::Tuple(v) => sem::Expr::Record(Box::from(v.expect_semantic_func_param_tuple()?)),
             Item::Block(v) => sem::Expr::Block(Box::from(v.to_semantic()?)),
             Item::Func(v) => sem::Expr::Func(Box::from(v.to_semantic()?)),
@@ -280,21 +712,31 @@ impl Item {
             | Item::RecordType(_)
             | Item::UnionType(_)
             | Item::FuncType(_)
+            | Item::ApplyType(_)
             | Item::LetDecl(_)
             | Item::VarDecl(_)
             | Item::TypeAliasDecl(_)
-            | Item::IdentItem(_) => return Err(UnexpectedNode { have: self }),
+            | Item::IdentItem(_)
+            | Item::FuncDecl(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::Assign(_)
+            | Item::While(_) => return unexpected(self, Category::Expr),
         })
     }
 
-    pub fn expect_semantic_decl(&self) -> Result<sem::Decl, SemanticError> {
+    pub fn expect_semantic_decl(&self) -> SemanticResult<sem::Decl> {
         Ok(match self {
+            Item::Error(v) => return Err(SemanticError::from(v.as_ref()).into()),
+
             Item::LetDecl(v) => sem::Decl::Let(v.to_semantic()?),
             Item::VarDecl(v) => sem::Decl::Var(v.to_semantic()?),
             Item::TypeAliasDecl(v) => sem::Decl::TypeAlias(v.to_semantic()?),
+            Item::FuncDecl(v) => sem::Decl::Let(v.to_semantic()?),
 
             Item::Nat(_)
             | Item::Ident(_)
+            | Item::Path(_)
             | Item::Tuple(_)
             | Item::Block(_)
             | Item::Func(_)
@@ -303,19 +745,27 @@ impl Item {
             | Item::RecordType(_)
             | Item::UnionType(_)
             | Item::FuncType(_)
+            | Item::ApplyType(_)
             | Item::UnaryOpExpr(_)
             | Item::BinaryOpExpr(_)
             | Item::ApplyExpr(_)
             | Item::Select(_)
             | Item::Pipe(_)
-            | Item::IdentItem(_) => return Err(UnexpectedNode { have: self }),
+            | Item::IdentItem(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::Assign(_)
+            | Item::While(_) => return unexpected(self, Category::Decl),
         })
     }
 
-    pub fn expect_semantic_stmt(&self) -> Result<sem::Stmt, SemanticError> {
+    pub fn expect_semantic_stmt(&self) -> SemanticResult<sem::Stmt> {
         Ok(match self {
+            Item::Error(v) => return Err(SemanticError::from(v.as_ref()).into()),
+
             Item::Nat(_)
             | Item::Ident(_)
+            | Item::Path(_)
             | Item::Tuple(_)
             | Item::Block(_)
             | Item::Func(_)
@@ -328,13 +778,20 @@ impl Item {
 
             Item::LetDecl(_)
             | Item::VarDecl(_)
-            | Item::TypeAliasDecl(_) => sem::Stmt::Decl(self.expect_semantic_decl()?),
+            | Item::TypeAliasDecl(_)
+            | Item::FuncDecl(_) => sem::Stmt::Decl(self.expect_semantic_decl()?),
+
+            Item::Assign(v) => sem::Stmt::Assign(v.to_semantic()?),
+            Item::While(v) => sem::Stmt::While(v.to_semantic()?),
 
             Item::TypeTuple(_)
             | Item::RecordType(_)
             | Item::UnionType(_)
             | Item::FuncType(_)
-            | Item::IdentItem(_) => return Err(UnexpectedNode { have: self }),
+            | Item::ApplyType(_)
+            | Item::IdentItem(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_) => return unexpected(self, Category::Stmt),
         })
     }
 }
@@ -2,45 +2,92 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
-use paracell_util_macro::AsVariant;
+use paracell_parser_sem::span::Span;
+use paracell_util_macro::{AsVariant, ToLiteral};
 
-#[derive(Clone, Debug)]
-pub struct Nat {
-    pub val: u128,
-}
+pub use crate::flow::ast::{ErrorItem, Nat};
 
 #[derive(Clone, Debug)]
 pub struct Ident {
     pub lit: String,
+    pub span: Span,
 }
 
+/// `Op::Add`
 #[derive(Clone, Debug)]
+pub struct Path {
+    pub segments: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, ToLiteral)]
 pub enum UnaryOperator {
+    #[literal = "~"]
     Invert,
+    #[literal = "!"]
     Not,
 }
 
-#[derive(Clone, Debug)]
+/// Spelled as in flow except `and` and `or`, which stand for `&` and `|`.
+#[derive(Clone, Debug, ToLiteral)]
 pub enum BinaryOperator {
+    #[literal = "+"]
     Add,
+    #[literal = "-"]
     Sub,
+    #[literal = "*"]
     Mul,
+    #[literal = "/"]
     Div,
+    #[literal = "%"]
     Mod,
+    #[literal = "&"]
     And,
+    #[literal = "|"]
     Or,
+    #[literal = "^"]
+    Xor,
+    #[literal = "<<"]
+    Shl,
+    #[literal = ">>"]
+    Shr,
+    #[literal = "=="]
+    Eq,
+    #[literal = "!="]
+    Ne,
+    #[literal = "<"]
+    Lt,
+    #[literal = "<="]
+    Le,
+    #[literal = ">"]
+    Gt,
+    #[literal = ">="]
+    Ge,
+    #[literal = "&&"]
+    LogicAnd,
+    #[literal = "||"]
+    LogicOr,
 }
 
 #[derive(Clone, Debug)]
 pub struct Field {
     pub ident: Ident,
     pub ty: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct Variant {
     pub ident: Ident,
     pub ty: Item,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct Case {
+    pub pattern: Item,
+    pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -52,18 +99,28 @@ pub enum ListLead {
 
     Apply,
     Return,
+
+    Block,
+    Select,
+    Pipe,
+    While,
+    Set,
+    Range,
+    RangeInclusive,
 }
 
 #[derive(Clone, Debug)]
 pub struct List {
     pub lead: ListLead,
     pub items: Vec<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct UnaryOpExpr {
     pub op: UnaryOperator,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -71,33 +128,140 @@ pub struct BinaryOpExpr {
     pub op: BinaryOperator,
     pub left: Item,
     pub right: Item,
+    pub span: Span,
+}
+
+/// `(generic T (field N Nat))`, the parameters of a generic declaration.
+#[derive(Clone, Debug)]
+pub struct Generics {
+    pub params: Vec<Item>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct LetDecl {
+    /// Lines of the `;;;` comments right before the declaration.
+    pub docs: Vec<String>,
     pub ident: Ident,
+    pub generics: Option<Generics>,
+    pub ty: Option<Item>,
     pub expr: Item,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct VarDecl {
+    /// Lines of the `;;;` comments right before the declaration.
+    pub docs: Vec<String>,
     pub ident: Ident,
+    pub ty: Option<Item>,
     pub expr: Item,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct TypeAliasDecl {
+    /// Lines of the `;;;` comments right before the declaration.
+    pub docs: Vec<String>,
+    pub ident: Ident,
+    pub generics: Option<Generics>,
+    pub ty: Item,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModDecl {
+    /// Lines of the `;;;` comments right before the declaration.
+    pub docs: Vec<String>,
+    pub ident: Ident,
+    pub span: Span,
+}
+
+/// `(import alu::ALU)`, whose path may have a single segment.
+#[derive(Clone, Debug)]
+pub struct ImportDecl {
+    pub path: Path,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug, AsVariant)]
 pub enum Item {
     Nat(Nat),
     Ident(Ident),
+    Path(Path),
 
     Field(Box<Field>),
     Variant(Box<Variant>),
+    Case(Box<Case>),
 
     LetDecl(Box<LetDecl>),
     VarDecl(Box<VarDecl>),
+    TypeAliasDecl(Box<TypeAliasDecl>),
+    ModDecl(Box<ModDecl>),
+    ImportDecl(Box<ImportDecl>),
 
     UnaryOpExpr(Box<UnaryOpExpr>),
     BinaryOpExpr(Box<BinaryOpExpr>),
 
     List(List),
 }
+
+impl Item {
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Nat(v) => v.span,
+            Item::Ident(v) => v.span,
+            Item::Path(v) => v.span,
+            Item::Field(v) => v.span,
+            Item::Variant(v) => v.span,
+            Item::Case(v) => v.span,
+            Item::LetDecl(v) => v.span,
+            Item::VarDecl(v) => v.span,
+            Item::TypeAliasDecl(v) => v.span,
+            Item::ModDecl(v) => v.span,
+            Item::ImportDecl(v) => v.span,
+            Item::UnaryOpExpr(v) => v.span,
+            Item::BinaryOpExpr(v) => v.span,
+            Item::List(v) => v.span,
+        }
+    }
+
+    /// Human-readable name of the node, for diagnostics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Item::Nat(_) => "number literal",
+            Item::Ident(_) => "identifier",
+            Item::Path(_) => "path",
+            Item::Field(_) => "`field`",
+            Item::Variant(_) => "`variant`",
+            Item::Case(_) => "`case`",
+            Item::LetDecl(_) => "`let` declaration",
+            Item::VarDecl(_) => "`var` declaration",
+            Item::TypeAliasDecl(_) => "type alias",
+            Item::ModDecl(_) => "`mod` declaration",
+            Item::ImportDecl(_) => "`import`",
+            Item::UnaryOpExpr(_) => "unary expression",
+            Item::BinaryOpExpr(_) => "binary expression",
+            Item::List(v) => match v.lead {
+                ListLead::Union => "union type",
+                ListLead::Record => "record",
+                ListLead::Func => "function",
+                ListLead::Match => "match expression",
+                ListLead::Apply => "application",
+                ListLead::Return => "`return`",
+                ListLead::Block => "block",
+                ListLead::Select => "field selection",
+                ListLead::Pipe => "pipe",
+                ListLead::While => "`while` loop",
+                ListLead::Set => "assignment",
+                ListLead::Range | ListLead::RangeInclusive => "range",
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub items: Vec<Item>,
+    pub span: Span,
+}
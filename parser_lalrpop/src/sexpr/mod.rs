@@ -3,6 +3,18 @@
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
 pub mod ast;
+pub mod print;
 pub mod sem;
 
 lalrpop_util::lalrpop_mod!(pub grammar, "/sexpr/grammar.rs");
+
+use crate::flow::ast::ErrorItem;
+use crate::flow::sem::{SemanticError, SemanticResult, ToSemantic};
+
+/// Parses and lowers a source file, reporting every semantic error found.
+pub fn parse(src: &str) -> SemanticResult<paracell_parser_sem::sem::SourceFile> {
+    match grammar::SourceFileParser::new().parse(src) {
+        Ok(file) => file.to_semantic(),
+        Err(e) => Err(SemanticError::from(&ErrorItem::from_parse_error(e)).into()),
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Prints `sem` trees as canonical sexpr source, which parses back to an equal tree.
+//! Names spelled like a keyword are escaped as `|set|`.
+
+use crate::flow::print::Operator;
+use paracell_parser_sem::sem::*;
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+
+const INDENT: &str = "    ";
+
+/// Displays a `sem` node as sexpr source.
+pub struct Print<'a, T>(pub &'a T);
+
+/// Words the sexpr grammar reserves, which no name is read as unless escaped.
+const KEYWORDS: &[&str] = &[
+    "and", "apply", "block", "case", "field", "func", "generic", "import", "let", "match", "mod", "or", "pipe", "range",
+    "range_inclusive", "record", "return", "select", "set", "type", "union", "var", "variant", "while",
+];
+
+/// Displays a name, escaped as `|name|` if spelled like a keyword.
+struct Name<'a>(&'a str);
+
+impl Display for Name<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if KEYWORDS.contains(&self.0) {
+            write!(f, "|{}|", self.0)
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
+/// Displays the segments of a path with `::` between them.
+struct PathName<'a>(&'a [String]);
+
+impl Display for PathName<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("::")?;
+            }
+            write!(f, "{}", Name(segment))?;
+        }
+        Ok(())
+    }
+}
+
+/// Operators spelled differently from their literal.
+fn spelling(lit: &str) -> &str {
+    match lit {
+        "&" => "and",
+        "|" => "or",
+        lit => lit,
+    }
+}
+
+/// Whether the fields are named `0`, `1`, .. in order, as written without `field`.
+fn positional<'a>(idents: impl Iterator<Item = &'a str>) -> bool {
+    idents.enumerate().all(|(i, ident)| ident == i.to_string())
+}
+
+fn newline(f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+    f.write_char('\n')?;
+    for _ in 0..depth {
+        f.write_str(INDENT)?;
+    }
+    Ok(())
+}
+
+/// Writes each statement on its own line, one level deeper.
+fn write_body(f: &mut Formatter<'_>, stmts: &[Stmt], depth: usize) -> fmt::Result {
+    for stmt in stmts {
+        newline(f, depth + 1)?;
+        write_stmt(f, stmt, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn write_type(f: &mut Formatter<'_>, ty: &Type) -> fmt::Result {
+    match ty {
+        Type::Ident(v) => write!(f, "{}", Name(&v.lit)),
+        Type::Apply(v) => {
+            write!(f, "(apply {}", Name(&v.ty.lit))?;
+            for param in &v.params {
+                match param {
+                    TypeParam::Nat(v) => write!(f, " {}", v)?,
+                    TypeParam::Type(v) => {
+                        f.write_char(' ')?;
+                        write_type(f, v)?;
+                    }
+                }
+            }
+            f.write_char(')')
+        }
+        Type::Record(v) => write_record_type(f, v),
+        Type::Union(v) => {
+            f.write_str("(union")?;
+            for variant in &v.variants {
+                write!(f, " (variant {} ", Name(&variant.ident))?;
+                write_type(f, &variant.ty)?;
+                f.write_char(')')?;
+            }
+            f.write_char(')')
+        }
+        Type::Func(v) => {
+            f.write_str("(func ")?;
+            write_func_type(f, v)?;
+            f.write_char(')')
+        }
+        Type::Infer(_) => f.write_char('_'),
+    }
+}
+
+fn write_record_type(f: &mut Formatter<'_>, record: &RecordType) -> fmt::Result {
+    let named = !positional(record.fields.iter().map(|field| field.ident.as_str()));
+    f.write_str("(record")?;
+    for field in &record.fields {
+        if named {
+            write!(f, " (field {} ", Name(&field.ident))?;
+            write_type(f, &field.ty)?;
+            f.write_char(')')?;
+        } else {
+            f.write_char(' ')?;
+            write_type(f, &field.ty)?;
+        }
+    }
+    f.write_char(')')
+}
+
+/// `(record (field a Nat)) Nat`, the part of a `func` list before its body.
+fn write_func_type(f: &mut Formatter<'_>, ty: &FuncType) -> fmt::Result {
+    f.write_str("(record")?;
+    for field in &ty.params.fields {
+        write!(f, " (field {} ", Name(&field.ident))?;
+        write_type(f, &field.ty)?;
+        f.write_char(')')?;
+    }
+    f.write_str(") ")?;
+    write_type(f, &ty.result)
+}
+
+fn write_pattern(f: &mut Formatter<'_>, pattern: &Pattern) -> fmt::Result {
+    match pattern {
+        Pattern::Wildcard(_) => f.write_char('_'),
+        Pattern::Nat(v) => write!(f, "{}", v),
+        Pattern::Bind(v) => write!(f, "{}", Name(&v.lit)),
+        Pattern::Variant(v) => match &v.payload {
+            None => write!(f, "{}", PathName(&v.path.segments)),
+            Some(payload) => {
+                write!(f, "(apply {}", PathName(&v.path.segments))?;
+                match payload {
+                    // Spread, unless a lone positional field, which would be taken for the payload itself.
+                    Pattern::Record(record) if !matches!(record.fields.as_slice(), [field] if field.ident == "0") => {
+                        write_field_patterns(f, record)?
+                    }
+                    payload => {
+                        f.write_char(' ')?;
+                        write_pattern(f, payload)?;
+                    }
+                }
+                f.write_char(')')
+            }
+        },
+        Pattern::Record(v) => {
+            f.write_str("(record")?;
+            write_field_patterns(f, v)?;
+            f.write_char(')')
+        }
+        // (or (or a b) c)
+        Pattern::Or(v) => match v.alts.split_last() {
+            Some((last, [])) => write_pattern(f, last),
+            Some((last, init)) => {
+                f.write_str("(or ")?;
+                write_pattern(f, &Pattern::Or(Box::from(OrPattern { alts: init.to_vec(), span: v.span })))?;
+                f.write_char(' ')?;
+                write_pattern(f, last)?;
+                f.write_char(')')
+            }
+            None => Ok(()),
+        },
+        Pattern::Range(v) => {
+            let lead = if v.inclusive { "range_inclusive" } else { "range" };
+            write!(f, "({} {} {})", lead, v.lo, v.hi)
+        }
+    }
+}
+
+fn write_field_patterns(f: &mut Formatter<'_>, record: &RecordPattern) -> fmt::Result {
+    let named = !positional(record.fields.iter().map(|field| field.ident.as_str()));
+    for field in &record.fields {
+        if named {
+            write!(f, " (field {} ", Name(&field.ident))?;
+            write_pattern(f, &field.pattern)?;
+            f.write_char(')')?;
+        } else {
+            f.write_char(' ')?;
+            write_pattern(f, &field.pattern)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_field_fills(f: &mut Formatter<'_>, record: &RecordExpr, depth: usize) -> fmt::Result {
+    let named = !positional(record.fields.iter().map(|field| field.ident.as_str()));
+    for field in &record.fields {
+        if named {
+            write!(f, " (field {} ", Name(&field.ident))?;
+            write_expr(f, &field.expr, depth)?;
+            f.write_char(')')?;
+        } else {
+            f.write_char(' ')?;
+            write_expr(f, &field.expr, depth)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_expr(f: &mut Formatter<'_>, expr: &Expr, depth: usize) -> fmt::Result {
+    match expr {
+        Expr::Nat(v) => write!(f, "{}", v),
+        Expr::Ident(v) => write!(f, "{}", Name(&v.lit)),
+        Expr::Path(v) => write!(f, "{}", PathName(&v.segments)),
+        Expr::Block(v) => {
+            f.write_str("(block")?;
+            write_body(f, &v.stmts, depth)?;
+            f.write_char(')')
+        }
+        Expr::Func(v) => {
+            f.write_str("(func ")?;
+            write_func_type(f, &v.ty)?;
+            write_body(f, &v.block.stmts, depth)?;
+            f.write_char(')')
+        }
+        Expr::Record(v) => {
+            f.write_str("(record")?;
+            write_field_fills(f, v, depth)?;
+            f.write_char(')')
+        }
+        Expr::Apply(v) => match Operator::from_apply(v) {
+            Some(Operator::Unary(lit, operand)) => {
+                write!(f, "({} ", lit)?;
+                write_expr(f, operand, depth)?;
+                f.write_char(')')
+            }
+            Some(Operator::Binary(lit, left, right)) => {
+                write!(f, "({} ", spelling(lit))?;
+                write_expr(f, left, depth)?;
+                f.write_char(' ')?;
+                write_expr(f, right, depth)?;
+                f.write_char(')')
+            }
+            None => {
+                f.write_str("(apply ")?;
+                write_expr(f, &v.func, depth)?;
+                write_field_fills(f, &v.params, depth)?;
+                f.write_char(')')
+            }
+        },
+        Expr::Match(v) => {
+            f.write_str("(match ")?;
+            write_expr(f, &v.expr, depth)?;
+            for case in &v.cases {
+                newline(f, depth + 1)?;
+                f.write_str("(case ")?;
+                write_pattern(f, &case.pattern)?;
+                f.write_char(' ')?;
+                write_expr(f, &case.expr, depth + 1)?;
+                f.write_char(')')?;
+            }
+            f.write_char(')')
+        }
+        Expr::Select(v) => {
+            f.write_str("(select ")?;
+            write_expr(f, &v.expr, depth)?;
+            write!(f, " {})", Name(&v.ident))
+        }
+        Expr::Pipe(v) => {
+            f.write_str("(pipe ")?;
+            write_expr(f, &v.from, depth)?;
+            f.write_char(' ')?;
+            write_expr(f, &v.to, depth)?;
+            f.write_char(')')
+        }
+    }
+}
+
+fn write_doc(f: &mut Formatter<'_>, doc: Option<&str>, depth: usize) -> fmt::Result {
+    for line in doc.unwrap_or_default().lines() {
+        if line.is_empty() {
+            f.write_str(";;;")?;
+        } else {
+            write!(f, ";;; {}", line)?;
+        }
+        newline(f, depth)?;
+    }
+    Ok(())
+}
+
+/// The type of an annotated `let` or `var`, before its value.
+fn write_annotation(f: &mut Formatter<'_>, ty: Option<&Type>) -> fmt::Result {
+    match ty {
+        Some(ty) => {
+            write_type(f, ty)?;
+            f.write_char(' ')
+        }
+        None => Ok(()),
+    }
+}
+
+fn write_generics(f: &mut Formatter<'_>, generics: &[GenericParam]) -> fmt::Result {
+    if generics.is_empty() {
+        return Ok(());
+    }
+    f.write_str("(generic")?;
+    for param in generics {
+        match param {
+            GenericParam::Type(v) => write!(f, " {}", Name(&v.lit))?,
+            GenericParam::Width(v) => write!(f, " (field {} Nat)", Name(&v.lit))?,
+        }
+    }
+    f.write_str(") ")
+}
+
+fn write_decl(f: &mut Formatter<'_>, decl: &Decl, depth: usize) -> fmt::Result {
+    write_doc(f, decl.doc(), depth)?;
+    match decl {
+        Decl::Let(v) => {
+            write!(f, "(let {} ", Name(&v.ident))?;
+            write_generics(f, &v.generics)?;
+            write_annotation(f, v.ty.as_ref())?;
+            write_expr(f, &v.expr, depth)?;
+        }
+        Decl::Var(v) => {
+            write!(f, "(var {} ", Name(&v.ident))?;
+            write_annotation(f, v.ty.as_ref())?;
+            write_expr(f, &v.expr, depth)?;
+        }
+        Decl::TypeAlias(v) => {
+            write!(f, "(type {} ", Name(&v.ident))?;
+            write_generics(f, &v.generics)?;
+            write_type(f, &v.ty)?;
+        }
+    }
+    f.write_char(')')
+}
+
+fn write_stmt(f: &mut Formatter<'_>, stmt: &Stmt, depth: usize) -> fmt::Result {
+    match stmt {
+        Stmt::Decl(v) => write_decl(f, v, depth),
+        Stmt::Expr(v) => write_expr(f, v, depth),
+        Stmt::Assign(v) => {
+            write!(f, "(set {} ", Name(&v.target.lit))?;
+            write_expr(f, &v.expr, depth)?;
+            f.write_char(')')
+        }
+        Stmt::While(v) => {
+            f.write_str("(while ")?;
+            write_expr(f, &v.cond, depth)?;
+            write_body(f, &v.block.stmts, depth)?;
+            f.write_char(')')
+        }
+    }
+}
+
+impl Display for Print<'_, SourceFile> {
+    /// `mod` and `import` declarations come first, one per line.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for v in &self.0.mods {
+            write_doc(f, v.doc.as_deref(), 0)?;
+            writeln!(f, "(mod {})", Name(&v.ident))?;
+        }
+        for v in &self.0.imports {
+            writeln!(f, "(import {})", PathName(&v.path.segments))?;
+        }
+        let header = !self.0.mods.is_empty() || !self.0.imports.is_empty();
+        if header && !self.0.decls.is_empty() {
+            f.write_char('\n')?;
+        }
+        for (i, decl) in self.0.decls.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n\n")?;
+            }
+            write_decl(f, decl, 0)?;
+        }
+        if !self.0.decls.is_empty() {
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Print<'_, Decl> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_decl(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Stmt> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_stmt(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Expr> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_expr(f, self.0, 0)
+    }
+}
+
+impl Display for Print<'_, Type> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_type(f, self.0)
+    }
+}
+
+impl Display for Print<'_, Pattern> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_pattern(f, self.0)
+    }
+}
@@ -0,0 +1,621 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use crate::flow::sem::{Category, SemanticError, SemanticResult, ToSemantic, collect, join, join_docs};
+use crate::sexpr::ast;
+use crate::sexpr::ast::{Item, List, ListLead};
+use paracell_parser_sem::sem;
+
+const UNION_USAGE: &str = "(union (variant Name Type)..)";
+const FUNC_USAGE: &str = "(func (record (field name Type)..) Result body..)";
+const FUNC_TYPE_USAGE: &str = "(func (record (field name Type)..) Result)";
+const MATCH_USAGE: &str = "(match expr (case pattern expr)..)";
+const APPLY_USAGE: &str = "(apply func arg..)";
+const APPLY_TYPE_USAGE: &str = "(apply Type param..)";
+const SELECT_USAGE: &str = "(select expr field)";
+const PIPE_USAGE: &str = "(pipe from to)";
+const WHILE_USAGE: &str = "(while cond body..)";
+const SET_USAGE: &str = "(set name expr)";
+const RANGE_USAGE: &str = "(range lo hi)";
+const RETURN_USAGE: &str = "(return expr) as the last form of a function body";
+
+fn unexpected<T>(item: &Item, expected: Category) -> SemanticResult<T> {
+    Err(SemanticError::UnexpectedNode { expected, found: item.kind(), span: item.span() }.into())
+}
+
+fn malformed<T>(form: &'static str, usage: &'static str, list: &List) -> SemanticResult<T> {
+    Err(SemanticError::Malformed { form, usage, span: list.span }.into())
+}
+
+macro_rules! def_semantic {
+    ($self: ident : $ast: ty => $sem: ty $body: block) => {
+        impl ToSemantic<$sem> for $ast {
+            fn to_semantic($self: &Self) -> SemanticResult<$sem> {
+                Ok($body)
+            }
+        }
+    };
+}
+
+def_semantic! { self: ast::Ident => sem::Ident {
+    sem::Ident {
+        lit: self.lit.clone(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::Path => sem::Path {
+    sem::Path {
+        segments: self.segments.iter().map(|ident| ident.lit.clone()).collect(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::UnaryOpExpr => sem::ApplyExpr {
+    sem::ApplyExpr {
+        func: sem::Expr::Ident(sem::Ident { lit: self.op.to_literal().to_string(), span: self.span }),
+        params: sem::RecordExpr {
+            fields: vec![sem::FieldFill { ident: 0.to_string(), expr: self.expr.expect_semantic_expr()?, span: self.expr.span() }],
+            span: self.span,
+        },
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::BinaryOpExpr => sem::ApplyExpr {
+    let (left, right) = join(self.left.expect_semantic_expr(), self.right.expect_semantic_expr())?;
+    sem::ApplyExpr {
+        func: sem::Expr::Ident(sem::Ident { lit: self.op.to_literal().to_string(), span: self.span }),
+        params: sem::RecordExpr {
+            fields: vec![
+                sem::FieldFill { ident: 0.to_string(), expr: left, span: self.left.span() },
+                sem::FieldFill { ident: 1.to_string(), expr: right, span: self.right.span() },
+            ],
+            span: self.span,
+        },
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::Case => sem::Case {
+    let (pattern, expr) = join(self.pattern.expect_semantic_pattern(), self.expr.expect_semantic_expr())?;
+    sem::Case {
+        pattern,
+        expr,
+        span: self.span,
+    }
+}}
+
+/// The parameters of `(generic T (field N Nat))`, none if a declaration is not generic.
+fn expect_semantic_generics(generics: Option<&ast::Generics>) -> SemanticResult<Vec<sem::GenericParam>> {
+    let Some(generics) = generics else { return Ok(vec![]) };
+    collect(generics.params.iter().map(|param| match param {
+        Item::Ident(v) => Ok(sem::GenericParam::Type(v.to_semantic()?)),
+        Item::Field(v) if matches!(&v.ty, Item::Ident(ty) if ty.lit == "Nat") => Ok(sem::GenericParam::Width(v.ident.to_semantic()?)),
+        _ => unexpected(param, Category::Generic),
+    }))
+}
+
+def_semantic! { self: ast::LetDecl => sem::LetDecl {
+    let (generics, (ty, expr)) = join(
+        expect_semantic_generics(self.generics.as_ref()),
+        join(self.ty.as_ref().map(Item::expect_semantic_type).transpose(), self.expr.expect_semantic_expr()),
+    )?;
+    sem::LetDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        generics,
+        ty,
+        expr,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::VarDecl => sem::VarDecl {
+    let (ty, expr) = join(self.ty.as_ref().map(Item::expect_semantic_type).transpose(), self.expr.expect_semantic_expr())?;
+    sem::VarDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        ty,
+        expr,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::TypeAliasDecl => sem::TypeAliasDecl {
+    let (generics, ty) = join(expect_semantic_generics(self.generics.as_ref()), self.ty.expect_semantic_type())?;
+    sem::TypeAliasDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        generics,
+        ty,
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::ModDecl => sem::ModDecl {
+    sem::ModDecl {
+        doc: join_docs(&self.docs),
+        ident: self.ident.lit.clone(),
+        span: self.span,
+    }
+}}
+
+def_semantic! { self: ast::ImportDecl => sem::ImportDecl {
+    sem::ImportDecl {
+        path: sem::ModulePath {
+            segments: self.path.segments.iter().map(|ident| ident.lit.clone()).collect(),
+            span: self.path.span,
+        },
+        span: self.span,
+    }
+}}
+
+impl ToSemantic<sem::SourceFile> for ast::SourceFile {
+    /// `mod` and `import` may come anywhere among the top-level declarations.
+    fn to_semantic(&self) -> SemanticResult<sem::SourceFile> {
+        let mut mods = Vec::new();
+        let mut imports = Vec::new();
+        let mut decls = Vec::new();
+        for item in &self.items {
+            match item {
+                Item::ModDecl(v) => mods.push(v.to_semantic()),
+                Item::ImportDecl(v) => imports.push(v.to_semantic()),
+                item => decls.push(item.expect_semantic_decl()),
+            }
+        }
+        let (mods, (imports, decls)) = join(
+            collect(mods.into_iter()),
+            join(collect(imports.into_iter()), collect(decls.into_iter())),
+        )?;
+        Ok(sem::SourceFile { mods, imports, decls })
+    }
+}
+
+impl List {
+    /// `(record (field a Nat) (field b Nat))` or `(record Nat Nat)`
+    pub fn expect_semantic_record_type(&self) -> SemanticResult<sem::RecordType> {
+        let named = matches!(self.items.first(), Some(Item::Field(_)));
+        Ok(sem::RecordType {
+            fields: collect(self.items.iter().enumerate().map(|(i, item)| {
+                match item {
+                    Item::Field(field) if named => Ok(sem::Field {
+                        ident: field.ident.lit.clone(),
+                        ty: field.ty.expect_semantic_type()?,
+                        span: field.span,
+                    }),
+                    Item::Field(_) => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ if named => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ => Ok(sem::Field {
+                        ident: i.to_string(),
+                        ty: item.expect_semantic_type()?,
+                        span: item.span(),
+                    }),
+                }
+            }))?,
+            span: self.span,
+        })
+    }
+
+    /// Parameters of a function, every one named by `field`.
+    fn expect_semantic_params(&self) -> SemanticResult<sem::RecordType> {
+        Ok(sem::RecordType {
+            fields: collect(self.items.iter().map(|field| {
+                match field {
+                    Item::Field(field) => Ok(sem::Field {
+                        ident: field.ident.lit.clone(),
+                        ty: field.ty.expect_semantic_type()?,
+                        span: field.span,
+                    }),
+                    _ => unexpected(field, Category::Field),
+                }
+            }))?,
+            span: self.span,
+        })
+    }
+
+    /// `(union (variant A Nat) (variant B Nat))`
+    pub fn expect_semantic_union_type(&self) -> SemanticResult<sem::UnionType> {
+        Ok(sem::UnionType {
+            variants: collect(self.items.iter().map(|variant| {
+                match variant {
+                    Item::Variant(variant) => Ok(sem::Variant {
+                        ident: variant.ident.lit.clone(),
+                        ty: variant.ty.expect_semantic_type()?,
+                        span: variant.span,
+                    }),
+                    _ => malformed("union", UNION_USAGE, self),
+                }
+            }))?,
+            span: self.span,
+        })
+    }
+
+    /// Parameters and result type leading a `func` list.
+    fn expect_semantic_func_type(&self, usage: &'static str) -> SemanticResult<sem::FuncType> {
+        let (params, result) = match self.items.as_slice() {
+            [Item::List(params @ List { lead: ListLead::Record, .. }), result, ..] => (params, result),
+            _ => return malformed("func", usage, self),
+        };
+        let (params, result) = join(params.expect_semantic_params(), result.expect_semantic_type())?;
+        Ok(sem::FuncType {
+            params,
+            result,
+            span: self.span,
+        })
+    }
+
+    /// Fields of a record expression or the arguments of an application, all named by `field` or all positional.
+    fn expect_semantic_field_fills(&self, items: &[Item]) -> SemanticResult<sem::RecordExpr> {
+        let named = matches!(items.first(), Some(Item::Field(_)));
+        Ok(sem::RecordExpr {
+            fields: collect(items.iter().enumerate().map(|(i, item)| {
+                match item {
+                    Item::Field(field) if named => Ok(sem::FieldFill {
+                        ident: field.ident.lit.clone(),
+                        expr: field.ty.expect_semantic_expr()?,
+                        span: field.span,
+                    }),
+                    Item::Field(_) => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ if named => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ => Ok(sem::FieldFill {
+                        ident: i.to_string(),
+                        expr: item.expect_semantic_expr()?,
+                        span: item.span(),
+                    }),
+                }
+            }))?,
+            span: self.span,
+        })
+    }
+
+    /// `(record a b)` or `(record (field q a) (field r b))`
+    fn expect_semantic_field_patterns(&self, items: &[Item]) -> SemanticResult<sem::RecordPattern> {
+        let named = matches!(items.first(), Some(Item::Field(_)));
+        Ok(sem::RecordPattern {
+            fields: collect(items.iter().enumerate().map(|(i, item)| {
+                match item {
+                    Item::Field(field) if named => Ok(sem::FieldPattern {
+                        ident: field.ident.lit.clone(),
+                        pattern: field.ty.expect_semantic_pattern()?,
+                        span: field.span,
+                    }),
+                    Item::Field(_) => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ if named => Err(SemanticError::MixedFields { span: item.span() }.into()),
+                    _ => Ok(sem::FieldPattern {
+                        ident: i.to_string(),
+                        pattern: item.expect_semantic_pattern()?,
+                        span: item.span(),
+                    }),
+                }
+            }))?,
+            span: self.span,
+        })
+    }
+
+    /// `(func (record (field a Nat)) Nat body..)`. The body is a block whose value is its last form,
+    /// which may be spelled `(return expr)`.
+    pub fn expect_semantic_func(&self) -> SemanticResult<sem::Func> {
+        let body = self.items.get(2..).unwrap_or_default();
+        let stmts = collect(body.iter().enumerate().map(|(i, item)| {
+            match item {
+                Item::List(list @ List { lead: ListLead::Return, .. }) => match list.items.as_slice() {
+                    [expr] if i + 1 == body.len() => Ok(sem::Stmt::Expr(expr.expect_semantic_expr()?)),
+                    _ => malformed("return", RETURN_USAGE, list),
+                },
+                _ => item.expect_semantic_stmt(),
+            }
+        }));
+        let (ty, stmts) = join(self.expect_semantic_func_type(FUNC_USAGE), stmts)?;
+        Ok(sem::Func {
+            ty,
+            block: sem::Block { stmts, span: self.span },
+            span: self.span,
+        })
+    }
+
+    /// `(block stmt..)`
+    pub fn expect_semantic_block(&self) -> SemanticResult<sem::Block> {
+        Ok(sem::Block {
+            stmts: collect(self.items.iter().map(Item::expect_semantic_stmt))?,
+            span: self.span,
+        })
+    }
+
+    /// `(select expr field)`
+    pub fn expect_semantic_select(&self) -> SemanticResult<sem::Select> {
+        match self.items.as_slice() {
+            [expr, Item::Ident(ident)] => Ok(sem::Select {
+                expr: expr.expect_semantic_expr()?,
+                ident: ident.lit.clone(),
+                span: self.span,
+            }),
+            _ => malformed("select", SELECT_USAGE, self),
+        }
+    }
+
+    /// `(pipe from to)`
+    pub fn expect_semantic_pipe(&self) -> SemanticResult<sem::Pipe> {
+        match self.items.as_slice() {
+            [from, to] => {
+                let (from, to) = join(from.expect_semantic_expr(), to.expect_semantic_expr())?;
+                Ok(sem::Pipe {
+                    from,
+                    to,
+                    span: self.span,
+                })
+            }
+            _ => malformed("pipe", PIPE_USAGE, self),
+        }
+    }
+
+    /// `(while cond body..)`
+    pub fn expect_semantic_while(&self) -> SemanticResult<sem::While> {
+        let (cond, body) = match self.items.split_first() {
+            Some((cond, body)) => (cond, body),
+            None => return malformed("while", WHILE_USAGE, self),
+        };
+        let (cond, stmts) = join(cond.expect_semantic_expr(), collect(body.iter().map(Item::expect_semantic_stmt)))?;
+        Ok(sem::While {
+            cond,
+            block: sem::Block { stmts, span: self.span },
+            span: self.span,
+        })
+    }
+
+    /// `(set target expr)`
+    pub fn expect_semantic_assign(&self) -> SemanticResult<sem::Assign> {
+        let (target, expr) = match self.items.as_slice() {
+            [target, expr] => (target, expr),
+            _ => return malformed("set", SET_USAGE, self),
+        };
+        let target = match target {
+            Item::Ident(v) => v.to_semantic(),
+            _ => unexpected(target, Category::Place),
+        };
+        let (target, expr) = join(target, expr.expect_semantic_expr())?;
+        Ok(sem::Assign {
+            target,
+            expr,
+            span: self.span,
+        })
+    }
+
+    /// `(range 0 8)` or `(range_inclusive 0 7)`
+    pub fn expect_semantic_range(&self) -> SemanticResult<sem::RangePattern> {
+        match self.items.as_slice() {
+            [Item::Nat(lo), Item::Nat(hi)] => Ok(sem::RangePattern {
+                lo: lo.to_semantic()?,
+                hi: hi.to_semantic()?,
+                inclusive: matches!(self.lead, ListLead::RangeInclusive),
+                span: self.span,
+            }),
+            _ => malformed("range", RANGE_USAGE, self),
+        }
+    }
+
+    /// `(match expr (case pattern expr)..)`
+    pub fn expect_semantic_match(&self) -> SemanticResult<sem::Match> {
+        let (expr, cases) = match self.items.split_first() {
+            Some((expr, cases)) => (expr, cases),
+            None => return malformed("match", MATCH_USAGE, self),
+        };
+        let cases = collect(cases.iter().map(|case| {
+            match case {
+                Item::Case(case) => case.to_semantic(),
+                _ => malformed("match", MATCH_USAGE, self),
+            }
+        }));
+        let (expr, cases) = join(expr.expect_semantic_expr(), cases)?;
+        Ok(sem::Match {
+            expr,
+            cases,
+            span: self.span,
+        })
+    }
+
+    /// `(apply f a b)` or `(apply f (field x a))`
+    pub fn expect_semantic_apply(&self) -> SemanticResult<sem::ApplyExpr> {
+        let (func, args) = match self.items.split_first() {
+            Some((func, args)) => (func, args),
+            None => return malformed("apply", APPLY_USAGE, self),
+        };
+        let (func, params) = join(func.expect_semantic_expr(), self.expect_semantic_field_fills(args))?;
+        Ok(sem::ApplyExpr {
+            func,
+            params,
+            span: self.span,
+        })
+    }
+
+    /// `(apply Nat 8)`
+    pub fn expect_semantic_apply_type(&self) -> SemanticResult<sem::ApplyType> {
+        let (ty, params) = match self.items.split_first() {
+            Some((Item::Ident(ty), params)) => (ty, params),
+            _ => return malformed("apply", APPLY_TYPE_USAGE, self),
+        };
+        let params = collect(params.iter().map(|param| match param {
+            Item::Nat(v) => Ok(sem::TypeParam::Nat(v.to_semantic()?)),
+            _ => Ok(sem::TypeParam::Type(param.expect_semantic_type()?)),
+        }))?;
+        Ok(sem::ApplyType {
+            ty: ty.to_semantic()?,
+            params,
+            span: self.span,
+        })
+    }
+}
+
+impl Item {
+    pub fn expect_semantic_type(&self) -> SemanticResult<sem::Type> {
+        Ok(match self {
+            Item::Ident(v) if v.lit == "_" => sem::Type::Infer(sem::InferType { span: v.span }),
+            Item::Ident(v) => sem::Type::Ident(v.to_semantic()?),
+            Item::List(v) => match v.lead {
+                ListLead::Record => sem::Type::Record(Box::from(v.expect_semantic_record_type()?)),
+                ListLead::Union => sem::Type::Union(Box::from(v.expect_semantic_union_type()?)),
+                ListLead::Func if v.items.len() == 2 => sem::Type::Func(Box::from(v.expect_semantic_func_type(FUNC_TYPE_USAGE)?)),
+                ListLead::Func => return malformed("func", FUNC_TYPE_USAGE, v),
+                ListLead::Apply => sem::Type::Apply(Box::from(v.expect_semantic_apply_type()?)),
+                ListLead::Match
+                | ListLead::Return
+                | ListLead::Block
+                | ListLead::Select
+                | ListLead::Pipe
+                | ListLead::While
+                | ListLead::Set
+                | ListLead::Range
+                | ListLead::RangeInclusive => return unexpected(self, Category::Type),
+            },
+
+            Item::Nat(_)
+            | Item::Path(_)
+            | Item::Field(_)
+            | Item::Variant(_)
+            | Item::Case(_)
+            | Item::LetDecl(_)
+            | Item::VarDecl(_)
+            | Item::TypeAliasDecl(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::UnaryOpExpr(_)
+            | Item::BinaryOpExpr(_) => return unexpected(self, Category::Type),
+        })
+    }
+
+    pub fn expect_semantic_expr(&self) -> SemanticResult<sem::Expr> {
+        Ok(match self {
+            Item::Nat(v) => sem::Expr::Nat(v.to_semantic()?),
+            Item::Ident(v) => sem::Expr::Ident(v.to_semantic()?),
+            Item::Path(v) => sem::Expr::Path(v.to_semantic()?),
+            Item::UnaryOpExpr(v) => sem::Expr::Apply(Box::from(v.to_semantic()?)),
+            Item::BinaryOpExpr(v) => sem::Expr::Apply(Box::from(v.to_semantic()?)),
+            Item::List(v) => match v.lead {
+                ListLead::Record => sem::Expr::Record(Box::from(v.expect_semantic_field_fills(&v.items)?)),
+                ListLead::Func => sem::Expr::Func(Box::from(v.expect_semantic_func()?)),
+                ListLead::Match => sem::Expr::Match(Box::from(v.expect_semantic_match()?)),
+                ListLead::Apply => sem::Expr::Apply(Box::from(v.expect_semantic_apply()?)),
+                ListLead::Block => sem::Expr::Block(Box::from(v.expect_semantic_block()?)),
+                ListLead::Select => sem::Expr::Select(Box::from(v.expect_semantic_select()?)),
+                ListLead::Pipe => sem::Expr::Pipe(Box::from(v.expect_semantic_pipe()?)),
+                ListLead::Return => return malformed("return", RETURN_USAGE, v),
+                ListLead::Union
+                | ListLead::While
+                | ListLead::Set
+                | ListLead::Range
+                | ListLead::RangeInclusive => return unexpected(self, Category::Expr),
+            },
+
+            Item::Field(_)
+            | Item::Variant(_)
+            | Item::Case(_)
+            | Item::LetDecl(_)
+            | Item::VarDecl(_)
+            | Item::TypeAliasDecl(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_) => return unexpected(self, Category::Expr),
+        })
+    }
+
+    pub fn expect_semantic_pattern(&self) -> SemanticResult<sem::Pattern> {
+        Ok(match self {
+            Item::Nat(v) => sem::Pattern::Nat(v.to_semantic()?),
+            Item::Ident(v) if v.lit == "_" => sem::Pattern::Wildcard(sem::WildcardPattern { span: v.span }),
+            Item::Ident(v) => sem::Pattern::Bind(v.to_semantic()?),
+            Item::Path(v) => sem::Pattern::Variant(Box::from(sem::VariantPattern {
+                path: v.to_semantic()?,
+                payload: None,
+                span: v.span,
+            })),
+            // (apply Op::Mul a b)
+            Item::List(v @ List { lead: ListLead::Apply, .. }) => match v.items.split_first() {
+                Some((Item::Path(path), payload)) => sem::Pattern::Variant(Box::from(sem::VariantPattern {
+                    path: path.to_semantic()?,
+                    // A payload of one positional field is the field itself.
+                    payload: match payload {
+                        [] => None,
+                        [field] if !matches!(field, Item::Field(_)) => Some(field.expect_semantic_pattern()?),
+                        _ => Some(sem::Pattern::Record(Box::from(v.expect_semantic_field_patterns(payload)?))),
+                    },
+                    span: v.span,
+                })),
+                _ => return malformed("apply", "(apply Path pattern..)", v),
+            },
+            Item::List(v @ List { lead: ListLead::Record, .. }) => {
+                sem::Pattern::Record(Box::from(v.expect_semantic_field_patterns(&v.items)?))
+            }
+            Item::List(v @ List { lead: ListLead::Range | ListLead::RangeInclusive, .. }) => {
+                sem::Pattern::Range(Box::from(v.expect_semantic_range()?))
+            }
+            // (or a b), flattened like `a | b | c`.
+            Item::BinaryOpExpr(v) if matches!(v.op, ast::BinaryOperator::Or) => {
+                let (left, right) = join(v.left.expect_semantic_pattern(), v.right.expect_semantic_pattern())?;
+                let mut alts = Vec::new();
+                for alt in [left, right] {
+                    match alt {
+                        sem::Pattern::Or(or) => alts.extend(or.alts),
+                        alt => alts.push(alt),
+                    }
+                }
+                sem::Pattern::Or(Box::from(sem::OrPattern { alts, span: v.span }))
+            }
+
+            Item::List(_)
+            | Item::Field(_)
+            | Item::Variant(_)
+            | Item::Case(_)
+            | Item::LetDecl(_)
+            | Item::VarDecl(_)
+            | Item::TypeAliasDecl(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::UnaryOpExpr(_)
+            | Item::BinaryOpExpr(_) => return unexpected(self, Category::Pattern),
+        })
+    }
+
+    pub fn expect_semantic_decl(&self) -> SemanticResult<sem::Decl> {
+        Ok(match self {
+            Item::LetDecl(v) => sem::Decl::Let(v.to_semantic()?),
+            Item::VarDecl(v) => sem::Decl::Var(v.to_semantic()?),
+            Item::TypeAliasDecl(v) => sem::Decl::TypeAlias(v.to_semantic()?),
+
+            Item::Nat(_)
+            | Item::Ident(_)
+            | Item::Path(_)
+            | Item::Field(_)
+            | Item::Variant(_)
+            | Item::Case(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_)
+            | Item::UnaryOpExpr(_)
+            | Item::BinaryOpExpr(_)
+            | Item::List(_) => return unexpected(self, Category::Decl),
+        })
+    }
+
+    pub fn expect_semantic_stmt(&self) -> SemanticResult<sem::Stmt> {
+        Ok(match self {
+            Item::LetDecl(_)
+            | Item::VarDecl(_)
+            | Item::TypeAliasDecl(_) => sem::Stmt::Decl(self.expect_semantic_decl()?),
+
+            Item::List(v @ List { lead: ListLead::While, .. }) => sem::Stmt::While(v.expect_semantic_while()?),
+            Item::List(v @ List { lead: ListLead::Set, .. }) => sem::Stmt::Assign(v.expect_semantic_assign()?),
+
+            Item::Nat(_)
+            | Item::Ident(_)
+            | Item::Path(_)
+            | Item::UnaryOpExpr(_)
+            | Item::BinaryOpExpr(_)
+            | Item::List(_) => sem::Stmt::Expr(self.expect_semantic_expr()?),
+
+            Item::Field(_)
+            | Item::Variant(_)
+            | Item::Case(_)
+            | Item::ModDecl(_)
+            | Item::ImportDecl(_) => return unexpected(self, Category::Stmt),
+        })
+    }
+}
@@ -4,27 +4,30 @@
 
 use paracell_parser_lalrpop::flow::ast::*;
 use paracell_parser_lalrpop::flow::grammar;
+use paracell_parser_lalrpop::flow::lexer::Lexer;
+use paracell_parser_lalrpop::flow::sem::{Category, SemanticError, ToSemantic};
+use paracell_parser_sem::diag::Source;
 
 #[test]
 fn test_parse_nat() {
-    let hex = grammar::NatParser::new().parse("0xEF").unwrap().val;
-    let dec = grammar::NatParser::new().parse("1024").unwrap().val;
-    let oct = grammar::NatParser::new().parse("0o644").unwrap().val;
-    let bin = grammar::NatParser::new().parse("0b1001").unwrap().val;
-    assert_eq!(hex, 0xEF);
-    assert_eq!(dec, 1024);
-    assert_eq!(oct, 0o644);
-    assert_eq!(bin, 0b1001);
+    let hex = grammar::NatParser::new().parse(Lexer::new("0xEF")).unwrap().val;
+    let dec = grammar::NatParser::new().parse(Lexer::new("1024")).unwrap().val;
+    let oct = grammar::NatParser::new().parse(Lexer::new("0o644")).unwrap().val;
+    let bin = grammar::NatParser::new().parse(Lexer::new("0b1001")).unwrap().val;
+    assert_eq!(hex, 0xEFu32.into());
+    assert_eq!(dec, 1024u32.into());
+    assert_eq!(oct, 0o644u32.into());
+    assert_eq!(bin, 0b1001u32.into());
 }
 
 #[test]
 fn test_parse_record() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         record {
             A: Nat,
             B: Nat
         }
-    ").unwrap().expect_semantic_type().unwrap().as_Record().unwrap();
+    ")).unwrap().expect_semantic_type().unwrap().as_Record().unwrap();
 
     let f2 = s.fields.pop().unwrap();
     let f1 = s.fields.pop().unwrap();
@@ -35,12 +38,12 @@ fn test_parse_record() {
 
 #[test]
 fn test_parse_union() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         union {
             A: Nat,
             B: Nat
         }
-    ").unwrap().expect_semantic_type().unwrap().as_Union().unwrap();
+    ")).unwrap().expect_semantic_type().unwrap().as_Union().unwrap();
 
     let v2 = s.variants.pop().unwrap();
     let v1 = s.variants.pop().unwrap();
@@ -51,35 +54,35 @@ fn test_parse_union() {
 
 #[test]
 fn test_parse_unary() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         ~Bit
-    ").unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
+    ")).unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
 
     let f = s.params.fields.pop().unwrap();
 
-    assert_eq!(s.func.as_Ident().unwrap(), UnaryOperator::Invert.to_literal());
-    assert_eq!(f.expr.as_Ident().unwrap(), "Bit");
+    assert_eq!(s.func.as_Ident().unwrap().lit, UnaryOperator::Invert.to_literal());
+    assert_eq!(f.expr.as_Ident().unwrap().lit, "Bit");
 }
 
 #[test]
 fn test_parse_binary() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         1 + 2
-    ").unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
+    ")).unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
 
     let f2 = s.params.fields.pop().unwrap();
     let f1 = s.params.fields.pop().unwrap();
 
-    assert_eq!(s.func.as_Ident().unwrap(), BinaryOperator::Add.to_literal());
-    assert_eq!(f1.expr.as_Nat().unwrap().val, 1);
-    assert_eq!(f2.expr.as_Nat().unwrap().val, 2);
+    assert_eq!(s.func.as_Ident().unwrap().lit, BinaryOperator::Add.to_literal());
+    assert_eq!(f1.expr.as_Nat().unwrap().val, 1u32.into());
+    assert_eq!(f2.expr.as_Nat().unwrap().val, 2u32.into());
 }
 
 #[test]
 fn test_parse_let() {
-    let s = grammar::ItemParser::new().parse("
+    let s = grammar::ItemParser::new().parse(Lexer::new("
         let v = 1 + 2
-    ").unwrap().expect_semantic_decl().unwrap().as_Let().unwrap();
+    ")).unwrap().expect_semantic_decl().unwrap().as_Let().unwrap();
 
     assert_eq!(s.ident, "v");
 
@@ -88,50 +91,656 @@ fn test_parse_let() {
     let f2 = arith.params.fields.pop().unwrap();
     let f1 = arith.params.fields.pop().unwrap();
 
-    assert_eq!(arith.func.as_Ident().unwrap(), BinaryOperator::Add.to_literal());
-    assert_eq!(f1.expr.as_Nat().unwrap().val, 1);
-    assert_eq!(f2.expr.as_Nat().unwrap().val, 2);
+    assert_eq!(arith.func.as_Ident().unwrap().lit, BinaryOperator::Add.to_literal());
+    assert_eq!(f1.expr.as_Nat().unwrap().val, 1u32.into());
+    assert_eq!(f2.expr.as_Nat().unwrap().val, 2u32.into());
 }
 
 #[test]
 fn test_parse_func() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         fun (a: Nat, b: Nat) -> Nat {
             let v = a + b;
             v
         }
-    ").unwrap().expect_semantic_expr().unwrap().as_Func().unwrap();
+    ")).unwrap().expect_semantic_expr().unwrap().as_Func().unwrap();
 
-    let result = s.block.stmts.pop().unwrap().as_Expr().unwrap().as_Ident().unwrap();
+    let result = s.block.stmts.pop().unwrap().as_Expr().unwrap().as_Ident().unwrap().lit;
 
     assert_eq!(result, "v");
 
     let let_decl = s.block.stmts.pop().unwrap().as_Decl().unwrap().as_Let().unwrap();
+
+    assert_eq!(let_decl.ident, "v");
 }
 
 #[test]
 fn test_parse_apply() {
-    let mut s = grammar::ItemParser::new().parse("
+    let s = grammar::ItemParser::new().parse(Lexer::new("
         Invoke(1, 2, 3 + 4)
-    ").unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
+    ")).unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
 
-    assert_eq!(s.func.as_Ident().unwrap(), "Invoke");
+    assert_eq!(s.func.as_Ident().unwrap().lit, "Invoke");
 }
 
 #[test]
 fn test_parse_match() {
-    let mut s = grammar::ItemParser::new().parse("
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
         match nat {
             1 => 2,
             3 => 4
         }
-    ").unwrap().expect_semantic_expr().unwrap().as_Match().unwrap();
+    ")).unwrap().expect_semantic_expr().unwrap().as_Match().unwrap();
+
+    assert_eq!(s.expr.as_Ident().unwrap().lit, "nat");
 
     let c2 = s.cases.pop().unwrap();
     let c1 = s.cases.pop().unwrap();
 
-    assert_eq!(c1.pattern.as_Nat().unwrap().val, 1);
-    assert_eq!(c2.pattern.as_Nat().unwrap().val, 3);
-    assert_eq!(c1.expr.as_Nat().unwrap().val, 2);
-    assert_eq!(c2.expr.as_Nat().unwrap().val, 4);
+    assert_eq!(c1.pattern.as_Nat().unwrap().val, 1u32.into());
+    assert_eq!(c2.pattern.as_Nat().unwrap().val, 3u32.into());
+    assert_eq!(c1.expr.as_Nat().unwrap().val, 2u32.into());
+    assert_eq!(c2.expr.as_Nat().unwrap().val, 4u32.into());
+}
+
+#[test]
+fn test_parse_span() {
+    let src = "let v = a + 20";
+    let s = grammar::ItemParser::new().parse(Lexer::new(src)).unwrap().expect_semantic_decl().unwrap().as_Let().unwrap();
+
+    assert_eq!(&src[s.span.start..s.span.end], "let v = a + 20");
+
+    let mut arith = s.expr.as_Apply().unwrap();
+
+    let f2 = arith.params.fields.pop().unwrap();
+    let f1 = arith.params.fields.pop().unwrap();
+
+    assert_eq!(&src[arith.span.start..arith.span.end], "a + 20");
+    assert_eq!(&src[f1.expr.span().start..f1.expr.span().end], "a");
+    assert_eq!(&src[f2.expr.span().start..f2.expr.span().end], "20");
+}
+
+#[test]
+fn test_semantic_errors() {
+    let src = "let a = record { x: Nat };\nlet b = 1;\ntype T = 1 + 2";
+    let errors = grammar::SourceFileParser::new().parse(Lexer::new(src)).unwrap().to_semantic().unwrap_err();
+
+    assert_eq!(errors.errors.len(), 2);
+
+    match &errors.errors[0] {
+        SemanticError::UnexpectedNode { expected, found, span } => {
+            assert_eq!(*expected, Category::Expr);
+            assert_eq!(*found, "record type");
+            assert_eq!(&src[span.start..span.end], "record { x: Nat }");
+        }
+        _ => panic!(),
+    }
+
+    let report = errors.render(&Source::new("test.flow", src));
+
+    assert!(report.contains("error[E0001]: expected expression, found record type\n --> test.flow:1:9\n"));
+    assert!(report.contains("error[E0001]: expected type, found binary expression\n --> test.flow:3:10\n"));
+    assert!(report.contains("3 | type T = 1 + 2\n  |          ^^^^^ this binary expression is not a type\n"));
+}
+
+#[test]
+fn test_parse_recovery() {
+    let src = "let a = ;\nlet b = 1;\nlet c = fun () -> Nat { 2 + ; 3 };\nlet d = 4";
+    let file = grammar::SourceFileParser::new().parse(Lexer::new(src)).unwrap();
+
+    assert_eq!(file.items.len(), 4);
+    assert!(file.items[0].clone().as_Error().is_some());
+    assert!(file.items[1].clone().as_LetDecl().is_some());
+    assert!(file.items[3].clone().as_LetDecl().is_some());
+
+    let errors = file.to_semantic().unwrap_err();
+
+    assert_eq!(errors.errors.len(), 2);
+    assert_eq!(errors.errors[0].code(), "E0004");
+    assert_eq!(&src[errors.errors[1].span().start..errors.errors[1].span().end], ";");
+}
+
+#[test]
+fn test_parse_big_nat() {
+    use paracell_parser_lalrpop::flow::fmt::format;
+    use paracell_parser_lalrpop::flow::print::Print;
+    use paracell_parser_sem::span::SpanlessEq;
+
+    let parse = |lit| grammar::NatParser::new().parse(Lexer::new(lit));
+
+    let key = parse("0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF").unwrap();
+    assert_eq!(key.val.bits(), 256);
+    assert_eq!(key.val.count_ones(), 256);
+    assert_eq!(key.width, None);
+
+    let byte = parse("0xFF'8").unwrap();
+    assert_eq!((byte.val, byte.width), (0xFFu32.into(), Some(8)));
+    assert_eq!(parse("1_000'16").unwrap().val, 1000u32.into());
+
+    for lit in ["1_", "0x_F", "0xFF'0", "0xFF'08", "0xFF'", "0xFF'8'8", "0b102"] {
+        assert!(parse(lit).is_err(), "{}", lit);
+    }
+
+    let src = "let big = 0xFFFF_0000'32 + 340282366920938463463374607431768211456;";
+    let s = paracell_parser_lalrpop::flow::parse(src).unwrap();
+    let printed = Print(&s).to_string();
+
+    assert!(printed.starts_with("let big = 4294901760'32 + 340282366920938463463374607431768211456;"), "{}", printed);
+    assert!(s.spanless_eq(&paracell_parser_lalrpop::flow::parse(&printed).unwrap()), "{}", printed);
+    assert_eq!(format(src).unwrap(), format!("{}\n", src));
+
+    let src = "let bad = 12'x;";
+    let errors = paracell_parser_lalrpop::flow::parse(src).unwrap_err();
+
+    assert_eq!(errors.errors.len(), 1);
+    assert_eq!(errors.errors[0].code(), "E0006");
+
+    let report = errors.render(&Source::new("test.flow", src));
+    assert!(report.contains(" --> test.flow:1:11\n"), "{}", report);
+}
+
+#[test]
+fn test_parse_while() {
+    let s = paracell_parser_lalrpop::flow::parse("
+        fun Divide(dividend: Nat, divisor: Nat) -> (Nat, Nat, Nat) {
+            var quotient = 0;
+            var remainder = dividend;
+
+            while divisor < remainder {
+                quotient = quotient + 1;
+                remainder = remainder - divisor
+            };
+
+            (0, quotient, remainder)
+        }
+    ").unwrap();
+
+    let mut f = s.decls[0].clone().as_Let().unwrap().expr.as_Func().unwrap();
+
+    assert_eq!(s.decls[0].clone().as_Let().unwrap().ident, "Divide");
+
+    f.block.stmts.pop().unwrap().as_Expr().unwrap().as_Record().unwrap();
+
+    let mut w = f.block.stmts.pop().unwrap().as_While().unwrap();
+
+    assert_eq!(w.cond.as_Apply().unwrap().func.as_Ident().unwrap().lit, BinaryOperator::Lt.to_literal());
+
+    let a2 = w.block.stmts.pop().unwrap().as_Assign().unwrap();
+    let a1 = w.block.stmts.pop().unwrap().as_Assign().unwrap();
+
+    assert_eq!(a1.target.lit, "quotient");
+    assert_eq!(a2.target.lit, "remainder");
+    assert_eq!(a2.expr.as_Apply().unwrap().func.as_Ident().unwrap().lit, BinaryOperator::Sub.to_literal());
+}
+
+#[test]
+fn test_parse_assign_target() {
+    let errors = paracell_parser_lalrpop::flow::parse("let f = fun () -> Nat { 1 + 2 = 3 }").unwrap_err();
+
+    match &errors.errors[0] {
+        SemanticError::UnexpectedNode { expected, .. } => assert_eq!(*expected, Category::Place),
+        _ => panic!(),
+    }
+}
+
+fn expect_binary(expr: paracell_parser_sem::sem::Expr, op: BinaryOperator) -> (paracell_parser_sem::sem::Expr, paracell_parser_sem::sem::Expr) {
+    let mut s = expr.as_Apply().unwrap();
+
+    assert_eq!(s.func.as_Ident().unwrap().lit, op.to_literal());
+
+    let right = s.params.fields.pop().unwrap().expr;
+    let left = s.params.fields.pop().unwrap().expr;
+    (left, right)
+}
+
+#[test]
+fn test_parse_precedence() {
+    let s = grammar::ItemParser::new().parse(Lexer::new("
+        a + b * c << 1 == d ^ e && f || g |> h
+    ")).unwrap().expect_semantic_expr().unwrap().as_Pipe().unwrap();
+
+    assert_eq!(s.to.as_Ident().unwrap().lit, "h");
+
+    let (l, r) = expect_binary(s.from, BinaryOperator::LogicOr);
+    assert_eq!(r.as_Ident().unwrap().lit, "g");
+
+    let (l, r) = expect_binary(l, BinaryOperator::LogicAnd);
+    assert_eq!(r.as_Ident().unwrap().lit, "f");
+
+    let (l, r) = expect_binary(l, BinaryOperator::Eq);
+    let (d, e) = expect_binary(r, BinaryOperator::Xor);
+    assert_eq!(d.as_Ident().unwrap().lit, "d");
+    assert_eq!(e.as_Ident().unwrap().lit, "e");
+
+    let (l, r) = expect_binary(l, BinaryOperator::Shl);
+    assert_eq!(r.as_Nat().unwrap().val, 1u32.into());
+
+    let (a, l) = expect_binary(l, BinaryOperator::Add);
+    assert_eq!(a.as_Ident().unwrap().lit, "a");
+
+    let (b, c) = expect_binary(l, BinaryOperator::Mul);
+    assert_eq!(b.as_Ident().unwrap().lit, "b");
+    assert_eq!(c.as_Ident().unwrap().lit, "c");
+
+    // A trailing `,` makes a record of one field instead.
+    let expr = grammar::ItemParser::new().parse(Lexer::new("(a + b,)")).unwrap().expect_semantic_expr().unwrap();
+
+    assert_eq!(expr.as_Record().unwrap().fields.len(), 1);
+}
+
+#[test]
+fn test_parse_operators() {
+    let ops = [
+        ("and", BinaryOperator::And),
+        ("or", BinaryOperator::Or),
+        ("^", BinaryOperator::Xor),
+        ("<<", BinaryOperator::Shl),
+        (">>", BinaryOperator::Shr),
+        ("==", BinaryOperator::Eq),
+        ("!=", BinaryOperator::Ne),
+        ("<", BinaryOperator::Lt),
+        ("<=", BinaryOperator::Le),
+        (">", BinaryOperator::Gt),
+        (">=", BinaryOperator::Ge),
+        ("&&", BinaryOperator::LogicAnd),
+        ("||", BinaryOperator::LogicOr),
+    ];
+
+    for (lit, op) in ops {
+        let expr = grammar::ItemParser::new().parse(Lexer::new(&format!("x {} y", lit))).unwrap().expect_semantic_expr().unwrap();
+        let (x, y) = expect_binary(expr, op);
+        assert_eq!(x.as_Ident().unwrap().lit, "x");
+        assert_eq!(y.as_Ident().unwrap().lit, "y");
+    }
+}
+
+#[test]
+fn test_parse_comparison_non_assoc() {
+    assert!(grammar::ItemParser::new().parse(Lexer::new("a < b < c")).is_err());
+    assert!(grammar::ItemParser::new().parse(Lexer::new("(a < b) == (b < c)")).is_ok());
+}
+
+#[test]
+fn test_parse_grouping() {
+    let expr = grammar::ItemParser::new().parse(Lexer::new("(a + b) * c")).unwrap().expect_semantic_expr().unwrap();
+    let (l, c) = expect_binary(expr, BinaryOperator::Mul);
+    let (a, b) = expect_binary(l, BinaryOperator::Add);
+
+    assert_eq!(a.as_Ident().unwrap().lit, "a");
+    assert_eq!(b.as_Ident().unwrap().lit, "b");
+    assert_eq!(c.as_Ident().unwrap().lit, "c");
+}
+
+#[test]
+fn test_parse_pattern() {
+    let mut s = grammar::ItemParser::new().parse(Lexer::new("
+        match v {
+            Op::Add => 1,
+            Op::Mul(a, _) => a,
+            Op::Neg(x) => x,
+            (q, r) => q,
+            (hi: h, lo: 0) => h,
+            0..=7 | 9 => 2,
+            10..16 => 3,
+            n => n
+        }
+    ")).unwrap().expect_semantic_expr().unwrap().as_Match().unwrap();
+
+    let c8 = s.cases.pop().unwrap().pattern;
+    let c7 = s.cases.pop().unwrap().pattern;
+    let c6 = s.cases.pop().unwrap().pattern;
+    let c5 = s.cases.pop().unwrap().pattern;
+    let c4 = s.cases.pop().unwrap().pattern;
+    let c3 = s.cases.pop().unwrap().pattern;
+    let c2 = s.cases.pop().unwrap().pattern;
+    let c1 = s.cases.pop().unwrap().pattern;
+
+    let v1 = c1.as_Variant().unwrap();
+    assert_eq!(v1.path.segments, vec!["Op", "Add"]);
+    assert!(v1.payload.is_none());
+
+    let mut v2 = c2.as_Variant().unwrap().payload.unwrap().as_Record().unwrap();
+    let wildcard = v2.fields.pop().unwrap();
+    let bind = v2.fields.pop().unwrap();
+    assert_eq!(bind.ident, "0");
+    assert_eq!(bind.pattern.as_Bind().unwrap().lit, "a");
+    assert!(wildcard.pattern.as_Wildcard().is_some());
+
+    let v3 = c3.as_Variant().unwrap();
+    assert_eq!(v3.payload.unwrap().as_Bind().unwrap().lit, "x");
+
+    let mut t = c4.as_Record().unwrap();
+    assert_eq!(t.fields.pop().unwrap().ident, "1");
+
+    let mut t = c5.as_Record().unwrap();
+    let lo = t.fields.pop().unwrap();
+    assert_eq!(lo.ident, "lo");
+    assert_eq!(lo.pattern.as_Nat().unwrap().val, 0u32.into());
+
+    let mut or = c6.as_Or().unwrap();
+    assert_eq!(or.alts.pop().unwrap().as_Nat().unwrap().val, 9u32.into());
+    let range = or.alts.pop().unwrap().as_Range().unwrap();
+    assert_eq!((range.lo.val, range.hi.val, range.inclusive), (0u32.into(), 7u32.into(), true));
+
+    let range = c7.as_Range().unwrap();
+    assert_eq!((range.lo.val, range.hi.val, range.inclusive), (10u32.into(), 16u32.into(), false));
+
+    assert_eq!(c8.as_Bind().unwrap().lit, "n");
+}
+
+#[test]
+fn test_parse_path_expr() {
+    let s = grammar::ItemParser::new().parse(Lexer::new("
+        Op::Mul(1, 2)
+    ")).unwrap().expect_semantic_expr().unwrap().as_Apply().unwrap();
+
+    assert_eq!(s.func.as_Path().unwrap().segments, vec!["Op", "Mul"]);
+}
+
+#[test]
+fn test_parse_pattern_mixed_fields() {
+    let errors = grammar::ItemParser::new().parse(Lexer::new("
+        match v {
+            (a: x, y) => x
+        }
+    ")).unwrap().expect_semantic_expr().unwrap_err();
+
+    assert_eq!(errors.errors[0].code(), "E0008");
+}
+
+#[test]
+fn test_parse_match_scrutinee() {
+    let src = "
+        match a + b {
+            0 => 1,
+            _ => 2
+        }
+    ";
+    let s = grammar::ItemParser::new().parse(Lexer::new(src)).unwrap().expect_semantic_expr().unwrap().as_Match().unwrap();
+
+    assert_eq!(&src[s.expr.span().start..s.expr.span().end], "a + b");
+
+    let (a, b) = expect_binary(s.expr, BinaryOperator::Add);
+
+    assert_eq!(a.as_Ident().unwrap().lit, "a");
+    assert_eq!(b.as_Ident().unwrap().lit, "b");
+    assert_eq!(s.cases.len(), 2);
+}
+
+#[test]
+fn test_parse_comments() {
+    // The sample from README.md, verbatim.
+    let s = paracell_parser_lalrpop::flow::parse("
+        // Combinational logic.
+        fun ALU(a: Nat, b: Nat, op: Op) -> Nat {
+            match op {
+                Op::Add => a + b,
+                Op::Sub => a - b,
+                Op::Mul => a * b
+            }
+        }
+
+        // Sequential logic.
+        fun Divide(dividend: Nat, divisor: Nat) -> (Nat, Nat, Nat) {
+            match divisor {
+                0 => (1, 0, 0), // Invalid divisor.
+                _ => {
+                    var quotient = 0;
+                    var remainder = dividend;
+
+                    // FSM must be guaranteed to halt.
+                    while divisor < remainder {
+                        quotient = quotient + 1;
+                        remainder = remainder - divisor;
+                    };
+
+                    (0, quotient, remainder)
+                }
+            }
+        }
+    ").unwrap();
+
+    assert_eq!(s.decls.len(), 2);
+    assert_eq!(s.decls[1].clone().as_Let().unwrap().ident, "Divide");
+
+    let s = paracell_parser_lalrpop::flow::parse("let a = /* outer /* inner */ still outer */ 1").unwrap();
+
+    assert_eq!(s.decls[0].clone().as_Let().unwrap().expr.as_Nat().unwrap().val, 1u32.into());
+
+    let errors = paracell_parser_lalrpop::flow::parse("let a = 1 /* /* */").unwrap_err();
+
+    assert_eq!(errors.errors[0].code(), "E0009");
+}
+
+#[test]
+fn test_parse_doc_comment() {
+    let s = paracell_parser_lalrpop::flow::parse("
+        /// Arithmetic unit.
+        /// Wraps on overflow.
+        fun ALU(a: Nat, b: Nat) -> Nat { a + b }
+
+        //// Not a doc comment.
+        type Op = union { Add: (), Sub: () };
+
+        /// Width of the datapath.
+        let width = 32
+    ").unwrap();
+
+    assert_eq!(s.decls[0].doc(), Some("Arithmetic unit.\nWraps on overflow."));
+    assert_eq!(s.decls[1].doc(), None);
+    assert_eq!(s.decls[2].doc(), Some("Width of the datapath."));
+}
+
+const ROUND_TRIP: &str = "
+    /// Operations of the unit.
+    ///
+    /// Add wraps on overflow.
+    type Op = union { Add: (), Mul: record { a: Nat, b: Nat }, Pair: (Nat, Nat) };
+
+    fun ALU(a: Nat, b: Nat, op: Op) -> Nat {
+        let sum = (a + b) * 2 - a % b;
+        match op {
+            Op::Add => sum << 1 >> 2,
+            Op::Mul(x, y) | Op::Pair(x, y) => x * (y - 1),
+            Op::Mul(a: 0, b: _) => !(a ^ b) and ~a or b,
+            (q: 0..8, r: 8..=15) => (a < b) == (b < a),
+            _ => a < b && b < 3 || a.x |> f
+        }
+    }
+
+    var state = (quotient: 0, remainder: 8);
+
+    let apply = fun (f: Nat, x: Nat) -> Nat {
+        var acc = x;
+        while acc < 10 {
+            acc = f(acc, 1).value;
+            fun inner(v: Nat) -> Nat { v }
+        };
+        { acc };
+        (acc, Op::Add)
+    };
+";
+
+#[test]
+fn test_print_round_trip() {
+    use paracell_parser_lalrpop::flow::print::Print;
+    use paracell_parser_sem::span::SpanlessEq;
+
+    let s = paracell_parser_lalrpop::flow::parse(ROUND_TRIP).unwrap();
+    let printed = Print(&s).to_string();
+    let reparsed = paracell_parser_lalrpop::flow::parse(&printed).unwrap();
+
+    assert!(s.spanless_eq(&reparsed), "{}", printed);
+    assert_eq!(Print(&reparsed).to_string(), printed);
+    assert!(printed.starts_with("/// Operations of the unit.\n///\n/// Add wraps on overflow.\ntype Op = union {"));
+}
+
+#[test]
+fn test_format() {
+    use paracell_parser_lalrpop::flow::fmt::format;
+
+    let messy = "/// Opcode.
+type Op=union{Add:(),Sub:(),
+  /* mul */ Mul:()};   // ops
+type P = record {a:Nat,b:Nat};
+
+
+let x=0xFF;var y = (1+2)*3 ;
+fun f(a:Nat)->Nat{ // head
+a
+// tail
+}
+fun g(v: Op) -> Nat {match v {Op::Add=>1,Op::Sub|Op::Mul=>2, _=>0x0}}
+fun e() -> Nat {}
+// end
+";
+    let formatted = "/// Opcode.
+type Op = union {
+    Add: (),
+    Sub: (),
+    /* mul */
+    Mul: ()
+}; // ops
+type P = record { a: Nat, b: Nat };
+
+let x = 0xFF;
+var y = (1 + 2) * 3;
+fun f(a: Nat) -> Nat { // head
+    a
+    // tail
+}
+fun g(v: Op) -> Nat {
+    match v {
+        Op::Add           => 1,
+        Op::Sub | Op::Mul => 2,
+        _                 => 0x0
+    }
+}
+fun e() -> Nat {}
+// end
+";
+
+    assert_eq!(format(messy).unwrap(), formatted);
+    assert_eq!(format(formatted).unwrap(), formatted);
+
+    // Already formatted, so left as is.
+    let readme = "// Sequential logic.
+fun Divide(dividend: Nat, divisor: Nat) -> (Nat, Nat, Nat) {
+    match divisor {
+        0 => (1, 0, 0), // Invalid divisor.
+        _ => {
+            var quotient = 0;
+            var remainder = dividend;
+
+            // FSM must be guaranteed to halt.
+            while divisor < remainder {
+                quotient = quotient + 1;
+                remainder = remainder - divisor;
+            };
+
+            (0, quotient, remainder)
+        }
+    }
+}
+";
+
+    assert_eq!(format(readme).unwrap(), readme);
+
+    assert_eq!(format("let r=( 1 , );").unwrap(), "let r = (1,);\n");
+
+    // Comments within an element stay next to the token before them.
+    let inline = "let x = 1 + /* c */ 2;
+let a = 1 /* x */ + /* y */ 2 // z
+    |> f;
+fun f(a: Nat, // first
+    b: Nat /* last */) -> Nat {
+    a + b
+}
+";
+
+    assert_eq!(format("let x=1+/* c */2;\nlet a=1/* x */+/* y */2// z\n|>f;\nfun f(a:Nat, // first\nb:Nat/* last */)->Nat{a+b}").unwrap(), inline);
+    assert_eq!(format(inline).unwrap(), inline);
+
+    let errors = format("let a = (1 +); let b = 2").unwrap_err();
+
+    assert_eq!(errors.errors[0].code(), "E0004");
+}
+
+#[test]
+fn test_parse_inferred() {
+    use paracell_parser_lalrpop::flow::fmt::format;
+    use paracell_parser_lalrpop::flow::print::Print;
+    use paracell_parser_sem::sem;
+    use paracell_parser_sem::span::SpanlessEq;
+
+    let src = "let v: Nat = 1;\nvar w: _ = v;\nlet add = fun (a, b: Nat) { a + b };\n";
+    let s = paracell_parser_lalrpop::flow::parse(src).unwrap();
+
+    let [sem::Decl::Let(v), sem::Decl::Var(w), sem::Decl::Let(add)] = s.decls.as_slice() else { panic!("{:?}", s.decls) };
+    let sem::Expr::Func(f) = &add.expr else { panic!("{:?}", add.expr) };
+
+    assert!(matches!(v.ty, Some(sem::Type::Ident(ref t)) if t.lit == "Nat"));
+    assert!(matches!(w.ty, Some(sem::Type::Infer(_))));
+    assert!(matches!(f.ty.params.fields[0].ty, sem::Type::Infer(_)));
+    assert!(matches!(f.ty.result, sem::Type::Infer(_)));
+
+    let printed = Print(&s).to_string();
+
+    assert!(s.spanless_eq(&paracell_parser_lalrpop::flow::parse(&printed).unwrap()), "{}", printed);
+    assert!(printed.contains("fun add(a, b: Nat) {"), "{}", printed);
+    assert_eq!(format("let v:Nat=1;var w : _ = v;").unwrap(), "let v: Nat = 1;\nvar w: _ = v;\n");
+}
+
+#[test]
+fn test_parse_width() {
+    use paracell_parser_lalrpop::flow::fmt::format;
+    use paracell_parser_lalrpop::flow::print::Print;
+    use paracell_parser_sem::sem;
+    use paracell_parser_sem::span::SpanlessEq;
+
+    let src = "fun f(a: Nat[8], b: Int[_]) -> Bits[16] { ext(a * b) }";
+    let s = paracell_parser_lalrpop::flow::parse(src).unwrap();
+
+    let [sem::Decl::Let(f)] = s.decls.as_slice() else { panic!("{:?}", s.decls) };
+    let sem::Expr::Func(f) = &f.expr else { panic!("{:?}", f.expr) };
+    let sem::Type::Apply(a) = &f.ty.params.fields[0].ty else { panic!("{:?}", f.ty.params) };
+
+    assert_eq!(a.ty.lit, "Nat");
+    assert!(matches!(a.params.as_slice(), [sem::TypeParam::Nat(v)] if v.val == 8u32.into()));
+    assert!(matches!(&f.ty.params.fields[1].ty, sem::Type::Apply(b) if matches!(b.params.as_slice(), [sem::TypeParam::Type(sem::Type::Infer(_))])));
+
+    let printed = Print(&s).to_string();
+
+    assert!(s.spanless_eq(&paracell_parser_lalrpop::flow::parse(&printed).unwrap()), "{}", printed);
+    assert!(printed.starts_with("fun f(a: Nat[8], b: Int[_]) -> Bits[16] {"), "{}", printed);
+    assert_eq!(format("let v : Nat[ 8 ]=1;").unwrap(), "let v: Nat[8] = 1;\n");
+}
+
+#[test]
+fn test_parse_generics() {
+    use paracell_parser_lalrpop::flow::fmt::format;
+    use paracell_parser_lalrpop::flow::print::Print;
+    use paracell_parser_sem::sem;
+    use paracell_parser_sem::span::SpanlessEq;
+
+    let src = "type Vec[T, N: Nat] = (T, Bits[N]);\nfun Swap[T](a: T, b: T) -> (T, T) { (b, a) }\nlet zero[N: Nat]: Bits[N] = 0;\n";
+    let s = paracell_parser_lalrpop::flow::parse(src).unwrap();
+
+    let [sem::Decl::TypeAlias(vec), sem::Decl::Let(swap), sem::Decl::Let(zero)] = s.decls.as_slice() else { panic!("{:?}", s.decls) };
+
+    assert!(matches!(vec.generics.as_slice(), [sem::GenericParam::Type(t), sem::GenericParam::Width(n)] if t.lit == "T" && n.lit == "N"));
+    assert!(matches!(swap.generics.as_slice(), [sem::GenericParam::Type(t)] if t.lit == "T"));
+    assert!(matches!(zero.generics.as_slice(), [sem::GenericParam::Width(n)] if n.lit == "N"));
+
+    let printed = Print(&s).to_string();
+
+    assert!(s.spanless_eq(&paracell_parser_lalrpop::flow::parse(&printed).unwrap()), "{}", printed);
+    assert!(printed.contains("fun Swap[T](a: T, b: T) -> (T, T) {"), "{}", printed);
+    assert_eq!(format("type Vec [T,N :Nat]=(T, Bits[N]);").unwrap(), "type Vec[T, N: Nat] = (T, Bits[N]);\n");
+
+    let errors = paracell_parser_lalrpop::flow::parse("type V[T, 8, N: Int] = T;").unwrap_err();
+    let messages: Vec<_> = errors.errors.iter().map(|error| error.to_string()).collect();
+
+    assert_eq!(messages, ["expected generic parameter, found number literal", "expected generic parameter, found named item"]);
+    assert_eq!(errors.errors[0].to_diagnostic().help, ["a generic parameter is a type `T`, or a width `N: Nat`"]);
 }
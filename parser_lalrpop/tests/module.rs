@@ -0,0 +1,114 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_parser_sem::diag::Source;
+use paracell_parser_sem::module::{LoadErrors, Program, Target, load};
+use std::collections::HashMap;
+
+/// Loads flow modules kept in memory, by file name.
+fn load_files(files: &[(&str, &str)]) -> Result<Program, LoadErrors> {
+    let files = files.iter().copied().collect::<HashMap<_, _>>();
+    load(
+        Source::new("main.flow", files["main.flow"]),
+        |path| {
+            let name = format!("{}.flow", path.segments.join("/"));
+            files.get(name.as_str()).map(|text| Source::new(name, *text))
+        },
+        |source| flow::parse(&source.text).map_err(|errors| errors.to_diagnostics()),
+    )
+}
+
+#[test]
+fn test_parse_module() {
+    let s = flow::parse("
+        /// Arithmetic.
+        mod alu;
+        import alu::ALU;
+        import bus;
+        let a = ALU(1, 2)
+    ").unwrap();
+
+    assert_eq!(s.mods[0].ident, "alu");
+    assert_eq!(s.mods[0].doc.as_deref(), Some("Arithmetic."));
+    assert_eq!(s.imports[0].path.segments, ["alu", "ALU"]);
+    assert_eq!(s.imports[0].ident(), "ALU");
+    assert_eq!(s.imports[1].ident(), "bus");
+    assert_eq!(s.decls.len(), 1);
+
+    assert_eq!(flow::fmt::format("mod  alu ;import alu :: ALU").unwrap(), "mod alu;\nimport alu::ALU;\n");
+
+    let errors = flow::parse("let a = { import alu::ALU; 1 }").unwrap_err();
+
+    assert_eq!(errors.errors[0].code(), "E0001");
+}
+
+#[test]
+fn test_load() {
+    let program = load_files(&[
+        ("main.flow", "
+            mod alu;
+            mod bus;
+            import alu::ALU;
+            import alu::adder::Add;
+            let top = ALU(1, Add(2, 3))
+        "),
+        ("alu.flow", "
+            mod adder;
+            import bus::Width;
+            fun ALU(a: Nat, b: Nat) -> Nat { a + b }
+        "),
+        ("alu/adder.flow", "fun Add(a: Nat, b: Nat) -> Nat { a + b }"),
+        ("bus.flow", "type Width = Nat"),
+    ]).unwrap();
+
+    let paths = program.modules.iter().map(|module| module.path.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(paths, ["", "alu", "bus", "alu::adder"]);
+
+    let names = program.decls().map(|(name, _)| name).collect::<Vec<_>>();
+
+    assert_eq!(names, ["top", "alu::ALU", "bus::Width", "alu::adder::Add"]);
+
+    let root = &program.modules[0];
+
+    assert_eq!(root.scope["ALU"], Target::Decl { module: 1, decl: 0 });
+    assert_eq!(program.qualified_name(root.scope["Add"]), "alu::adder::Add");
+    assert_eq!(root.scope["bus"], Target::Module(2));
+    assert_eq!(program.qualified_name(program.modules[1].scope["Width"]), "bus::Width");
+    assert_eq!(program.qualified_name(program.modules[1].scope["adder"]), "alu::adder");
+}
+
+#[test]
+fn test_load_errors() {
+    let errors = load_files(&[
+        ("main.flow", "mod alu;\nmod broken;\nmod missing;\nimport alu::Sub;\nimport alu::ALU::x;\nimport alu::ALU;\nlet ALU = 1"),
+        ("alu.flow", "let ALU = 1"),
+        ("broken.flow", "let b = (1 +)"),
+    ]).unwrap_err();
+
+    let codes = errors.errors.iter().map(|(module, diag)| (*module, diag.code)).collect::<Vec<_>>();
+
+    assert_eq!(codes, [(2, "E0004"), (0, "E0011"), (0, "E0012"), (0, "E0012"), (0, "E0013")]);
+    assert_eq!(errors.errors[3].1.labels[0].message, "`ALU` in `alu` is not a module");
+
+    let rendered = errors.render();
+
+    assert!(rendered.contains("--> broken.flow:1:13"), "{}", rendered);
+    assert!(rendered.contains("--> main.flow:3:1"), "{}", rendered);
+}
+
+#[test]
+fn test_load_dir() {
+    let dir = std::env::temp_dir().join(format!("paracell_load_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("alu")).unwrap();
+    std::fs::write(dir.join("alu.flow"), "mod adder;").unwrap();
+    std::fs::write(dir.join("alu/adder.flow"), "let zero = 0").unwrap();
+
+    let program = flow::load(Source::new("main.flow", "mod alu; import alu::adder::zero;"), &dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let program = program.unwrap();
+
+    assert_eq!(program.qualified_name(program.modules[0].scope["zero"]), "alu::adder::zero");
+}
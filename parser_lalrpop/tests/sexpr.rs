@@ -4,6 +4,7 @@
 
 use paracell_parser_lalrpop::sexpr::ast::*;
 use paracell_parser_lalrpop::sexpr::grammar;
+use paracell_parser_sem::span::SpanlessEq;
 
 #[test]
 fn test_parse_item() {
@@ -26,4 +27,295 @@ fn test_parse_item() {
 
     assert_eq!(field1.ty.as_Ident().unwrap().lit, "Nat");
     assert_eq!(field2.ty.as_Ident().unwrap().lit, "Nat");
-}
\ No newline at end of file
+}
+#[test]
+fn test_parse_comments() {
+    let v = grammar::ItemParser::new().parse("
+        ; A pair of naturals.
+        (record
+            (field A Nat) ; first
+            (field B Nat))
+    ").unwrap().as_List().unwrap();
+
+    assert_eq!(v.items.len(), 2);
+}
+
+#[test]
+fn test_parse_doc_comment() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let s = sexpr::parse("
+        ;;; Arithmetic.
+        (mod alu)
+
+        ;;;; Not a doc comment.
+        ; Nor this.
+        ;;; Operations of the unit.
+        ;;;
+        ;;; Add wraps on overflow.
+        (type Op (union (variant Add (record))))
+
+        (let x 1)
+    ").unwrap();
+
+    assert_eq!(s.mods[0].doc.as_deref(), Some("Arithmetic."));
+    assert_eq!(s.decls[0].doc(), Some("Operations of the unit.\n\nAdd wraps on overflow."));
+    assert_eq!(s.decls[1].doc(), None);
+
+    // Across frontends, in both directions.
+    let f = flow::parse(&flow::print::Print(&s).to_string()).unwrap();
+    let printed = sexpr::print::Print(&f).to_string();
+
+    assert!(f.spanless_eq(&s));
+    assert!(sexpr::parse(&printed).unwrap().spanless_eq(&s), "{}", printed);
+    assert!(printed.contains(";;; Operations of the unit.\n;;;\n;;; Add wraps on overflow.\n(type Op"), "{}", printed);
+}
+
+#[test]
+fn test_lower_matches_flow() {
+    let flow = paracell_parser_lalrpop::flow::parse("
+        type Op = union { Add: (), Mul: record { a: Nat, b: Nat } };
+
+        fun ALU(a: Nat, b: Nat, op: Op) -> Nat {
+            let sum = a + b;
+            match op {
+                Op::Add => sum,
+                Op::Mul(x, y) | Op::Shift(x, y) => x * y,
+                (q: 0, r: n) => ~n,
+                _ => !(a ^ b)
+            }
+        }
+
+        let eight = ALU(1, 0x7, Op::Add);
+        var state = (quotient: 0, remainder: 8);
+        let pair = (a, 1 << 2)
+    ").unwrap();
+
+    let sexpr = paracell_parser_lalrpop::sexpr::parse("
+        (type Op (union
+            (variant Add (record))
+            (variant Mul (record (field a Nat) (field b Nat)))))
+
+        (let ALU (func (record (field a Nat) (field b Nat) (field op Op)) Nat
+            (let sum (+ a b))
+            (return (match op
+                (case Op::Add sum)
+                (case (or (apply Op::Mul x y) (apply Op::Shift x y)) (* x y))
+                (case (record (field q 0) (field r n)) (~ n))
+                (case _ (! (^ a b)))))))
+
+        (let eight (apply ALU 1 0x7 Op::Add))
+        (var state (record (field quotient 0) (field remainder 8)))
+        (let pair (record a (<< 1 2)))
+    ").unwrap();
+
+    assert_eq!(flow.decls.len(), 5);
+    assert!(flow.spanless_eq(&sexpr));
+
+    let other = paracell_parser_lalrpop::sexpr::parse("(let eight (apply ALU 1 0x8 Op::Add))").unwrap();
+
+    assert!(!flow.decls[2].spanless_eq(&other.decls[0]));
+}
+
+#[test]
+fn test_lower_sequential_matches_flow() {
+    let flow = paracell_parser_lalrpop::flow::parse("
+        type Pair = (Nat, Nat);
+
+        let Count = fun (f: Nat, x: Nat) -> Nat {
+            var acc = x;
+            while acc < 10 {
+                acc = f(acc, 1).value
+            };
+            match acc |> f {
+                0..=7 => { ~acc },
+                0..8 => 1,
+                _ => 0
+            }
+        }
+    ").unwrap();
+
+    let sexpr = paracell_parser_lalrpop::sexpr::parse("
+        (type Pair (record Nat Nat))
+
+        (let Count (func (record (field f Nat) (field x Nat)) Nat
+            (var acc x)
+            (while (< acc 10)
+                (set acc (select (apply f acc 1) value)))
+            (match (pipe acc f)
+                (case (range_inclusive 0 7) (block (~ acc)))
+                (case (range 0 8) 1)
+                (case _ 0))))
+    ").unwrap();
+
+    assert!(flow.spanless_eq(&sexpr));
+}
+
+#[test]
+fn test_lower_errors() {
+    let errors = paracell_parser_lalrpop::sexpr::parse("
+        (let f (func (record) Nat (return 1) 2))
+        (let u (union (variant A Nat)))
+        (let r (record (field a 1) 2))
+    ").unwrap_err();
+
+    let codes = errors.errors.iter().map(|error| error.code()).collect::<Vec<_>>();
+
+    assert_eq!(codes, vec!["E0010", "E0001", "E0008"]);
+}
+
+#[test]
+fn test_print_round_trip() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let src = "
+        (mod alu)
+        (import alu::adder::Add)
+
+        (type Op (union (variant Add (record)) (variant Pair (record Nat Nat))))
+
+        (let run (func (record (field f Nat) (field x Nat)) Nat
+            (var acc x)
+            (while (< acc 10)
+                (set acc (select (apply f acc 1) value)))
+            (match (pipe acc f)
+                (case (or (apply Op::Pair a 0) (range_inclusive 1 7)) (block (~ a)))
+                (case (record (field q _)) (- (* q 2) 1))
+                (case _ (record acc Op::Add)))))
+    ";
+    let s = sexpr::parse(src).unwrap();
+
+    let printed = sexpr::print::Print(&s).to_string();
+    let reparsed = sexpr::parse(&printed).unwrap();
+
+    assert!(s.spanless_eq(&reparsed), "{}", printed);
+    assert_eq!(sexpr::print::Print(&reparsed).to_string(), printed);
+
+    // Across frontends, in both directions.
+    let as_flow = flow::print::Print(&s).to_string();
+    let from_flow = flow::parse(&as_flow).unwrap();
+
+    assert!(s.spanless_eq(&from_flow), "{}", as_flow);
+    assert!(sexpr::parse(&sexpr::print::Print(&from_flow).to_string()).unwrap().spanless_eq(&s));
+}
+
+#[test]
+fn test_print_flow_forms() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    // One-field records, function types, and functions bound with a type.
+    let src = "
+        (type U (union (variant F (func (record (field a Nat)) Nat)) (variant G (record Nat))))
+
+        (let r (record 1))
+        (let f (func (record (field x Nat)) Nat) (func (record (field x Nat)) Nat x))
+
+        (let h (func (record (field u U)) (func (record) Nat)
+            (match u
+                (case (record y) (func (record) Nat y))
+                (case (apply U::G z) (func (record) Nat z))
+                (case (apply U::G (record w)) (func (record) Nat w))
+                (case _ (func (record) Nat 0)))))
+    ";
+    let s = sexpr::parse(src).unwrap();
+
+    let as_flow = flow::print::Print(&s).to_string();
+    let from_flow = flow::parse(&as_flow).unwrap();
+
+    assert!(s.spanless_eq(&from_flow), "{}", as_flow);
+    assert!(sexpr::parse(&sexpr::print::Print(&from_flow).to_string()).unwrap().spanless_eq(&s));
+    assert!(as_flow.contains("let r = (1,);"), "{}", as_flow);
+    assert!(as_flow.contains("(y,) =>"), "{}", as_flow);
+    assert!(as_flow.contains("U::G(z) =>"), "{}", as_flow);
+    assert!(as_flow.contains("U::G(w,) =>"), "{}", as_flow);
+    assert!(as_flow.contains("let f: (x: Nat) -> Nat = fun (x: Nat) -> Nat {"), "{}", as_flow);
+    assert!(as_flow.contains("fun h(u: U) -> () -> Nat {"), "{}", as_flow);
+}
+
+#[test]
+fn test_print_keyword_names() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let f = flow::parse("
+        type field = record { case: Nat };
+
+        fun apply[generic](set: generic, block: field) -> Nat {
+            var range = block.case;
+            range = select(set);
+            match range {
+                pipe => pipe |> func,
+                block::set(variant) => variant
+            }
+        }
+    ").unwrap();
+
+    let printed = sexpr::print::Print(&f).to_string();
+    let reparsed = sexpr::parse(&printed).unwrap();
+
+    assert!(f.spanless_eq(&reparsed), "{}", printed);
+    assert!(printed.contains("(set |range| (apply |select| |set|))"), "{}", printed);
+    assert!(printed.contains("(case (apply |block|::|set| |variant|) |variant|)"), "{}", printed);
+}
+
+#[test]
+fn test_parse_inferred() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let src = "
+        (let v Nat 1)
+        (var w _ v)
+        (let add (func (record (field a _) (field b Nat)) _ (+ a b)))
+    ";
+    let s = sexpr::parse(src).unwrap();
+    let printed = sexpr::print::Print(&s).to_string();
+
+    assert!(s.spanless_eq(&sexpr::parse(&printed).unwrap()), "{}", printed);
+    assert!(s.spanless_eq(&flow::parse("let v: Nat = 1; var w: _ = v; let add = fun (a, b: Nat) { a + b };").unwrap()));
+}
+
+#[test]
+fn test_parse_width() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let src = "(let f (func (record (field a (apply Nat 8)) (field b (apply Int _))) (apply Bits 16) (apply ext (* a b))))";
+    let s = sexpr::parse(src).unwrap();
+    let printed = sexpr::print::Print(&s).to_string();
+
+    assert!(s.spanless_eq(&sexpr::parse(&printed).unwrap()), "{}", printed);
+    assert!(s.spanless_eq(&flow::parse("fun f(a: Nat[8], b: Int[_]) -> Bits[16] { ext(a * b) }").unwrap()));
+}
+
+#[test]
+fn test_parse_big_nat() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let src = "(let k (+ 0xFFFF_0000'32 0x1_0000_0000_0000_0000_0000_0000_0000_0000))";
+    let s = sexpr::parse(src).unwrap();
+    let printed = sexpr::print::Print(&s).to_string();
+
+    assert_eq!(printed, "(let k (+ 4294901760'32 340282366920938463463374607431768211456))\n");
+    assert!(s.spanless_eq(&sexpr::parse(&printed).unwrap()), "{}", printed);
+    assert!(s.spanless_eq(&flow::parse("let k = 0xFFFF_0000'32 + 0x1_0000_0000_0000_0000_0000_0000_0000_0000;").unwrap()));
+}
+
+#[test]
+fn test_parse_generics() {
+    use paracell_parser_lalrpop::{flow, sexpr};
+
+    let src = "
+        (type Vec (generic T (field N Nat)) (record (field elems T) (field len (apply Nat N))))
+        (let First (generic T (field N Nat)) (func (record (field v (apply Vec T N))) T (select v elems)))
+    ";
+    let s = sexpr::parse(src).unwrap();
+    let printed = sexpr::print::Print(&s).to_string();
+
+    assert!(s.spanless_eq(&sexpr::parse(&printed).unwrap()), "{}", printed);
+    assert!(s.spanless_eq(&flow::parse("
+        type Vec[T, N: Nat] = record { elems: T, len: Nat[N] };
+        fun First[T, N: Nat](v: Vec[T, N]) -> T { v.elems }
+    ").unwrap()));
+
+    let errors = sexpr::parse("(type V (generic 8) Nat)").unwrap_err();
+
+    assert_eq!(errors.errors[0].to_string(), "expected generic parameter, found number literal");
+}
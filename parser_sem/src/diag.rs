@@ -0,0 +1,206 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use crate::span::Span;
+use std::fmt;
+use std::fmt::Write;
+
+/// A named source text with a line table for offset to line/column lookup.
+#[derive(Clone, Debug)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+    line_starts: Vec<usize>,
+}
+
+impl Source {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Source {
+        let text = text.into();
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Source {
+            name: name.into(),
+            text,
+            line_starts,
+        }
+    }
+
+    /// 1-based line and column of a byte offset. Columns count characters, not bytes.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.text.len());
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let col = self.text[self.line_starts[line]..offset].chars().count();
+        (line + 1, col + 1)
+    }
+
+    /// Text of a 1-based line without its line terminator.
+    pub fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = match self.line_starts.get(line) {
+            Some(next) => *next,
+            None => self.text.len(),
+        };
+        self.text[start..end].trim_end_matches(['\n', '\r'])
+    }
+
+    pub fn slice(&self, span: Span) -> &str {
+        &self.text[span.start.min(self.text.len())..span.end.min(self.text.len())]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// An owned, renderable report about a span of source code.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, code, message)
+    }
+
+    /// Attaches the label the report is about. The first primary label decides the reported location.
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: true });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label { span, message: message.into(), primary: false });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).or(self.labels.first()).map(|label| label.span)
+    }
+
+    /// Renders the diagnostic as a code frame:
+    ///
+    /// ```text
+    /// error[E0001]: expected type, found number literal
+    ///  --> alu.flow:2:8
+    ///   |
+    /// 2 |     a: 1,
+    ///   |        ^ not a type
+    ///   |
+    ///   = help: ...
+    /// ```
+    pub fn render(&self, source: &Source) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}[{}]: {}", self.severity, self.code, self.message);
+
+        let mut lines = self.labels.iter().map(|label| {
+            (source.line_col(label.span.start).0, label)
+        }).collect::<Vec<_>>();
+        lines.sort_by_key(|(line, label)| (*line, !label.primary));
+
+        let gutter = lines.iter().map(|(line, _)| line.to_string().len()).max().unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        if let Some(span) = self.span() {
+            let (line, col) = source.line_col(span.start);
+            let _ = writeln!(out, "{pad}--> {}:{}:{}", source.name, line, col);
+        }
+
+        if !lines.is_empty() {
+            let _ = writeln!(out, "{pad} |");
+        }
+
+        let mut last = None;
+        for (line, label) in &lines {
+            if last != Some(*line) {
+                if let Some(last) = last
+                    && *line > last + 1
+                {
+                    let _ = writeln!(out, "{pad} ...");
+                }
+                let _ = writeln!(out, "{:>gutter$} | {}", line, source.line(*line));
+                last = Some(*line);
+            }
+
+            let text = source.line(*line);
+            let (_, col) = source.line_col(label.span.start);
+            let (end_line, end_col) = source.line_col(label.span.end);
+            let width = if end_line == *line {
+                end_col.saturating_sub(col).max(1)
+            } else {
+                text.chars().count().saturating_sub(col - 1).max(1)
+            };
+            let mark = if label.primary { "^" } else { "-" };
+            let _ = write!(out, "{pad} | {}{}", " ".repeat(col - 1), mark.repeat(width));
+            if !label.message.is_empty() {
+                let _ = write!(out, " {}", label.message);
+            }
+            out.push('\n');
+        }
+
+        if !self.help.is_empty() {
+            let _ = writeln!(out, "{pad} |");
+        }
+        for help in &self.help {
+            let _ = writeln!(out, "{pad} = help: {}", help);
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Renders every diagnostic in order, separated by blank lines.
+pub fn render_all(diags: &[Diagnostic], source: &Source) -> String {
+    diags.iter().map(|diag| diag.render(source)).collect::<Vec<_>>().join("\n")
+}
@@ -2,4 +2,10 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
+// Lets derives that name `paracell_parser_sem::..` expand inside this crate too.
+extern crate self as paracell_parser_sem;
+
+pub mod diag;
+pub mod module;
 pub mod sem;
+pub mod span;
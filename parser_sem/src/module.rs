@@ -0,0 +1,203 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Programs made of modules, one per file, with the imports between them resolved.
+//!
+//! The root module declares its children with `mod`, and they may declare their own.
+//! An `import` path starts from the root module.
+
+use crate::diag::{Diagnostic, Source};
+use crate::sem::{Decl, ModulePath, SourceFile};
+use crate::span::Span;
+use std::collections::HashMap;
+
+/// What a name visible at the top level of a module stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Declaration `decl` of module `module`.
+    Decl { module: usize, decl: usize },
+    Module(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Module {
+    /// Empty for the root module. Spans the `mod` declaration in the parent otherwise.
+    pub path: ModulePath,
+    pub source: Source,
+    pub file: SourceFile,
+    /// Every name visible at the top level: declarations, child modules and imports.
+    pub scope: HashMap<String, Target>,
+}
+
+/// Modules in the order they were loaded, the root module first.
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub modules: Vec<Module>,
+}
+
+impl Program {
+    fn child(&self, module: usize, ident: &str) -> Option<usize> {
+        let parent = &self.modules[module].path.segments;
+        self.modules.iter().position(|child| {
+            child.path.segments.len() == parent.len() + 1
+                && child.path.segments.starts_with(parent)
+                && child.path.segments.last().is_some_and(|last| last == ident)
+        })
+    }
+
+    fn decl(&self, module: usize, ident: &str) -> Option<usize> {
+        self.modules[module].file.decls.iter().position(|decl| decl.ident() == ident)
+    }
+
+    /// Follows a path from the root module, or describes the segment it fails at.
+    pub fn lookup(&self, segments: &[String]) -> Result<Target, String> {
+        let mut module = 0;
+        for (i, segment) in segments.iter().enumerate() {
+            let last = i + 1 == segments.len();
+            if let Some(child) = self.child(module, segment) {
+                if last {
+                    return Ok(Target::Module(child));
+                }
+                module = child;
+                continue;
+            }
+            let within = match i {
+                0 => "the root module".to_string(),
+                _ => format!("`{}`", segments[..i].join("::")),
+            };
+            return match self.decl(module, segment) {
+                Some(decl) if last => Ok(Target::Decl { module, decl }),
+                Some(_) => Err(format!("`{}` in {} is not a module", segment, within)),
+                None => Err(format!("no `{}` in {}", segment, within)),
+            };
+        }
+        Ok(Target::Module(module))
+    }
+
+    /// `alu::ALU` for a declaration, `alu` for a module.
+    pub fn qualified_name(&self, target: Target) -> String {
+        match target {
+            Target::Decl { module, decl } => {
+                let mut segments = self.modules[module].path.segments.clone();
+                segments.push(self.modules[module].file.decls[decl].ident().to_string());
+                segments.join("::")
+            }
+            Target::Module(module) => self.modules[module].path.to_string(),
+        }
+    }
+
+    /// Every top-level declaration of every module, with its qualified name.
+    pub fn decls(&self) -> impl Iterator<Item = (String, &Decl)> {
+        self.modules.iter().enumerate().flat_map(move |(module, m)| {
+            m.file.decls.iter().enumerate().map(move |(decl, v)| (self.qualified_name(Target::Decl { module, decl }), v))
+        })
+    }
+}
+
+/// Every error found while loading, each with the index of the module whose source it points into.
+#[derive(Clone, Debug)]
+pub struct LoadErrors {
+    pub program: Program,
+    pub errors: Vec<(usize, Diagnostic)>,
+}
+
+impl LoadErrors {
+    pub fn render(&self) -> String {
+        self.errors.iter()
+            .map(|(module, diag)| diag.render(&self.program.modules[*module].source))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Loads the root module and every module it declares, transitively, then resolves the imports.
+///
+/// `read` finds the source of a module by its path, and `parse` lowers a source to `sem`.
+pub fn load(
+    root: Source,
+    mut read: impl FnMut(&ModulePath) -> Option<Source>,
+    mut parse: impl FnMut(&Source) -> Result<SourceFile, Vec<Diagnostic>>,
+) -> Result<Program, LoadErrors> {
+    let mut program = Program { modules: Vec::new() };
+    let mut errors = Vec::new();
+
+    let mut push = |program: &mut Program, errors: &mut Vec<(usize, Diagnostic)>, path, source| {
+        let module = program.modules.len();
+        let file = parse(&source).unwrap_or_else(|diags| {
+            errors.extend(diags.into_iter().map(|diag| (module, diag)));
+            SourceFile::default()
+        });
+        program.modules.push(Module { path, source, file, scope: HashMap::new() });
+    };
+
+    push(&mut program, &mut errors, ModulePath { segments: Vec::new(), span: Span::new(0, 0) }, root);
+
+    let mut module = 0;
+    while module < program.modules.len() {
+        for decl in program.modules[module].file.mods.clone() {
+            if let Some(child) = program.child(module, &decl.ident) {
+                errors.push((module, defined_twice(&decl.ident, decl.span, program.modules[child].path.span)));
+                continue;
+            }
+            let mut segments = program.modules[module].path.segments.clone();
+            segments.push(decl.ident.clone());
+            let path = ModulePath { segments, span: decl.span };
+            match read(&path) {
+                Some(source) => push(&mut program, &mut errors, path, source),
+                None => errors.push((module, Diagnostic::error("E0011", format!("cannot find module `{}`", path))
+                    .with_primary(decl.span, "no source for this module"))),
+            }
+        }
+        module += 1;
+    }
+
+    for module in 0..program.modules.len() {
+        let mut scope = HashMap::new();
+        let mut spans = HashMap::new();
+        for (decl, v) in program.modules[module].file.decls.iter().enumerate() {
+            scope.entry(v.ident().to_string()).or_insert(Target::Decl { module, decl });
+            spans.entry(v.ident().to_string()).or_insert(v.span());
+        }
+        for decl in &program.modules[module].file.mods {
+            let Some(child) = program.child(module, &decl.ident) else { continue };
+            if scope.get(&decl.ident) == Some(&Target::Module(child)) {
+                continue;
+            }
+            if let Some(first) = spans.get(&decl.ident) {
+                errors.push((module, defined_twice(&decl.ident, decl.span, *first)));
+                continue;
+            }
+            scope.insert(decl.ident.clone(), Target::Module(child));
+            spans.insert(decl.ident.clone(), decl.span);
+        }
+        for import in &program.modules[module].file.imports {
+            let ident = import.ident();
+            match program.lookup(&import.path.segments) {
+                Ok(_) if spans.contains_key(ident) => {
+                    errors.push((module, defined_twice(ident, import.span, spans[ident])));
+                }
+                Ok(target) => {
+                    scope.insert(ident.to_string(), target);
+                    spans.insert(ident.to_string(), import.span);
+                }
+                Err(label) => errors.push((module, Diagnostic::error("E0012", format!("unresolved import `{}`", import.path))
+                    .with_primary(import.path.span, label))),
+            }
+        }
+        program.modules[module].scope = scope;
+    }
+
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(LoadErrors { program, errors })
+    }
+}
+
+fn defined_twice(ident: &str, span: Span, first: Span) -> Diagnostic {
+    Diagnostic::error("E0013", format!("`{}` is defined more than once", ident))
+        .with_primary(span, "defined again here")
+        .with_secondary(first, "first defined here")
+        .with_help("a module can give a name to one thing only")
+}
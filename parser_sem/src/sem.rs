@@ -4,113 +4,302 @@
 
 // Atoms
 
-use paracell_util_macro::AsVariant;
+use crate::span::Span;
+use num_bigint::BigUint;
+use paracell_util_macro::{AsVariant, SpanlessEq};
+use std::fmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Ident {
     pub lit: String,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+/// `255`, or `0xFF'8` given a width of 8 bits.
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Nat {
-    pub val: u128,
+    pub val: BigUint,
+    pub width: Option<u32>,
+    pub span: Span,
+}
+
+impl fmt::Display for Nat {
+    /// Decimal, followed by the width if one was given.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.val)?;
+        match self.width {
+            Some(width) => write!(f, "'{}", width),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `Op::Add`
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct Path {
+    pub segments: Vec<String>,
+    pub span: Span,
+}
+
+/// `alu::adder`, naming a module or a declaration in one from the root module of a program.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct ModulePath {
+    pub segments: Vec<String>,
+    pub span: Span,
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("::"))
+    }
 }
 
 // Types
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Field {
     pub ident: String,
     pub ty: Type,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct RecordType {
     pub fields: Vec<Field>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Variant {
     pub ident: String,
     pub ty: Type,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct UnionType {
     pub variants: Vec<Variant>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct FuncType {
     pub params: RecordType,
     pub result: Type,
+    pub span: Span,
+}
+
+/// `_`, a type left to inference.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct InferType {
+    pub span: Span,
+}
+
+/// A parameter given to a type, a width or a type.
+#[derive(Clone, Debug, SpanlessEq)]
+pub enum TypeParam {
+    Nat(Nat),
+    Type(Type),
+}
+
+impl TypeParam {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeParam::Nat(v) => v.span,
+            TypeParam::Type(v) => v.span(),
+        }
+    }
+}
+
+/// `T`, or `N: Nat`, a parameter of a generic `fun`, `let` or type alias.
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
+pub enum GenericParam {
+    /// A type.
+    Type(Ident),
+    /// A width in bits, as given to `Nat[N]`.
+    Width(Ident),
 }
 
-#[derive(Clone, Debug, AsVariant)]
+impl GenericParam {
+    pub fn ident(&self) -> &Ident {
+        match self {
+            GenericParam::Type(v) => v,
+            GenericParam::Width(v) => v,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.ident().span
+    }
+}
+
+/// `Nat[8]`, a type given parameters.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct ApplyType {
+    pub ty: Ident,
+    pub params: Vec<TypeParam>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
 pub enum Type {
-    Ident(String),
+    Ident(Ident),
+    Apply(Box<ApplyType>),
     Record(Box<RecordType>),
     Union(Box<UnionType>),
     Func(Box<FuncType>),
+    Infer(InferType),
+}
+
+impl Type {
+    pub fn span(&self) -> Span {
+        match self {
+            Type::Ident(v) => v.span,
+            Type::Apply(v) => v.span,
+            Type::Record(v) => v.span,
+            Type::Union(v) => v.span,
+            Type::Func(v) => v.span,
+            Type::Infer(v) => v.span,
+        }
+    }
 }
 
 // Expressions
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Select {
     pub expr: Expr,
     pub ident: String,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Pipe {
     pub from: Expr,
     pub to: Expr,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Block {
     pub stmts: Vec<Stmt>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Func {
     pub ty: FuncType,
     pub block: Block,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct FieldFill {
     pub ident: String,
     pub expr: Expr,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct RecordExpr {
     pub fields: Vec<FieldFill>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct ApplyExpr {
     pub func: Expr,
     pub params: RecordExpr,
+    pub span: Span,
+}
+
+// Patterns
+
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct WildcardPattern {
+    pub span: Span,
+}
+
+/// `Op::Add` or `Op::Mul(a, b)`. A payload of one positional field is the field itself.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct VariantPattern {
+    pub path: Path,
+    pub payload: Option<Pattern>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct FieldPattern {
+    pub ident: String,
+    pub pattern: Pattern,
+    pub span: Span,
+}
+
+/// `(q, r)` destructures fields `0` and `1`; `(q: a, r: b)` destructures by name.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct RecordPattern {
+    pub fields: Vec<FieldPattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct OrPattern {
+    pub alts: Vec<Pattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct RangePattern {
+    pub lo: Nat,
+    pub hi: Nat,
+    pub inclusive: bool,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
+pub enum Pattern {
+    Wildcard(WildcardPattern),
+    Nat(Nat),
+    Bind(Ident),
+    Variant(Box<VariantPattern>),
+    Record(Box<RecordPattern>),
+    Or(Box<OrPattern>),
+    Range(Box<RangePattern>),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(v) => v.span,
+            Pattern::Nat(v) => v.span,
+            Pattern::Bind(v) => v.span,
+            Pattern::Variant(v) => v.span,
+            Pattern::Record(v) => v.span,
+            Pattern::Or(v) => v.span,
+            Pattern::Range(v) => v.span,
+        }
+    }
+}
+
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Case {
-    pub pattern: Expr,
+    pub pattern: Pattern,
     pub expr: Expr,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct Match {
+    pub expr: Expr,
     pub cases: Vec<Case>,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug, AsVariant)]
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
 pub enum Expr {
     Nat(Nat),
-    Ident(String),
+    Ident(Ident),
+    Path(Path),
     Block(Box<Block>),
     Func(Box<Func>),
     Record(Box<RecordExpr>),
@@ -121,42 +310,149 @@ pub enum Expr {
     Pipe(Box<Pipe>),
 }
 
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Nat(v) => v.span,
+            Expr::Ident(v) => v.span,
+            Expr::Path(v) => v.span,
+            Expr::Block(v) => v.span,
+            Expr::Func(v) => v.span,
+            Expr::Record(v) => v.span,
+            Expr::Apply(v) => v.span,
+            Expr::Match(v) => v.span,
+            Expr::Select(v) => v.span,
+            Expr::Pipe(v) => v.span,
+        }
+    }
+}
+
 // Declarations
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct LetDecl {
+    /// Text of the `///` comments before the declaration, one line per comment.
+    pub doc: Option<String>,
     pub ident: String,
+    /// `fun Swap[T](...)`, instantiated anew wherever the declaration is named.
+    pub generics: Vec<GenericParam>,
+    /// `let v: T = ...`, checked against the type inferred for the value.
+    pub ty: Option<Type>,
     pub expr: Expr,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct VarDecl {
+    pub doc: Option<String>,
     pub ident: String,
+    pub ty: Option<Type>,
     pub expr: Expr,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SpanlessEq)]
 pub struct TypeAliasDecl {
+    pub doc: Option<String>,
     pub ident: String,
+    pub generics: Vec<GenericParam>,
     pub ty: Type,
+    pub span: Span,
 }
 
-#[derive(Clone, Debug, AsVariant)]
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
 pub enum Decl {
     Let(LetDecl),
     Var(VarDecl),
     TypeAlias(TypeAliasDecl),
 }
 
+impl Decl {
+    pub fn span(&self) -> Span {
+        match self {
+            Decl::Let(v) => v.span,
+            Decl::Var(v) => v.span,
+            Decl::TypeAlias(v) => v.span,
+        }
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        match self {
+            Decl::Let(v) => v.doc.as_deref(),
+            Decl::Var(v) => v.doc.as_deref(),
+            Decl::TypeAlias(v) => v.doc.as_deref(),
+        }
+    }
+
+    pub fn ident(&self) -> &str {
+        match self {
+            Decl::Let(v) => &v.ident,
+            Decl::Var(v) => &v.ident,
+            Decl::TypeAlias(v) => &v.ident,
+        }
+    }
+}
+
+/// `mod alu`, declaring the child module `alu` kept in a file of its own.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct ModDecl {
+    pub doc: Option<String>,
+    pub ident: String,
+    pub span: Span,
+}
+
+/// `import alu::ALU`, making the last segment of the path visible in the importing module.
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct ImportDecl {
+    pub path: ModulePath,
+    pub span: Span,
+}
+
+impl ImportDecl {
+    /// The name the import binds.
+    pub fn ident(&self) -> &str {
+        self.path.segments.last().expect("an import path has at least one segment")
+    }
+}
+
 // Statement
 
-#[derive(Clone, Debug, AsVariant)]
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct Assign {
+    pub target: Ident,
+    pub expr: Expr,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, SpanlessEq)]
+pub struct While {
+    pub cond: Expr,
+    pub block: Block,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, AsVariant, SpanlessEq)]
 pub enum Stmt {
     Decl(Decl),
     Expr(Expr),
+    Assign(Assign),
+    While(While),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Decl(v) => v.span(),
+            Stmt::Expr(v) => v.span(),
+            Stmt::Assign(v) => v.span,
+            Stmt::While(v) => v.span,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, SpanlessEq)]
 pub struct SourceFile {
+    pub mods: Vec<ModDecl>,
+    pub imports: Vec<ImportDecl>,
     pub decls: Vec<Decl>,
 }
@@ -0,0 +1,79 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use num_bigint::BigUint;
+
+/// Byte-offset range `[start, end)` into a source file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Structural equality that ignores every `span`, for comparing trees parsed from different sources.
+pub trait SpanlessEq {
+    fn spanless_eq(&self, other: &Self) -> bool;
+}
+
+impl SpanlessEq for Span {
+    fn spanless_eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_spanless_eq_by_eq {
+    ($($ty: ty),*) => {
+        $(impl SpanlessEq for $ty {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                self == other
+            }
+        })*
+    };
+}
+
+impl_spanless_eq_by_eq!(bool, u32, usize, String, BigUint);
+
+impl<T: SpanlessEq + ?Sized> SpanlessEq for Box<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        T::spanless_eq(self, other)
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Option<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.spanless_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Vec<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.spanless_eq(b))
+    }
+}
@@ -0,0 +1,1058 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Type inference and checking of lowered programs, by unification.
+//!
+//! Types the source leaves out, written `_` or omitted from a function literal, are variables
+//! found from how the values are used. The type of a `let` is generalized over the variables
+//! nothing outside it constrains, so `let id = fun (x) { x }` can be applied to any value.
+//!
+//! Widths are found the same way, except that those computed from others, as `+` gives one bit
+//! more than its wider operand, are left to be compared once what they are computed from is
+//! known. A literal of no width found gets the fewest bits that hold it.
+//!
+//! Aliases are transparent: `type Op = union { .. }` names the union, and a value of one is a
+//! value of the other. The generic parameters of a declaration stand for no type or width in
+//! particular within it, so they are only the same as themselves, and each use of the
+//! declaration gives them variables of their own, which are recorded for monomorphization.
+//! Arguments are matched to parameters by position when they are written positionally, and by
+//! name otherwise. An expression whose type could not be found is given a fresh variable, so
+//! that it is not reported again where it is used.
+
+use crate::sym::*;
+use num_bigint::BigUint;
+use paracell_parser_sem::diag::{Diagnostic, Severity};
+use paracell_parser_sem::span::Span;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+/// The types of every symbol checked, with the problems found on the way.
+#[derive(Clone, Debug, Default)]
+pub struct Typing<'a> {
+    /// With every variable found substituted. Those left are the ones a `let` is generalized over.
+    pub types: HashMap<Symbol, Type<'a>>,
+    /// What the generic parameters of a declaration stand for where it is used, by the module
+    /// and span of the use, in the order they are declared.
+    pub instances: HashMap<(usize, Span), Vec<GenericArg<'a>>>,
+    /// The type of the value each `match` matches, by the module and span of the `match`.
+    pub scrutinees: HashMap<(usize, Span), Type<'a>>,
+    /// Each with the index of the module whose source it points into.
+    pub diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl<'a> Typing<'a> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|(_, diag)| diag.severity == Severity::Error)
+    }
+}
+
+/// Checks every declaration of every module of a program.
+pub fn check<'a>(program: &Program<'a>) -> Typing<'a> {
+    let mut checker = Checker {
+        program,
+        module: 0,
+        checked: HashSet::new(),
+        pending: HashSet::new(),
+        vars: vec![None; program.vars],
+        // Variables written in the source join the level they are first checked at.
+        levels: vec![usize::MAX; program.vars],
+        level: 0,
+        schemes: HashMap::new(),
+        deferred: Vec::new(),
+        constraints: Vec::new(),
+        typing: Typing::default(),
+    };
+    for (module, m) in program.modules.iter().enumerate() {
+        checker.module = module;
+        for decl in &m.decls.vals {
+            checker.decl(decl);
+        }
+    }
+    checker.solve(true);
+    let types = std::mem::take(&mut checker.typing.types);
+    checker.typing.types = types.into_iter().map(|(sym, ty)| (sym, checker.zonk(&ty))).collect();
+    let instances = std::mem::take(&mut checker.typing.instances);
+    checker.typing.instances = instances.into_iter()
+        .map(|(at, args)| (at, args.iter().map(|arg| checker.map_arg(arg, &mut |checker, var| checker.resolve(var))).collect()))
+        .collect();
+    let scrutinees = std::mem::take(&mut checker.typing.scrutinees);
+    checker.typing.scrutinees = scrutinees.into_iter().map(|(at, ty)| (at, checker.zonk(&ty))).collect();
+    checker.typing
+}
+
+fn nat<'a>(kind: NatKind, width: Width) -> Type<'a> {
+    Type::Primitive(PrimitiveType::Nat(NatType { kind, width }))
+}
+
+fn positional<T>(idents: impl Iterator<Item = T>) -> bool
+where
+    T: AsRef<str>,
+{
+    idents.enumerate().all(|(i, ident)| ident.as_ref() == i.to_string())
+}
+
+/// A variable of any sort.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Var {
+    Type(TypeVar),
+    Width(WidthVar),
+    Kind(KindVar),
+}
+
+impl Var {
+    fn index(self) -> usize {
+        match self {
+            Var::Type(v) => v.0,
+            Var::Width(v) => v.0,
+            Var::Kind(v) => v.0,
+        }
+    }
+}
+
+/// What a variable was found to be.
+#[derive(Clone, Debug)]
+enum Bound<'a> {
+    Type(Type<'a>),
+    Width(Width),
+    Kind(NatKind),
+}
+
+/// What is left to check of a program once more of its widths are known.
+#[derive(Clone, Debug)]
+enum Constraint<'a> {
+    /// Widths `expected` and `found` were unified with, which could not be compared yet.
+    Equal {
+        widths: Vec<(Width, Width)>,
+        expected: Type<'a>,
+        found: Type<'a>,
+        span: Span,
+        because: Option<(Span, &'static str)>,
+    },
+    /// A literal, which must fit in its type.
+    Fits {
+        val: BigUint,
+        ty: NatType,
+        span: Span,
+    },
+    /// `trunc` or `ext` from one width to another.
+    Resize {
+        builtin: Builtin,
+        from: NatType,
+        to: NatType,
+        span: Span,
+    },
+}
+
+struct Checker<'p, 'a> {
+    program: &'p Program<'a>,
+    module: usize,
+    /// Declarations checked or being checked.
+    checked: HashSet<Symbol>,
+    /// Declarations whose type is being found from their initializer.
+    pending: HashSet<Symbol>,
+    /// What each variable was found to be.
+    vars: Vec<Option<Bound<'a>>>,
+    /// How many `let`s deep each variable is used. Binding a variable lowers the variables of
+    /// what it is bound to to its level, so that one deeper than a `let` is only used within it.
+    levels: Vec<usize>,
+    level: usize,
+    /// The variables the type of each `let` is generalized over.
+    schemes: HashMap<Symbol, Vec<Var>>,
+    /// Widths met while unifying two types, to be made the same once they can be compared.
+    deferred: Vec<(Width, Width)>,
+    /// With the index of the module the checking of each was left in.
+    constraints: Vec<(usize, Constraint<'a>)>,
+    typing: Typing<'a>,
+}
+
+impl<'p, 'a> Checker<'p, 'a> {
+    fn report(&mut self, diag: Diagnostic) {
+        self.typing.diagnostics.push((self.module, diag));
+    }
+
+    /// Numbers of no known kind or width are named as the unbounded `Nat` they can still be.
+    fn name(&self, ty: &Type<'a>) -> String {
+        let ty = self.map(&self.zonk(ty), &mut |_, var| match var {
+            Var::Kind(_) => Some(Bound::Kind(NatKind::Nat)),
+            Var::Width(_) => Some(Bound::Width(Width::Unbounded)),
+            Var::Type(_) => None,
+        });
+        self.program.type_name(&ty)
+    }
+
+    fn next(&mut self) -> usize {
+        self.vars.push(None);
+        self.levels.push(self.level);
+        self.vars.len() - 1
+    }
+
+    fn fresh(&mut self) -> Type<'a> {
+        Type::Var(TypeVar(self.next()))
+    }
+
+    /// A number of any kind and width.
+    fn fresh_nat(&mut self) -> NatType {
+        NatType { kind: NatKind::Var(KindVar(self.next())), width: Width::Var(WidthVar(self.next())) }
+    }
+
+    /// A variable of the same sort as `var`.
+    fn fresh_like(&mut self, var: Var) -> Bound<'a> {
+        match var {
+            Var::Type(_) => Bound::Type(self.fresh()),
+            Var::Width(_) => Bound::Width(Width::Var(WidthVar(self.next()))),
+            Var::Kind(_) => Bound::Kind(NatKind::Var(KindVar(self.next()))),
+        }
+    }
+
+    /// Follows variables to what they were found to be.
+    fn shallow(&self, ty: &Type<'a>) -> Type<'a> {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty
+            && let Some(Bound::Type(bound)) = &self.vars[var.0]
+        {
+            ty = bound.clone();
+        }
+        ty
+    }
+
+    /// What a variable was found to be, with the variables in that substituted too.
+    fn resolve(&self, var: Var) -> Option<Bound<'a>> {
+        Some(match self.vars[var.index()].as_ref()? {
+            Bound::Type(ty) => Bound::Type(self.zonk(ty)),
+            Bound::Width(width) => Bound::Width(self.map_width(width, &mut |checker, var| checker.resolve(var))),
+            Bound::Kind(kind) => Bound::Kind(self.map_kind(*kind, &mut |checker, var| checker.resolve(var))),
+        })
+    }
+
+    /// Substitutes every variable found, all the way down.
+    fn zonk(&self, ty: &Type<'a>) -> Type<'a> {
+        self.map(ty, &mut |checker, var| checker.resolve(var))
+    }
+
+    fn zonk_nat(&self, ty: &NatType) -> NatType {
+        let resolve = &mut |checker: &Self, var| checker.resolve(var);
+        NatType { kind: self.map_kind(ty.kind, resolve), width: self.map_width(&ty.width, resolve) }
+    }
+
+    /// Rebuilds `ty`, replacing the variables `each` gives what to replace with.
+    fn map<F>(&self, ty: &Type<'a>, each: &mut F) -> Type<'a>
+    where
+        F: FnMut(&Self, Var) -> Option<Bound<'a>>,
+    {
+        let fields = |fields: &[Field<'a>], each: &mut F| -> Vec<Field<'a>> {
+            fields.iter().map(|field| Field { ident: field.ident.clone(), ty: self.map(&field.ty, each) }).collect()
+        };
+        match ty {
+            Type::Var(var) => match each(self, Var::Type(*var)) {
+                Some(Bound::Type(ty)) => ty,
+                _ => Type::Var(*var),
+            },
+            Type::Primitive(PrimitiveType::Nat(v)) => nat(self.map_kind(v.kind, each), self.map_width(&v.width, each)),
+            Type::Record(v) => Type::Record(RefCell::new(RecordType::new(fields(&v.borrow().fields, each)))),
+            Type::Union(v) => {
+                let variants = v.borrow().variants.iter()
+                    .map(|variant| Variant { ident: variant.ident.clone(), ty: self.map(&variant.ty, each) })
+                    .collect();
+                Type::Union(RefCell::new(UnionType::new(variants)))
+            }
+            Type::Func(v) => {
+                let params = RecordType::new(fields(&v.params.fields, each));
+                Type::Func(Box::from(FuncType { params, results: self.map(&v.results, each) }))
+            }
+            Type::Alias(sym, args) => Type::Alias(*sym, args.iter().map(|arg| self.map_arg(arg, each)).collect()),
+            Type::Param(_) => ty.clone(),
+        }
+    }
+
+    fn map_arg<F>(&self, arg: &GenericArg<'a>, each: &mut F) -> GenericArg<'a>
+    where
+        F: FnMut(&Self, Var) -> Option<Bound<'a>>,
+    {
+        match arg {
+            GenericArg::Type(ty) => GenericArg::Type(self.map(ty, each)),
+            GenericArg::Width(width) => GenericArg::Width(self.map_width(width, each)),
+        }
+    }
+
+    fn map_kind<F>(&self, kind: NatKind, each: &mut F) -> NatKind
+    where
+        F: FnMut(&Self, Var) -> Option<Bound<'a>>,
+    {
+        match kind {
+            NatKind::Var(var) => match each(self, Var::Kind(var)) {
+                Some(Bound::Kind(kind)) => kind,
+                _ => kind,
+            },
+            _ => kind,
+        }
+    }
+
+    fn map_width<F>(&self, width: &Width, each: &mut F) -> Width
+    where
+        F: FnMut(&Self, Var) -> Option<Bound<'a>>,
+    {
+        match width {
+            Width::Var(var) => match each(self, Var::Width(*var)) {
+                Some(Bound::Width(width)) => width,
+                _ => width.clone(),
+            },
+            Width::Max(a, b) => Width::Max(Box::from(self.map_width(a, each)), Box::from(self.map_width(b, each))).fold(),
+            Width::Sum(a, b) => Width::Sum(Box::from(self.map_width(a, each)), Box::from(self.map_width(b, each))).fold(),
+            Width::Unbounded | Width::Const(_) | Width::Param(_) => width.clone(),
+        }
+    }
+
+    /// Whether a width has no variables left to find.
+    fn fixed(&self, width: &Width) -> bool {
+        self.free_bound(&Bound::Width(width.clone())).is_empty()
+    }
+
+    /// The variables in `ty` not found yet.
+    fn free(&self, ty: &Type<'a>) -> Vec<Var> {
+        let mut vars = Vec::new();
+        self.map(&self.zonk(ty), &mut |_, var| {
+            if !vars.contains(&var) {
+                vars.push(var);
+            }
+            None
+        });
+        vars
+    }
+
+    fn free_nat(&self, ty: &NatType) -> Vec<Var> {
+        self.free(&Type::Primitive(PrimitiveType::Nat(ty.clone())))
+    }
+
+    fn free_bound(&self, bound: &Bound<'a>) -> Vec<Var> {
+        match bound {
+            Bound::Type(ty) => self.free(ty),
+            Bound::Width(width) => self.free_nat(&NatType { kind: NatKind::Nat, width: width.clone() }),
+            Bound::Kind(kind) => self.free_nat(&NatType { kind: *kind, width: Width::Unbounded }),
+        }
+    }
+
+    /// Brings the variables in `ty` to the current level, as they are used at it.
+    fn adopt(&mut self, ty: &Type<'a>) {
+        for var in self.free(ty) {
+            self.levels[var.index()] = self.levels[var.index()].min(self.level);
+        }
+    }
+
+    /// The type an alias stands for given its arguments, through any aliases of aliases.
+    fn unfold(&self, ty: &Type<'a>) -> Type<'a> {
+        let mut ty = self.shallow(ty);
+        let mut seen = HashSet::new();
+        while let Type::Alias(sym, args) = &ty
+            && seen.insert(*sym)
+            && let Some(Decl::TypeAlias(alias)) = self.program.decl(*sym)
+        {
+            let args = alias.generics.iter().map(|param| param.sym()).zip(args.iter().cloned()).collect();
+            ty = self.shallow(&alias.ty.subst(&args));
+        }
+        ty
+    }
+
+    /// Variables for the generic parameters of a declaration, at one of its uses.
+    fn fresh_args(&mut self, sym: Symbol) -> Vec<GenericArg<'a>> {
+        let generics = self.program.decl(sym).map_or(&[][..], Decl::generics);
+        generics.iter().map(|param| match param {
+            GenericParam::Type(_) => GenericArg::Type(self.fresh()),
+            GenericParam::Width(_) => GenericArg::Width(Width::Var(WidthVar(self.next()))),
+        }).collect()
+    }
+
+    /// Gives the generic parameters in the type of `sym` variables of their own at the use at
+    /// `span`, recording them.
+    fn instantiate(&mut self, sym: Symbol, ty: Type<'a>, span: Span) -> Type<'a> {
+        let args = self.fresh_args(sym);
+        if args.is_empty() {
+            return ty;
+        }
+        let generics = self.program.decl(sym).map_or(&[][..], Decl::generics);
+        let subst = generics.iter().map(|param| param.sym()).zip(args.iter().cloned()).collect();
+        self.typing.instances.insert((self.module, span), args);
+        self.zonk(&ty).subst(&subst)
+    }
+
+    fn bind(&mut self, var: Var, bound: Bound<'a>) -> bool {
+        let free = self.free_bound(&bound);
+        if free.contains(&var) {
+            return false;
+        }
+        for v in free {
+            self.levels[v.index()] = self.levels[v.index()].min(self.levels[var.index()]);
+        }
+        self.vars[var.index()] = Some(bound);
+        true
+    }
+
+    /// Makes `a` and `b` the same type, through aliases. A pair of aliases met again while
+    /// unifying them is assumed the same, so that recursive types unify in finite time.
+    /// Function parameters unify by position.
+    fn unify(&mut self, a: &Type<'a>, b: &Type<'a>, assumed: &mut Vec<(Symbol, Symbol)>) -> bool {
+        let (a, b) = (self.shallow(a), self.shallow(b));
+        match (&a, &b) {
+            (Type::Var(x), Type::Var(y)) if x == y => return true,
+            (Type::Var(x), _) => return self.bind(Var::Type(*x), Bound::Type(b)),
+            (_, Type::Var(y)) => return self.bind(Var::Type(*y), Bound::Type(a)),
+            (Type::Alias(x, xs), Type::Alias(y, ys)) if x == y => return self.unify_args(xs, ys, assumed),
+            (Type::Alias(x, _), Type::Alias(y, _)) if assumed.contains(&(*x, *y)) => return true,
+            (Type::Alias(x, _), Type::Alias(y, _)) => assumed.push((*x, *y)),
+            _ => {}
+        }
+        match (self.unfold(&a), self.unfold(&b)) {
+            (a @ Type::Var(_), b) | (b, a @ Type::Var(_)) => self.unify(&a, &b, assumed),
+            (Type::Primitive(PrimitiveType::Nat(a)), Type::Primitive(PrimitiveType::Nat(b))) => {
+                self.unify_kind(a.kind, b.kind) && self.unify_width(&a.width, &b.width)
+            }
+            (Type::Record(a), Type::Record(b)) => {
+                let (a, b) = (a.into_inner(), b.into_inner());
+                a.fields.len() == b.fields.len()
+                    && a.fields.iter().zip(&b.fields).all(|(a, b)| a.ident == b.ident && self.unify(&a.ty, &b.ty, assumed))
+            }
+            (Type::Union(a), Type::Union(b)) => {
+                let (a, b) = (a.into_inner(), b.into_inner());
+                a.variants.len() == b.variants.len()
+                    && a.variants.iter().zip(&b.variants).all(|(a, b)| a.ident == b.ident && self.unify(&a.ty, &b.ty, assumed))
+            }
+            (Type::Func(a), Type::Func(b)) => {
+                a.params.fields.len() == b.params.fields.len()
+                    && a.params.fields.iter().zip(&b.params.fields).all(|(a, b)| self.unify(&a.ty, &b.ty, assumed))
+                    && self.unify(&a.results, &b.results, assumed)
+            }
+            (Type::Param(x), Type::Param(y)) => x == y,
+            _ => false,
+        }
+    }
+
+    fn unify_args(&mut self, a: &[GenericArg<'a>], b: &[GenericArg<'a>], assumed: &mut Vec<(Symbol, Symbol)>) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|pair| match pair {
+            (GenericArg::Type(a), GenericArg::Type(b)) => self.unify(a, b, assumed),
+            (GenericArg::Width(a), GenericArg::Width(b)) => self.unify_width(a, b),
+            _ => false,
+        })
+    }
+
+    fn unify_kind(&mut self, a: NatKind, b: NatKind) -> bool {
+        let resolve = &mut |checker: &Self, var| checker.resolve(var);
+        match (self.map_kind(a, resolve), self.map_kind(b, resolve)) {
+            (a, b) if a == b => true,
+            (NatKind::Var(x), kind) | (kind, NatKind::Var(x)) => self.bind(Var::Kind(x), Bound::Kind(kind)),
+            _ => false,
+        }
+    }
+
+    /// Makes two widths the same, or leaves them to be once they can be compared.
+    fn unify_width(&mut self, a: &Width, b: &Width) -> bool {
+        let resolve = &mut |checker: &Self, var| checker.resolve(var);
+        match (self.map_width(a, resolve), self.map_width(b, resolve)) {
+            (a, b) if a == b => true,
+            (a, b) if a.is_known() && b.is_known() => false,
+            (Width::Var(x), width) | (width, Width::Var(x)) if self.bind(Var::Width(x), Bound::Width(width.clone())) => true,
+            // `w = w + 1` only holds of unbounded widths, which are found later if at all.
+            (a, b) => {
+                self.deferred.push((a, b));
+                true
+            }
+        }
+    }
+
+    fn mismatch(&mut self, label: String, span: Span, because: Option<(Span, &'static str)>) {
+        let mut diag = Diagnostic::error("E2001", "mismatched types").with_primary(span, label);
+        if let Some((span, reason)) = because {
+            diag = diag.with_secondary(span, reason);
+        }
+        self.report(diag);
+    }
+
+    /// Unifies `expected` with `found`, keeping what is left of their widths to check later.
+    fn unify_or_defer(&mut self, expected: &Type<'a>, found: &Type<'a>, span: Span, because: Option<(Span, &'static str)>) -> bool {
+        let same = self.unify(expected, found, &mut Vec::new());
+        let widths = std::mem::take(&mut self.deferred);
+        if same && !widths.is_empty() {
+            let constraint = Constraint::Equal { widths, expected: expected.clone(), found: found.clone(), span, because };
+            self.constraints.push((self.module, constraint));
+        }
+        same
+    }
+
+    /// Reports `found` where `expected` is required, with why it is when `because` says so.
+    fn expect(&mut self, expected: &Type<'a>, found: &Type<'a>, span: Span, because: Option<(Span, &'static str)>) {
+        if !self.unify_or_defer(expected, found, span, because) {
+            self.mismatch(format!("expected `{}`, found `{}`", self.name(expected), self.name(found)), span, because);
+        }
+    }
+
+    fn annotations_needed(&mut self, span: Span, label: String) -> Type<'a> {
+        self.report(Diagnostic::error("E2008", "type annotations needed")
+            .with_primary(span, label)
+            .with_help("annotate the parameter or the declaration it comes from"));
+        self.fresh()
+    }
+
+    /// Checks what can be checked of the constraints left. Once `last`, literals no type was
+    /// found for get the fewest bits that hold them, and what is still left is reported.
+    fn solve(&mut self, last: bool) {
+        let module = self.module;
+        loop {
+            let mut progress = false;
+            for (module, constraint) in std::mem::take(&mut self.constraints) {
+                self.module = module;
+                if let Some(constraint) = self.step(constraint, &mut progress) {
+                    self.constraints.push((module, constraint));
+                }
+            }
+            if progress || last && self.default() {
+                continue;
+            }
+            break;
+        }
+        if last {
+            for (module, constraint) in std::mem::take(&mut self.constraints) {
+                self.module = module;
+                let (span, label) = match constraint {
+                    Constraint::Equal { expected, found, span, .. } => {
+                        (span, format!("cannot find widths for which `{}` is `{}`", self.name(&expected), self.name(&found)))
+                    }
+                    Constraint::Fits { span, .. } => (span, "cannot find the width of this literal".to_string()),
+                    Constraint::Resize { builtin, span, .. } => (span, format!("cannot find the width `{}` gives", builtin.to_literal())),
+                };
+                self.annotations_needed(span, label);
+            }
+        }
+        self.module = module;
+    }
+
+    /// Checks a constraint as far as it can be, giving back what is left of it.
+    fn step(&mut self, constraint: Constraint<'a>, progress: &mut bool) -> Option<Constraint<'a>> {
+        match constraint {
+            Constraint::Equal { widths, expected, found, span, because } => {
+                let mut left = Vec::new();
+                for (a, b) in widths {
+                    let resolve = &mut |checker: &Self, var| checker.resolve(var);
+                    match (self.map_width(&a, resolve), self.map_width(&b, resolve)) {
+                        (a, b) if a == b => *progress = true,
+                        (a, b) if self.fixed(&a) && self.fixed(&b) => {
+                            self.mismatch(format!("expected `{}`, found `{}`", self.name(&expected), self.name(&found)), span, because);
+                            *progress = true;
+                            return None;
+                        }
+                        (Width::Var(x), width) | (width, Width::Var(x)) if self.bind(Var::Width(x), Bound::Width(width.clone())) => {
+                            *progress = true;
+                        }
+                        pair => left.push(pair),
+                    }
+                }
+                (!left.is_empty()).then_some(Constraint::Equal { widths: left, expected, found, span, because })
+            }
+            Constraint::Fits { val, ty, span } => {
+                let ty = self.zonk_nat(&ty);
+                let bits = match ty.kind {
+                    NatKind::Var(_) => return Some(Constraint::Fits { val, ty, span }),
+                    kind => NatType::bits(kind, &val),
+                };
+                if !self.fixed(&ty.width) {
+                    return Some(Constraint::Fits { val, ty, span });
+                }
+                if ty.width.least() < bits {
+                    let param = !matches!(ty.width, Width::Const(_));
+                    let ty = Type::Primitive(PrimitiveType::Nat(ty));
+                    let mut diag = Diagnostic::error("E2009", format!("literal out of range for `{}`", self.name(&ty)))
+                        .with_primary(span, format!("`{}` needs {} bits", val, bits));
+                    if param {
+                        diag = diag.with_help("a width parameter may stand for as little as 1 bit");
+                    }
+                    self.report(diag);
+                }
+                *progress = true;
+                None
+            }
+            Constraint::Resize { builtin, from, to, span } => {
+                let (from, to) = (self.zonk_nat(&from), self.zonk_nat(&to));
+                if !self.fixed(&from.width) || !self.fixed(&to.width) {
+                    return Some(Constraint::Resize { builtin, from, to, span });
+                }
+                let fits = match builtin {
+                    Builtin::Trunc => from.width.covers(&to.width),
+                    _ => to.width.covers(&from.width),
+                };
+                if !fits {
+                    let (from, to) = (Type::Primitive(PrimitiveType::Nat(from)), Type::Primitive(PrimitiveType::Nat(to)));
+                    let (verb, help) = match builtin {
+                        Builtin::Trunc => ("truncate", "use `ext` to widen a number"),
+                        _ => ("extend", "use `trunc` to narrow a number"),
+                    };
+                    self.report(Diagnostic::error("E2010", format!("cannot {} `{}` to `{}`", verb, self.name(&from), self.name(&to)))
+                        .with_primary(span, format!("`{}` here gives `{}`", builtin.to_literal(), self.name(&to)))
+                        .with_help(help));
+                }
+                *progress = true;
+                None
+            }
+        }
+    }
+
+    /// Reads numbers of no known kind as `Nat`s, and gives literals of no known width the
+    /// fewest bits that hold them. Tells whether it found any to.
+    fn default(&mut self) -> bool {
+        let mut kinds = Vec::new();
+        let mut widths: HashMap<WidthVar, u32> = HashMap::new();
+        for (_, constraint) in &self.constraints {
+            let Constraint::Fits { val, ty, .. } = constraint else { continue };
+            match self.zonk_nat(ty) {
+                NatType { kind: NatKind::Var(var), .. } => kinds.push(var),
+                NatType { kind, width: Width::Var(var) } => {
+                    let bits = widths.entry(var).or_default();
+                    *bits = (*bits).max(NatType::bits(kind, val));
+                }
+                _ => {}
+            }
+        }
+        let found = !kinds.is_empty() || !widths.is_empty();
+        for var in kinds {
+            self.unify_kind(NatKind::Var(var), NatKind::Nat);
+        }
+        for (var, bits) in widths {
+            self.unify_width(&Width::Var(var), &Width::Const(bits));
+        }
+        found
+    }
+
+    /// The variables still to be found that a constraint depends on.
+    fn constrained(&self) -> HashSet<Var> {
+        let mut vars = HashSet::new();
+        for (_, constraint) in &self.constraints {
+            match constraint {
+                Constraint::Equal { widths, .. } => {
+                    for (a, b) in widths {
+                        vars.extend(self.free_bound(&Bound::Width(a.clone())));
+                        vars.extend(self.free_bound(&Bound::Width(b.clone())));
+                    }
+                }
+                Constraint::Fits { ty, .. } => vars.extend(self.free_nat(ty)),
+                Constraint::Resize { from, to, .. } => {
+                    vars.extend(self.free_nat(from));
+                    vars.extend(self.free_nat(to));
+                }
+            }
+        }
+        vars
+    }
+
+    fn symbol(&mut self, sym: Symbol) -> Type<'a> {
+        if !self.typing.types.contains_key(&sym) {
+            self.lazy(sym);
+        }
+        let Some(ty) = self.typing.types.get(&sym).cloned() else {
+            return self.fresh();
+        };
+        let Some(vars) = self.schemes.get(&sym).cloned() else {
+            return ty;
+        };
+        // Each use of a generalized `let` gets variables of its own.
+        let fresh: HashMap<_, _> = vars.into_iter().map(|var| (var, self.fresh_like(var))).collect();
+        self.map(&self.zonk(&ty), &mut |_, var| fresh.get(&var).cloned())
+    }
+
+    /// Checks a declaration used before it is declared, in the module it is declared in.
+    fn lazy(&mut self, sym: Symbol) {
+        let info = self.program.symbol(sym);
+        let Some(decl) = info.decl else { return };
+        if self.pending.contains(&sym) {
+            let diag = Diagnostic::error("E2007", format!("cannot find the type of `{}`, which depends on itself", info.ident))
+                .with_primary(info.span, "declared here")
+                .with_help("declare it as a function, or give it a value that does not use it");
+            self.typing.diagnostics.push((info.module, diag));
+            return;
+        }
+        let module = std::mem::replace(&mut self.module, info.module);
+        let level = std::mem::replace(&mut self.level, 0);
+        self.decl(decl);
+        self.module = module;
+        self.level = level;
+    }
+
+    fn decl(&mut self, decl: &'a Decl<'a>) {
+        let sym = decl.sym();
+        if !self.checked.insert(sym) {
+            return;
+        }
+        let (annotation, expr) = match decl {
+            Decl::Let(v) => (&v.ty, &v.expr),
+            Decl::Var(v) => (&v.ty, &v.expr),
+            Decl::TypeAlias(v) => return self.alias(v),
+        };
+        self.level += 1;
+        if let Some(ty) = annotation {
+            self.adopt(ty);
+        }
+        let found = if let Expr::Func(v) = expr {
+            // Functions may call themselves, so their type comes first.
+            let func = Type::Func(Box::from(v.ty.clone()));
+            self.typing.types.insert(sym, func.clone());
+            self.expr(expr);
+            func
+        } else {
+            self.pending.insert(sym);
+            let found = self.expr(expr);
+            self.pending.remove(&sym);
+            found
+        };
+        let ty = match annotation {
+            Some(ty) => {
+                self.expect(ty, &found, expr.span(), Some((decl.span(), "expected because of the annotation")));
+                ty.clone()
+            }
+            None => found,
+        };
+        self.level -= 1;
+        match decl {
+            Decl::Let(_) => {
+                // Widths left to check are shared by every use, so they are never generalized.
+                self.solve(false);
+                let constrained = self.constrained();
+                let vars: Vec<_> = self.free(&ty).into_iter()
+                    .filter(|var| self.levels[var.index()] > self.level && !constrained.contains(var))
+                    .collect();
+                if !vars.is_empty() {
+                    self.schemes.insert(sym, vars);
+                }
+            }
+            // Assigned later, so never generalized.
+            _ => self.adopt(&ty),
+        }
+        self.typing.types.insert(sym, ty);
+    }
+
+    /// Reports an alias that stands for nothing but aliases, as `type A = B; type B = A;`.
+    /// One that reaches itself through a record or a union is a recursive type, and fine.
+    fn alias(&mut self, alias: &TypeAliasDecl<'a>) {
+        let args = alias.generics.iter().map(|param| match *param {
+            GenericParam::Type(sym) => GenericArg::Type(Type::Param(sym)),
+            GenericParam::Width(sym) => GenericArg::Width(Width::Param(sym)),
+        }).collect();
+        if let Type::Alias(..) = self.unfold(&Type::Alias(alias.sym, args)) {
+            self.report(Diagnostic::error("E2011", format!("`{}` stands for itself", alias.ident))
+                .with_primary(alias.span, "only aliases lead back here")
+                .with_help("a type may only refer to itself within a `record` or a `union`"));
+        }
+    }
+
+    /// The type of a literal, which is left to fit in it, of the width written after it if any.
+    fn literal(&mut self, val: BigUint, width: Option<u32>, span: Span) -> NatType {
+        let mut ty = self.fresh_nat();
+        if let Some(width) = width {
+            ty.width = Width::Const(width);
+        }
+        self.constraints.push((self.module, Constraint::Fits { val, ty: ty.clone(), span }));
+        ty
+    }
+
+    /// Operators keep the kind of their operands. `+` and `-` give a bit more than the wider
+    /// operand, `*` as many as both, and comparisons and logic one bit.
+    fn builtin(&mut self, v: &BuiltinExpr) -> Type<'a> {
+        let NatType { kind, width: a } = self.fresh_nat();
+        let b = Width::Var(WidthVar(self.next()));
+        let other = NatKind::Var(KindVar(self.next()));
+        let (params, result) = match v.builtin {
+            Builtin::Nat | Builtin::Bits | Builtin::Int => unreachable!("builtin types are not lowered to values"),
+            Builtin::Invert => (vec![nat(kind, a.clone())], nat(kind, a)),
+            Builtin::Not => (vec![nat(kind, a)], nat(NatKind::Nat, Width::Const(1))),
+            Builtin::Trunc | Builtin::Ext => {
+                let (from, to) = (NatType { kind, width: a }, NatType { kind, width: b });
+                let constraint = Constraint::Resize { builtin: v.builtin, from: from.clone(), to: to.clone(), span: v.span };
+                self.constraints.push((self.module, constraint));
+                let (from, to) = (Type::Primitive(PrimitiveType::Nat(from)), Type::Primitive(PrimitiveType::Nat(to)));
+                (vec![from], to)
+            }
+            Builtin::Add | Builtin::Sub => (vec![nat(kind, a.clone()), nat(kind, b.clone())], nat(kind, Width::carry(a, b))),
+            Builtin::Mul => {
+                let sum = Width::Sum(Box::from(a.clone()), Box::from(b.clone()));
+                (vec![nat(kind, a), nat(kind, b)], nat(kind, sum))
+            }
+            Builtin::Div => (vec![nat(kind, a.clone()), nat(kind, b)], nat(kind, a)),
+            Builtin::Mod => (vec![nat(kind, a), nat(kind, b.clone())], nat(kind, b)),
+            Builtin::And | Builtin::Or | Builtin::Xor => {
+                let max = Width::Max(Box::from(a.clone()), Box::from(b.clone()));
+                (vec![nat(kind, a), nat(kind, b)], nat(kind, max))
+            }
+            Builtin::Shl | Builtin::Shr => (vec![nat(kind, a.clone()), nat(other, b)], nat(kind, a)),
+            Builtin::Eq | Builtin::Ne | Builtin::Lt | Builtin::Le | Builtin::Gt | Builtin::Ge => {
+                (vec![nat(kind, a), nat(kind, b)], nat(NatKind::Nat, Width::Const(1)))
+            }
+            Builtin::LogicAnd | Builtin::LogicOr => (vec![nat(kind, a), nat(other, b)], nat(NatKind::Nat, Width::Const(1))),
+        };
+        let params = params.into_iter().enumerate().map(|(i, ty)| Field { ident: i.to_string(), ty }).collect();
+        Type::Func(Box::from(FuncType { params: RecordType::new(params), results: result }))
+    }
+
+    fn expr(&mut self, expr: &Expr<'a>) -> Type<'a> {
+        match expr {
+            Expr::Nat(v) => Type::Primitive(PrimitiveType::Nat(self.literal(v.val.clone(), v.width, v.span))),
+            Expr::Symbol(v) => {
+                let ty = self.symbol(v.sym);
+                self.instantiate(v.sym, ty, v.span)
+            }
+            Expr::Builtin(v) => self.builtin(v),
+            Expr::Record(v) => Type::Record(RefCell::new(self.record(v))),
+            Expr::Variant(v) => self.variant(v),
+            Expr::Apply(v) => self.apply(v),
+            Expr::Select(v) => self.select(v),
+            Expr::Block(v) => self.scope(v),
+            Expr::Func(v) => {
+                let func = Type::Func(Box::from(v.ty.clone()));
+                self.adopt(&func);
+                for (sym, field) in v.params.iter().zip(&v.ty.params.fields) {
+                    self.typing.types.insert(*sym, field.ty.clone());
+                }
+                let body = self.scope(&v.body);
+                self.expect(&v.ty.results, &body, v.body.expr.span(), Some((v.span, "expected because of the return type")));
+                func
+            }
+            Expr::Match(v) => {
+                let scrutinee = self.expr(&v.expr);
+                self.typing.scrutinees.insert((self.module, v.span), scrutinee.clone());
+                let mut first: Option<(Type<'a>, Span)> = None;
+                for case in &v.cases {
+                    self.pattern(&case.pattern, &scrutinee);
+                    let ty = self.scope(&case.expr);
+                    match &first {
+                        Some((expected, span)) => {
+                            let (expected, span) = (expected.clone(), *span);
+                            self.expect(&expected, &ty, case.expr.expr.span(), Some((span, "expected because of the first arm")));
+                        }
+                        None => first = Some((ty, case.expr.expr.span())),
+                    }
+                }
+                match first {
+                    Some((ty, _)) => ty,
+                    None => self.fresh(),
+                }
+            }
+            Expr::Assign(v) => {
+                let found = self.expr(&v.expr);
+                let expected = self.symbol(v.target);
+                let declared = self.program.symbol(v.target).span;
+                self.expect(&expected, &found, v.expr.span(), Some((declared, "expected because of the declaration")));
+                Type::unit()
+            }
+            Expr::While(v) => {
+                let cond = self.expr(&v.cond);
+                let expected = Type::Primitive(PrimitiveType::Nat(self.fresh_nat()));
+                self.expect(&expected, &cond, v.cond.span(), None);
+                self.scope(&v.body);
+                Type::unit()
+            }
+        }
+    }
+
+    fn scope(&mut self, scope: &Scope<'a>) -> Type<'a> {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(decl) => self.decl(decl),
+                Stmt::Expr(expr) => {
+                    self.expr(expr);
+                }
+            }
+        }
+        self.expr(&scope.expr)
+    }
+
+    fn record(&mut self, record: &RecordExpr<'a>) -> RecordType<'a> {
+        RecordType::new(record.fields.iter().map(|field| Field { ident: field.ident.clone(), ty: self.expr(&field.expr) }).collect())
+    }
+
+    /// The union an alias given `args` stands for, or a report that it is not one.
+    fn union(&mut self, ty: Symbol, args: &[GenericArg<'a>], span: Span) -> Option<UnionType<'a>> {
+        match self.unfold(&Type::Alias(ty, args.to_vec())) {
+            Type::Union(v) => Some(v.into_inner()),
+            found => {
+                let name = self.program.symbol(ty).ident.clone();
+                self.report(Diagnostic::error("E2004", format!("`{}` is not a union", name))
+                    .with_primary(span, format!("`{}` is `{}`", name, self.name(&found))));
+                None
+            }
+        }
+    }
+
+    /// The type variant `ident` of the union `ty` given `args` carries.
+    fn variant_type(&mut self, ty: Symbol, args: &[GenericArg<'a>], ident: &str, span: Span) -> Option<Type<'a>> {
+        let union = self.union(ty, args, span)?;
+        match union.names.get(ident) {
+            Some(i) => Some(union.variants[*i].ty.clone()),
+            None => {
+                let name = &self.program.symbol(ty).ident;
+                let variants = union.variants.iter().map(|variant| format!("`{}`", variant.ident)).collect::<Vec<_>>().join(", ");
+                let diag = Diagnostic::error("E2005", format!("no variant `{}` in `{}`", ident, name))
+                    .with_primary(span, "unknown variant")
+                    .with_help(format!("the variants of `{}` are {}", name, variants));
+                self.report(diag);
+                None
+            }
+        }
+    }
+
+    fn variant(&mut self, v: &VariantExpr<'a>) -> Type<'a> {
+        let (found, span) = match &v.payload {
+            Some(payload) => (self.expr(payload), payload.span()),
+            None => (Type::unit(), v.span),
+        };
+        let args = self.fresh_args(v.ty);
+        if let Some(expected) = self.variant_type(v.ty, &args, &v.ident, v.span) {
+            self.expect(&expected, &found, span, None);
+        }
+        Type::Alias(v.ty, args)
+    }
+
+    fn apply(&mut self, v: &ApplyExpr<'a>) -> Type<'a> {
+        let func = self.expr(&v.func);
+        let args = self.record(&v.params).fields;
+        let spans: Vec<_> = v.params.fields.iter().map(|field| field.expr.span()).collect();
+        let positional = positional(v.params.fields.iter().map(|field| &field.ident));
+        let func = match self.unfold(&func) {
+            Type::Func(func) => *func,
+            // Not known yet, so a function taking the arguments as they are given.
+            var @ Type::Var(_) if positional => {
+                let results = self.fresh();
+                let func = FuncType { params: RecordType::new(args), results: results.clone() };
+                self.expect(&var, &Type::Func(Box::from(func)), v.func.span(), None);
+                return results;
+            }
+            Type::Var(_) => {
+                return self.annotations_needed(v.func.span(), "the type of this function must be known to pass arguments by name".to_string());
+            }
+            found => {
+                self.report(Diagnostic::error("E2006", format!("expected a function, found `{}`", self.name(&found)))
+                    .with_primary(v.func.span(), "called here"));
+                return self.fresh();
+            }
+        };
+        let params = &func.params.fields;
+        if positional {
+            if args.len() != params.len() {
+                self.report(Diagnostic::error("E2002", format!(
+                    "this function takes {} arguments but {} were supplied", params.len(), args.len(),
+                )).with_primary(v.params.span, format!("expected `{}`", self.name(&Type::Func(Box::from(func.clone()))))));
+                return func.results.clone();
+            }
+            for ((param, arg), span) in params.iter().zip(&args).zip(spans) {
+                self.expect(&param.ty, &arg.ty, span, None);
+            }
+            return func.results.clone();
+        }
+        for (field, span) in v.params.fields.iter().zip(&spans) {
+            if !func.params.names.contains_key(&field.ident) {
+                self.report(Diagnostic::error("E2002", format!("no parameter `{}`", field.ident))
+                    .with_primary(*span, "unknown argument"));
+            }
+        }
+        for param in params {
+            match v.params.fields.iter().position(|field| field.ident == param.ident) {
+                Some(i) => self.expect(&param.ty, &args[i].ty, spans[i], None),
+                None => self.report(Diagnostic::error("E2002", format!("missing argument `{}`", param.ident))
+                    .with_primary(v.params.span, format!("`{}: {}` is not given", param.ident, self.name(&param.ty)))),
+            }
+        }
+        func.results.clone()
+    }
+
+    fn select(&mut self, v: &SelectExpr<'a>) -> Type<'a> {
+        let ty = self.expr(&v.expr);
+        match self.unfold(&ty) {
+            Type::Record(record) if record.borrow().names.contains_key(&v.ident) => {
+                let record = record.into_inner();
+                record.fields[record.names[&v.ident]].ty.clone()
+            }
+            Type::Var(_) => self.annotations_needed(v.expr.span(), format!("the type of this must be known to select `{}`", v.ident)),
+            _ => {
+                self.report(Diagnostic::error("E2003", format!("no field `{}` on type `{}`", v.ident, self.name(&ty)))
+                    .with_primary(v.span, "unknown field"));
+                self.fresh()
+            }
+        }
+    }
+
+    /// Binds the names in a pattern matching values of type `ty`.
+    fn pattern(&mut self, pattern: &Pattern, ty: &Type<'a>) {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Nat(v) => {
+                let literal = Type::Primitive(PrimitiveType::Nat(self.literal(v.val.clone(), v.width, v.span)));
+                self.expect_pattern(&literal, ty, v.span);
+            }
+            Pattern::Range(v) => {
+                let hi = if v.inclusive || v.hi == BigUint::ZERO { v.hi.clone() } else { &v.hi - 1u8 };
+                let literal = Type::Primitive(PrimitiveType::Nat(self.literal(hi, None, v.span)));
+                self.expect_pattern(&literal, ty, v.span);
+            }
+            Pattern::Bind(v) => match self.typing.types.get(&v.sym).cloned() {
+                // Bound by an earlier alternative of an or-pattern, whose type it must have here too.
+                Some(bound) => {
+                    if !self.unify_or_defer(&bound, ty, v.span, None) {
+                        let ident = self.program.symbol(v.sym).ident.clone();
+                        let label = format!("`{}` is `{}` in an earlier alternative, but `{}` here", ident, self.name(&bound), self.name(ty));
+                        self.mismatch(label, v.span, None);
+                    }
+                }
+                None => {
+                    self.typing.types.insert(v.sym, ty.clone());
+                }
+            },
+            Pattern::Variant(v) => {
+                let args = self.fresh_args(v.ty);
+                self.expect_pattern(&Type::Alias(v.ty, args.clone()), ty, v.span);
+                let payload = self.variant_type(v.ty, &args, &v.ident, v.span);
+                match (&v.payload, payload) {
+                    (Some(pattern), payload) => {
+                        let payload = payload.unwrap_or_else(|| self.fresh());
+                        self.pattern(pattern, &payload);
+                    }
+                    (None, Some(payload)) => self.expect(&payload, &Type::unit(), v.span, None),
+                    (None, None) => {}
+                }
+            }
+            Pattern::Record(v) => {
+                let record = match self.unfold(ty) {
+                    Type::Record(record) => Some(record.into_inner()),
+                    // Not known yet, so the record of the fields the pattern names.
+                    var @ Type::Var(_) => {
+                        let record = RecordType::new(v.fields.iter().map(|field| Field { ident: field.ident.clone(), ty: self.fresh() }).collect());
+                        self.expect_pattern(&Type::Record(RefCell::new(record.clone())), &var, v.span);
+                        Some(record)
+                    }
+                    _ => {
+                        self.report(Diagnostic::error("E2001", "mismatched types")
+                            .with_primary(v.span, format!("expected `{}`, found a record", self.name(ty))));
+                        None
+                    }
+                };
+                for field in &v.fields {
+                    let found = record.as_ref().map(|record| record.names.get(&field.ident).map(|i| record.fields[*i].ty.clone()));
+                    let field_ty = match found {
+                        Some(Some(ty)) => ty,
+                        Some(None) => {
+                            self.report(Diagnostic::error("E2003", format!("no field `{}` on type `{}`", field.ident, self.name(ty)))
+                                .with_primary(field.pattern.span(), "unknown field"));
+                            self.fresh()
+                        }
+                        None => self.fresh(),
+                    };
+                    self.pattern(&field.pattern, &field_ty);
+                }
+            }
+            Pattern::Or(v) => {
+                for alt in &v.alts {
+                    self.pattern(alt, ty);
+                }
+            }
+        }
+    }
+
+    /// A pattern of type `pattern` matching values of type `ty`.
+    fn expect_pattern(&mut self, pattern: &Type<'a>, ty: &Type<'a>, span: Span) {
+        if !self.unify_or_defer(ty, pattern, span, None) {
+            self.mismatch(format!("expected `{}`, found `{}`", self.name(ty), self.name(pattern)), span, None);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Common subexpression elimination, by hash-consing the expressions of each function.
+//!
+//! Every expression of a function is given a node of a DAG, and an operator application, a
+//! select or a record equal to one seen before, anywhere in the function, is given the node of
+//! that one rather than a new one, so that it is computed once. A `let` bound in the function
+//! stands for the node of its value. Expressions are only merged where their values are sure to
+//! be equal: those reading a `var`, calling a function, or applying `~`, `trunc` or `ext`, whose
+//! values depend on the width they are used at, get nodes of their own, as does anything with a
+//! scope or an effect.
+
+use crate::sym::*;
+use num_bigint::BigUint;
+use paracell_parser_sem::span::Span;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub type NodeId = usize;
+
+/// A node of the DAG, with the nodes of its operands.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Node {
+    Nat(BigUint, Option<u32>),
+    Symbol(Symbol),
+    Builtin(Builtin),
+    Apply(NodeId, Vec<(String, NodeId)>),
+    Select(NodeId, String),
+    Record(Vec<(String, NodeId)>),
+    /// An expression never merged with another, at its span.
+    Opaque(Span),
+}
+
+/// The DAG of a function.
+#[derive(Debug)]
+pub struct FuncDag {
+    pub sym: Symbol,
+    pub nodes: Vec<Node>,
+    /// The node of the value the function returns.
+    pub result: NodeId,
+    /// How many applications, selects and records were given the node of an equal one.
+    pub merged: usize,
+    exprs: HashMap<*const (), NodeId>,
+}
+
+impl FuncDag {
+    /// The node of an expression of the function.
+    pub fn node(&self, expr: &Expr<'_>) -> Option<NodeId> {
+        self.exprs.get(&key(expr)).copied()
+    }
+}
+
+fn key(expr: &Expr<'_>) -> *const () {
+    expr as *const Expr<'_> as *const ()
+}
+
+/// The DAG of every top-level function of a program.
+pub fn cse(program: &Program<'_>) -> Vec<FuncDag> {
+    let mut dags = Vec::new();
+    for module in &program.modules {
+        for decl in &module.decls.vals {
+            if let Decl::Let(LetDecl { sym, expr: Expr::Func(func), .. }) = decl {
+                let mut builder = Builder { program, nodes: Vec::new(), table: HashMap::new(), bound: HashMap::new(), exprs: HashMap::new(), merged: 0 };
+                let result = builder.scope(&func.body);
+                dags.push(FuncDag { sym: *sym, nodes: builder.nodes, result, merged: builder.merged, exprs: builder.exprs });
+            }
+        }
+    }
+    dags
+}
+
+/// One line per function, telling how many of its nodes were merged.
+pub fn report(program: &Program<'_>, dags: &[FuncDag]) -> String {
+    let mut out = String::new();
+    for dag in dags {
+        writeln!(out, "{}: {} merged, {} nodes", program.symbol(dag.sym).ident, dag.merged, dag.nodes.len()).unwrap();
+    }
+    out
+}
+
+struct Builder<'p, 'a> {
+    program: &'p Program<'a>,
+    nodes: Vec<Node>,
+    /// The node of each expression that may be merged.
+    table: HashMap<Node, NodeId>,
+    /// The node each `let` of the function stands for.
+    bound: HashMap<Symbol, NodeId>,
+    exprs: HashMap<*const (), NodeId>,
+    merged: usize,
+}
+
+impl<'p, 'a> Builder<'p, 'a> {
+    fn push(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn intern(&mut self, node: Node) -> NodeId {
+        if let Some(&id) = self.table.get(&node) {
+            if matches!(node, Node::Apply(..) | Node::Select(..) | Node::Record(_)) {
+                self.merged += 1;
+            }
+            return id;
+        }
+        let id = self.push(node.clone());
+        self.table.insert(node, id);
+        id
+    }
+
+    fn scope(&mut self, scope: &Scope<'a>) -> NodeId {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(Decl::Let(v)) => {
+                    let id = self.expr(&v.expr);
+                    if v.generics.is_empty() {
+                        self.bound.insert(v.sym, id);
+                    }
+                }
+                Stmt::Decl(Decl::Var(v)) => {
+                    self.expr(&v.expr);
+                }
+                Stmt::Decl(Decl::TypeAlias(_)) => {}
+                Stmt::Expr(v) => {
+                    self.expr(v);
+                }
+            }
+        }
+        self.expr(&scope.expr)
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) -> NodeId {
+        let node = match e {
+            Expr::Nat(v) => Some(Node::Nat(v.val.clone(), v.width)),
+            Expr::Symbol(v) => match self.bound.get(&v.sym) {
+                Some(&id) => {
+                    self.exprs.insert(key(e), id);
+                    return id;
+                }
+                None if matches!(self.program.decl(v.sym), Some(Decl::Var(_))) => None,
+                None => Some(Node::Symbol(v.sym)),
+            },
+            Expr::Builtin(v) => Some(Node::Builtin(v.builtin)),
+            Expr::Record(v) => Some(Node::Record(self.fields(&v.fields))),
+            Expr::Variant(v) => {
+                if let Some(payload) = &v.payload {
+                    self.expr(payload);
+                }
+                None
+            }
+            Expr::Apply(v) => {
+                let func = self.expr(&v.func);
+                let args = self.fields(&v.params.fields);
+                match &v.func {
+                    Expr::Builtin(b) if !matches!(b.builtin, Builtin::Invert | Builtin::Trunc | Builtin::Ext) => Some(Node::Apply(func, args)),
+                    _ => None,
+                }
+            }
+            Expr::Select(v) => Some(Node::Select(self.expr(&v.expr), v.ident.clone())),
+            // Only the declarations of a block run before its result.
+            Expr::Block(v) if v.stmts.iter().all(|stmt| matches!(stmt, Stmt::Decl(_))) => {
+                let id = self.scope(v);
+                self.exprs.insert(key(e), id);
+                return id;
+            }
+            Expr::Block(v) => {
+                self.scope(v);
+                None
+            }
+            Expr::Func(v) => {
+                self.scope(&v.body);
+                None
+            }
+            Expr::Match(v) => {
+                self.expr(&v.expr);
+                for case in &v.cases {
+                    self.scope(&case.expr);
+                }
+                None
+            }
+            Expr::Assign(v) => {
+                self.expr(&v.expr);
+                None
+            }
+            Expr::While(v) => {
+                self.expr(&v.cond);
+                self.scope(&v.body);
+                None
+            }
+        };
+        let id = match node {
+            Some(node) => self.intern(node),
+            None => self.push(Node::Opaque(e.span())),
+        };
+        self.exprs.insert(key(e), id);
+        id
+    }
+
+    fn fields(&mut self, fields: &[FieldExpr<'a>]) -> Vec<(String, NodeId)> {
+        fields.iter().map(|field| (field.ident.clone(), self.expr(&field.expr))).collect()
+    }
+}
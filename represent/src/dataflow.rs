@@ -0,0 +1,450 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Dataflow graphs of functions.
+//!
+//! The body of a function is turned into a DAG whose nodes are operators and calls, and whose
+//! edges are the values they take. A `let` is dissolved into the node of its value, and each
+//! assignment to a `var` gives a new version of it, so that every node reads exactly the version
+//! it sees. A `match` muxes the values of its arms, and a `var` its arms leave at different
+//! versions gets a φ node muxing them too. A `while` loop is one node taking its condition and
+//! the versions of the `var`s its body assigns at the end of an iteration: within the body they
+//! are read from loop nodes, and after it from exit nodes, so that the back edge of the loop
+//! stays within the `while` node and the graph has no cycle. A function written within the
+//! function is one node, taking the values it captures.
+
+use crate::sym::*;
+use num_bigint::BigUint;
+use paracell_parser_sem::span::Span;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+pub type NodeId = usize;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Param(Symbol),
+    Nat(BigUint),
+    /// A declaration outside the function, as another function is.
+    Global(Symbol),
+    /// A builtin operator applied to its inputs.
+    Builtin(Builtin),
+    /// A function, the first input, called with the rest.
+    Call,
+    /// A record of its inputs, one per field.
+    Record(Vec<String>),
+    /// A variant of the union the alias declares, of its input if it has a payload.
+    Variant(Symbol, String),
+    Select(String),
+    /// A function written within the function, whose body is not part of the graph, taking the
+    /// values of the function it captures.
+    Func,
+    /// What a pattern binds of the value matched, its input.
+    Bind(Symbol),
+    /// The value of the arm of a `match` its first input selects, one arm per other input.
+    Match,
+    /// The version of a `var` left by the arm of a `match` its first input selects.
+    Phi(Symbol),
+    /// The version of a `var` at the start of an iteration, its input the version before the loop.
+    Loop(Symbol),
+    /// A `while` loop, taking its condition then the versions of the `var`s it carries.
+    While,
+    /// The version of a `var` after the loop, its input.
+    Exit(Symbol),
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub op: Op,
+    pub inputs: Vec<NodeId>,
+    pub span: Span,
+}
+
+/// The dataflow graph of a function.
+#[derive(Clone, Debug)]
+pub struct Graph {
+    pub sym: Symbol,
+    /// Each after its inputs.
+    pub nodes: Vec<Node>,
+    pub result: NodeId,
+    /// The node of each version of each `var`, in order.
+    pub versions: HashMap<Symbol, Vec<NodeId>>,
+}
+
+/// The graph of every top-level function of a program.
+pub fn graphs(program: &Program<'_>) -> Vec<Graph> {
+    program.modules.iter()
+        .flat_map(|module| module.decls.vals.iter())
+        .filter_map(|decl| graph(program, decl.sym()))
+        .collect()
+}
+
+/// The graph of the function `func`, or `None` if it is not a function.
+pub fn graph(program: &Program<'_>, func: Symbol) -> Option<Graph> {
+    let Some(Decl::Let(LetDecl { expr: Expr::Func(v), .. })) = program.decl(func) else { return None };
+    let mut builder = Builder { program, nodes: Vec::new(), values: HashMap::new(), versions: HashMap::new() };
+    for &param in &v.params {
+        let id = builder.push(Op::Param(param), Vec::new(), program.symbol(param).span);
+        builder.values.insert(param, id);
+    }
+    let result = builder.scope(&v.body);
+    Some(Graph { sym: func, nodes: builder.nodes, result, versions: builder.versions })
+}
+
+impl Graph {
+    /// The graph in Graphviz DOT, each node labelled with what it computes and the versions of
+    /// `var`s it gives.
+    pub fn dot(&self, program: &Program<'_>) -> String {
+        let name = |sym: Symbol| program.symbol(sym).ident.clone();
+        let mut labels: Vec<_> = self.nodes.iter()
+            .map(|node| match &node.op {
+                Op::Param(sym) | Op::Global(sym) | Op::Bind(sym) => name(*sym),
+                Op::Nat(val) => val.to_string(),
+                Op::Builtin(builtin) => builtin.to_literal().to_string(),
+                Op::Call => "call".to_string(),
+                Op::Record(fields) if fields.is_empty() => "()".to_string(),
+                Op::Record(_) => "record".to_string(),
+                Op::Variant(ty, ident) => format!("{}::{}", name(*ty), ident),
+                Op::Select(ident) => format!(".{}", ident),
+                Op::Func => "fun".to_string(),
+                Op::Match => "match".to_string(),
+                Op::Phi(sym) => format!("φ {}", name(*sym)),
+                Op::Loop(sym) => format!("loop {}", name(*sym)),
+                Op::While => "while".to_string(),
+                Op::Exit(sym) => format!("exit {}", name(*sym)),
+            })
+            .collect();
+        let mut vars: Vec<_> = self.versions.iter().collect();
+        vars.sort_by_key(|(sym, _)| **sym);
+        for (sym, nodes) in vars {
+            for (version, &id) in nodes.iter().enumerate() {
+                write!(labels[id], "\n{}.{}", name(*sym), version).unwrap();
+            }
+        }
+        let mut out = String::new();
+        writeln!(out, "digraph \"{}\" {{", name(self.sym)).unwrap();
+        for (id, label) in labels.iter().enumerate() {
+            let shape = if id == self.result { ", shape=doublecircle" } else { "" };
+            writeln!(out, "    n{} [label=\"{}\"{}];", id, label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"), shape).unwrap();
+        }
+        for (id, node) in self.nodes.iter().enumerate() {
+            for input in &node.inputs {
+                writeln!(out, "    n{} -> n{};", input, id).unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+struct Builder<'p, 'a> {
+    program: &'p Program<'a>,
+    nodes: Vec<Node>,
+    /// The node each parameter, `let`, binding and current version of a `var` stands for.
+    values: HashMap<Symbol, NodeId>,
+    versions: HashMap<Symbol, Vec<NodeId>>,
+}
+
+impl<'p, 'a> Builder<'p, 'a> {
+    fn push(&mut self, op: Op, inputs: Vec<NodeId>, span: Span) -> NodeId {
+        self.nodes.push(Node { op, inputs, span });
+        self.nodes.len() - 1
+    }
+
+    /// Makes `id` the current version of the `var` `sym`.
+    fn version(&mut self, sym: Symbol, id: NodeId) {
+        self.values.insert(sym, id);
+        self.versions.entry(sym).or_default().push(id);
+    }
+
+    fn is_var(&self, sym: Symbol) -> bool {
+        matches!(self.program.decl(sym), Some(Decl::Var(_)))
+    }
+
+    /// Whether `sym` is declared at the top level of a module, outside every function.
+    fn is_global(&self, sym: Symbol) -> bool {
+        self.program.modules.iter().any(|module| module.decls.vals.iter().any(|decl| decl.sym() == sym))
+    }
+
+    /// The node `sym` stands for, a new `Global` node the first time a declaration outside the
+    /// function is read.
+    fn value(&mut self, sym: Symbol, span: Span) -> NodeId {
+        match self.values.get(&sym) {
+            Some(&id) => id,
+            None => {
+                let id = self.push(Op::Global(sym), Vec::new(), span);
+                self.values.insert(sym, id);
+                id
+            }
+        }
+    }
+
+    /// Gives each module-level `var` of `syms` not read yet its node, so that the versions the
+    /// function assigns start from it.
+    fn globals(&mut self, syms: &[Symbol], span: Span) {
+        for &sym in syms {
+            if self.is_global(sym) {
+                self.value(sym, span);
+            }
+        }
+    }
+
+    fn scope(&mut self, scope: &Scope<'a>) -> NodeId {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(Decl::Let(v)) => {
+                    let id = self.expr(&v.expr);
+                    self.values.insert(v.sym, id);
+                }
+                Stmt::Decl(Decl::Var(v)) => {
+                    let id = self.expr(&v.expr);
+                    self.version(v.sym, id);
+                }
+                Stmt::Decl(Decl::TypeAlias(_)) => {}
+                Stmt::Expr(v) => {
+                    self.expr(v);
+                }
+            }
+        }
+        self.expr(&scope.expr)
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) -> NodeId {
+        match e {
+            Expr::Nat(v) => self.push(Op::Nat(v.val.clone()), Vec::new(), v.span),
+            Expr::Symbol(v) => self.value(v.sym, v.span),
+            // Applied by `Expr::Apply`, the only place an operator is written.
+            Expr::Builtin(v) => self.push(Op::Builtin(v.builtin), Vec::new(), v.span),
+            Expr::Record(v) => {
+                let inputs = v.fields.iter().map(|field| self.expr(&field.expr)).collect();
+                self.push(Op::Record(v.fields.iter().map(|field| field.ident.clone()).collect()), inputs, v.span)
+            }
+            Expr::Variant(v) => {
+                let inputs = v.payload.iter().map(|payload| self.expr(payload)).collect();
+                self.push(Op::Variant(v.ty, v.ident.clone()), inputs, v.span)
+            }
+            Expr::Apply(v) => {
+                let args: Vec<_> = v.params.fields.iter().map(|field| self.expr(&field.expr)).collect();
+                match &v.func {
+                    Expr::Builtin(func) => self.push(Op::Builtin(func.builtin), args, v.span),
+                    func => {
+                        let func = self.expr(func);
+                        self.push(Op::Call, std::iter::once(func).chain(args).collect(), v.span)
+                    }
+                }
+            }
+            Expr::Select(v) => {
+                let input = self.expr(&v.expr);
+                self.push(Op::Select(v.ident.clone()), vec![input], v.span)
+            }
+            Expr::Block(v) => self.scope(v),
+            Expr::Func(v) => {
+                let mut syms = Vec::new();
+                used(&v.body, &mut syms);
+                let mut inputs = Vec::new();
+                for sym in syms {
+                    if let Some(&id) = self.values.get(&sym)
+                        && !matches!(self.nodes[id].op, Op::Global(_))
+                        && !inputs.contains(&id)
+                    {
+                        inputs.push(id);
+                    }
+                }
+                self.push(Op::Func, inputs, v.span)
+            }
+            Expr::Match(v) => self.match_(v),
+            Expr::Assign(v) => {
+                let id = self.expr(&v.expr);
+                self.version(v.target, id);
+                self.push(Op::Record(Vec::new()), Vec::new(), v.span)
+            }
+            Expr::While(v) => self.while_(v),
+        }
+    }
+
+    fn match_(&mut self, v: &Match<'a>) -> NodeId {
+        let scrutinee = self.expr(&v.expr);
+        let mut assigns = Vec::new();
+        for case in &v.cases {
+            assigned(&case.expr, &mut assigns);
+        }
+        self.globals(&assigns, v.span);
+        let before = self.values.clone();
+        let mut results = Vec::new();
+        let mut arms = Vec::new();
+        for case in &v.cases {
+            self.values = before.clone();
+            self.bind(&case.pattern, scrutinee);
+            results.push(self.scope(&case.expr));
+            arms.push(std::mem::take(&mut self.values));
+        }
+        self.values = before;
+        let mut vars: Vec<_> = self.values.keys().copied().filter(|sym| self.is_var(*sym)).collect();
+        vars.sort();
+        for sym in vars {
+            let inputs: Vec<_> = arms.iter().map(|arm| arm[&sym]).collect();
+            if inputs.iter().any(|&id| id != self.values[&sym]) {
+                let phi = self.push(Op::Phi(sym), std::iter::once(scrutinee).chain(inputs).collect(), v.span);
+                self.version(sym, phi);
+            }
+        }
+        self.push(Op::Match, std::iter::once(scrutinee).chain(results).collect(), v.span)
+    }
+
+    fn bind(&mut self, pattern: &Pattern, scrutinee: NodeId) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Nat(_) | Pattern::Range(_) => {}
+            Pattern::Bind(v) => {
+                if !self.values.contains_key(&v.sym) {
+                    let id = self.push(Op::Bind(v.sym), vec![scrutinee], v.span);
+                    self.values.insert(v.sym, id);
+                }
+            }
+            Pattern::Variant(v) => {
+                if let Some(payload) = &v.payload {
+                    self.bind(payload, scrutinee);
+                }
+            }
+            Pattern::Record(v) => {
+                for field in &v.fields {
+                    self.bind(&field.pattern, scrutinee);
+                }
+            }
+            Pattern::Or(v) => {
+                for alt in &v.alts {
+                    self.bind(alt, scrutinee);
+                }
+            }
+        }
+    }
+
+    fn while_(&mut self, v: &WhileExpr<'a>) -> NodeId {
+        let mut carried = Vec::new();
+        assigned(&v.body, &mut carried);
+        self.globals(&carried, v.span);
+        // Those declared within the body start afresh each iteration.
+        carried.retain(|sym| self.values.contains_key(sym));
+        for &sym in &carried {
+            let init = self.values[&sym];
+            let id = self.push(Op::Loop(sym), vec![init], v.span);
+            self.version(sym, id);
+        }
+        let cond = self.expr(&v.cond);
+        self.scope(&v.body);
+        let inputs = std::iter::once(cond).chain(carried.iter().map(|sym| self.values[sym])).collect();
+        let id = self.push(Op::While, inputs, v.span);
+        for &sym in &carried {
+            let exit = self.push(Op::Exit(sym), vec![id], v.span);
+            self.version(sym, exit);
+        }
+        self.push(Op::Record(Vec::new()), Vec::new(), v.span)
+    }
+}
+
+/// The `var`s a scope assigns, in the order first assigned.
+fn assigned(scope: &Scope<'_>, found: &mut Vec<Symbol>) {
+    for stmt in &scope.stmts {
+        match stmt {
+            Stmt::Decl(Decl::Let(v)) => assigned_expr(&v.expr, found),
+            Stmt::Decl(Decl::Var(v)) => assigned_expr(&v.expr, found),
+            Stmt::Decl(Decl::TypeAlias(_)) => {}
+            Stmt::Expr(v) => assigned_expr(v, found),
+        }
+    }
+    assigned_expr(&scope.expr, found);
+}
+
+fn assigned_expr(e: &Expr<'_>, found: &mut Vec<Symbol>) {
+    match e {
+        Expr::Nat(_) | Expr::Symbol(_) | Expr::Builtin(_) | Expr::Func(_) => {}
+        Expr::Record(v) => {
+            for field in &v.fields {
+                assigned_expr(&field.expr, found);
+            }
+        }
+        Expr::Variant(v) => {
+            if let Some(payload) = &v.payload {
+                assigned_expr(payload, found);
+            }
+        }
+        Expr::Apply(v) => {
+            assigned_expr(&v.func, found);
+            for field in &v.params.fields {
+                assigned_expr(&field.expr, found);
+            }
+        }
+        Expr::Select(v) => assigned_expr(&v.expr, found),
+        Expr::Block(v) => assigned(v, found),
+        Expr::Match(v) => {
+            assigned_expr(&v.expr, found);
+            for case in &v.cases {
+                assigned(&case.expr, found);
+            }
+        }
+        Expr::Assign(v) => {
+            assigned_expr(&v.expr, found);
+            if !found.contains(&v.target) {
+                found.push(v.target);
+            }
+        }
+        Expr::While(v) => {
+            assigned_expr(&v.cond, found);
+            assigned(&v.body, found);
+        }
+    }
+}
+
+/// The symbols a scope reads, in the order first read.
+fn used(scope: &Scope<'_>, found: &mut Vec<Symbol>) {
+    for stmt in &scope.stmts {
+        match stmt {
+            Stmt::Decl(Decl::Let(v)) => used_expr(&v.expr, found),
+            Stmt::Decl(Decl::Var(v)) => used_expr(&v.expr, found),
+            Stmt::Decl(Decl::TypeAlias(_)) => {}
+            Stmt::Expr(v) => used_expr(v, found),
+        }
+    }
+    used_expr(&scope.expr, found);
+}
+
+fn used_expr(e: &Expr<'_>, found: &mut Vec<Symbol>) {
+    match e {
+        Expr::Nat(_) | Expr::Builtin(_) => {}
+        Expr::Symbol(v) => {
+            if !found.contains(&v.sym) {
+                found.push(v.sym);
+            }
+        }
+        Expr::Record(v) => {
+            for field in &v.fields {
+                used_expr(&field.expr, found);
+            }
+        }
+        Expr::Variant(v) => {
+            if let Some(payload) = &v.payload {
+                used_expr(payload, found);
+            }
+        }
+        Expr::Apply(v) => {
+            used_expr(&v.func, found);
+            for field in &v.params.fields {
+                used_expr(&field.expr, found);
+            }
+        }
+        Expr::Select(v) => used_expr(&v.expr, found),
+        Expr::Block(v) => used(v, found),
+        Expr::Func(v) => used(&v.body, found),
+        Expr::Match(v) => {
+            used_expr(&v.expr, found);
+            for case in &v.cases {
+                used(&case.expr, found);
+            }
+        }
+        Expr::Assign(v) => used_expr(&v.expr, found),
+        Expr::While(v) => {
+            used_expr(&v.cond, found);
+            used(&v.body, found);
+        }
+    }
+}
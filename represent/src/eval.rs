@@ -0,0 +1,105 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Constant evaluation of lowered programs.
+//!
+//! Expressions made of literals, builtin operators and the `let`s they name are folded into
+//! their values, as natural numbers of any size. Nothing wraps, as the type of a sum or product
+//! is wide enough to hold it. An expression has no value if it reads a `var` or a parameter, if
+//! it would be negative or divide by zero, or if it uses `~`, `<<`, `trunc` or `ext`, whose
+//! values depend on the width of a type evaluation does not see.
+
+use crate::sym::*;
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet};
+
+/// The value of every top-level `let` of a program that has one.
+pub fn constants(program: &Program<'_>) -> HashMap<Symbol, BigUint> {
+    let mut evaluator = Evaluator::new(program);
+    let mut values = HashMap::new();
+    for module in &program.modules {
+        for decl in &module.decls.vals {
+            if let Decl::Let(v) = decl
+                && let Some(val) = evaluator.value(v.sym)
+            {
+                values.insert(v.sym, val);
+            }
+        }
+    }
+    values
+}
+
+pub struct Evaluator<'p, 'a> {
+    program: &'p Program<'a>,
+    /// The `let`s evaluated so far, `None` for those with no value.
+    values: HashMap<Symbol, Option<BigUint>>,
+    /// The `let`s being evaluated, so that one naming itself has no value.
+    pending: HashSet<Symbol>,
+}
+
+impl<'p, 'a> Evaluator<'p, 'a> {
+    pub fn new(program: &'p Program<'a>) -> Evaluator<'p, 'a> {
+        Evaluator { program, values: HashMap::new(), pending: HashSet::new() }
+    }
+
+    /// The value of the `let` declaring `sym`, or `None` if it is not constant.
+    pub fn value(&mut self, sym: Symbol) -> Option<BigUint> {
+        if let Some(val) = self.values.get(&sym) {
+            return val.clone();
+        }
+        let Some(Decl::Let(v)) = self.program.decl(sym) else { return None };
+        if !self.pending.insert(sym) {
+            return None;
+        }
+        let val = self.eval(&v.expr);
+        self.pending.remove(&sym);
+        self.values.insert(sym, val.clone());
+        val
+    }
+
+    /// The value of an expression, or `None` if it is not constant.
+    pub fn eval(&mut self, expr: &Expr<'a>) -> Option<BigUint> {
+        match expr {
+            Expr::Nat(v) => Some(v.val.clone()),
+            Expr::Symbol(v) => self.value(v.sym),
+            // Only the declarations of a block run before its result.
+            Expr::Block(v) if v.stmts.iter().all(|stmt| matches!(stmt, Stmt::Decl(_))) => self.eval(&v.expr),
+            Expr::Apply(v) => {
+                let Expr::Builtin(func) = &v.func else { return None };
+                let args = v.params.fields.iter().map(|field| self.eval(&field.expr)).collect::<Option<Vec<_>>>()?;
+                builtin(func.builtin, &args)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn truth(cond: bool) -> BigUint {
+    BigUint::from(u8::from(cond))
+}
+
+fn builtin(builtin: Builtin, args: &[BigUint]) -> Option<BigUint> {
+    let zero = BigUint::ZERO;
+    Some(match (builtin, args) {
+        (Builtin::Not, [a]) => truth(*a == zero),
+        (Builtin::Add, [a, b]) => a + b,
+        (Builtin::Sub, [a, b]) if a >= b => a - b,
+        (Builtin::Mul, [a, b]) => a * b,
+        (Builtin::Div, [a, b]) if *b != zero => a / b,
+        (Builtin::Mod, [a, b]) if *b != zero => a % b,
+        (Builtin::And, [a, b]) => a & b,
+        (Builtin::Or, [a, b]) => a | b,
+        (Builtin::Xor, [a, b]) => a ^ b,
+        (Builtin::Shr, [a, b]) => u32::try_from(b).map_or(zero, |b| a >> b),
+        (Builtin::Eq, [a, b]) => truth(a == b),
+        (Builtin::Ne, [a, b]) => truth(a != b),
+        (Builtin::Lt, [a, b]) => truth(a < b),
+        (Builtin::Le, [a, b]) => truth(a <= b),
+        (Builtin::Gt, [a, b]) => truth(a > b),
+        (Builtin::Ge, [a, b]) => truth(a >= b),
+        (Builtin::LogicAnd, [a, b]) => truth(*a != zero && *b != zero),
+        (Builtin::LogicOr, [a, b]) => truth(*a != zero || *b != zero),
+        _ => return None,
+    })
+}
@@ -0,0 +1,269 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Sizes of types in bits, as synthesis lays values out on wires.
+//!
+//! A record takes the bits of its fields side by side, and a union those of a tag numbering its
+//! variants next to those of its widest payload. A type alias that leads back to itself, as
+//! `type List = union { Nil: (), Cons: (Nat, List) }` does, has no finite size. Which aliases do
+//! is found by a fixpoint over the aliases each one refers to, so that aliases referring to one
+//! another are found along with those referring to themselves. Values of such types are fine
+//! on the CPU, where they live in memory, but not in a function synthesized to hardware.
+
+use crate::check::Typing;
+use crate::sym::*;
+use paracell_parser_sem::diag::Diagnostic;
+use std::collections::{HashMap, HashSet};
+
+/// How many bits the values of a type take.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Size {
+    Bits(u64),
+    /// Of a type holding values of the recursive alias.
+    Recursive(Symbol),
+    /// Of a number without a width, or of a function, which is not data.
+    Unbounded,
+    /// Of a type with variables or generic parameters left in it.
+    Unknown,
+}
+
+impl Size {
+    /// The size of two values side by side. A recursive alias is the first thing told of one
+    /// that has no size.
+    fn and(self, other: Size) -> Size {
+        match (self, other) {
+            (Size::Bits(a), Size::Bits(b)) => Size::Bits(a.saturating_add(b)),
+            (v @ Size::Recursive(_), _) | (_, v @ Size::Recursive(_)) => v,
+            (Size::Unbounded, _) | (_, Size::Unbounded) => Size::Unbounded,
+            _ => Size::Unknown,
+        }
+    }
+
+    /// The size of either of two values.
+    fn or(self, other: Size) -> Size {
+        match (self, other) {
+            (Size::Bits(a), Size::Bits(b)) => Size::Bits(a.max(b)),
+            (a, b) => a.and(b),
+        }
+    }
+}
+
+/// The aliases of a program, and which of them are recursive.
+pub struct Layout<'p, 'a> {
+    program: &'p Program<'a>,
+    recursive: HashSet<Symbol>,
+}
+
+impl<'p, 'a> Layout<'p, 'a> {
+    pub fn new(program: &'p Program<'a>) -> Layout<'p, 'a> {
+        Layout { program, recursive: recursive(program) }
+    }
+
+    /// Whether the alias `sym` leads back to itself.
+    pub fn is_recursive(&self, sym: Symbol) -> bool {
+        self.recursive.contains(&sym)
+    }
+
+    pub fn size(&self, ty: &Type<'a>) -> Size {
+        match ty {
+            Type::Primitive(PrimitiveType::Nat(v)) => match v.width {
+                Width::Const(width) => Size::Bits(width.into()),
+                Width::Unbounded => Size::Unbounded,
+                _ => Size::Unknown,
+            },
+            Type::Record(v) => v.borrow().fields.iter().fold(Size::Bits(0), |size, field| size.and(self.size(&field.ty))),
+            Type::Union(v) => {
+                let v = v.borrow();
+                let tag = usize::BITS - v.variants.len().saturating_sub(1).leading_zeros();
+                let payload = v.variants.iter().fold(Size::Bits(0), |size, variant| size.or(self.size(&variant.ty)));
+                Size::Bits(tag.into()).and(payload)
+            }
+            Type::Func(_) => Size::Unbounded,
+            Type::Alias(sym, _) if self.is_recursive(*sym) => Size::Recursive(*sym),
+            Type::Alias(sym, args) => match self.program.decl(*sym) {
+                Some(Decl::TypeAlias(alias)) => {
+                    let args = alias.generics.iter().map(|param| param.sym()).zip(args.iter().cloned()).collect();
+                    self.size(&alias.ty.subst(&args))
+                }
+                _ => Size::Unknown,
+            },
+            Type::Param(_) | Type::Var(_) => Size::Unknown,
+        }
+    }
+}
+
+/// The aliases a type names, with those named in the arguments of each.
+fn refs(ty: &Type<'_>, found: &mut HashSet<Symbol>) {
+    match ty {
+        Type::Primitive(_) | Type::Param(_) | Type::Var(_) => {}
+        Type::Record(v) => {
+            for field in &v.borrow().fields {
+                refs(&field.ty, found);
+            }
+        }
+        Type::Union(v) => {
+            for variant in &v.borrow().variants {
+                refs(&variant.ty, found);
+            }
+        }
+        Type::Func(v) => {
+            for field in &v.params.fields {
+                refs(&field.ty, found);
+            }
+            refs(&v.results, found);
+        }
+        Type::Alias(sym, args) => {
+            found.insert(*sym);
+            for arg in args {
+                if let GenericArg::Type(ty) = arg {
+                    refs(ty, found);
+                }
+            }
+        }
+    }
+}
+
+/// The aliases of a program that lead back to themselves. Each alias reaches those it names and
+/// whatever they reach, which is grown until it stops changing.
+pub fn recursive(program: &Program<'_>) -> HashSet<Symbol> {
+    let mut reach: HashMap<Symbol, HashSet<Symbol>> = program.symbols.iter()
+        .filter_map(|info| match info.decl {
+            Some(Decl::TypeAlias(alias)) => {
+                let mut found = HashSet::new();
+                refs(&alias.ty, &mut found);
+                Some((alias.sym, found))
+            }
+            _ => None,
+        })
+        .collect();
+    loop {
+        let mut changed = false;
+        let syms: Vec<_> = reach.keys().copied().collect();
+        for sym in syms {
+            let next: HashSet<_> = reach[&sym].iter().filter_map(|to| reach.get(to)).flatten().copied().collect();
+            let set = reach.get_mut(&sym).unwrap();
+            let before = set.len();
+            set.extend(next);
+            changed |= set.len() != before;
+        }
+        if !changed {
+            break;
+        }
+    }
+    reach.into_iter().filter(|(sym, set)| set.contains(sym)).map(|(sym, _)| sym).collect()
+}
+
+/// Checks that the function `func` can be synthesized to hardware, reporting each of its
+/// parameters, results and bindings whose type is recursive.
+pub fn synthesizable<'a>(program: &Program<'a>, typing: &Typing<'a>, func: Symbol) -> Vec<(usize, Diagnostic)> {
+    let layout = Layout::new(program);
+    let info = program.symbol(func);
+    let Some(Decl::Let(LetDecl { expr: Expr::Func(v), .. })) = info.decl else { return Vec::new() };
+    let mut uses = vec![(info.span, format!("`{}` returns", info.ident), v.ty.results.clone())];
+    let mut syms = v.params.clone();
+    scope(&v.body, &mut syms);
+    for sym in syms {
+        if let Some(ty) = typing.types.get(&sym) {
+            let bound = program.symbol(sym);
+            uses.push((bound.span, format!("`{}` is", bound.ident), ty.clone()));
+        }
+    }
+    let mut diagnostics = Vec::new();
+    for (span, what, ty) in uses {
+        let Size::Recursive(alias) = layout.size(&ty) else { continue };
+        let alias = program.symbol(alias);
+        let mut diag = Diagnostic::error("E2012", format!("`{}` cannot be synthesized, as `{}` is recursive", info.ident, alias.ident))
+            .with_primary(span, format!("{} `{}`, which has no finite size", what, program.type_name(&ty)));
+        if alias.module == info.module {
+            diag = diag.with_secondary(alias.span, "leads back to itself");
+        }
+        diagnostics.push((info.module, diag.with_help("run this function on the CPU, or bound the type")));
+    }
+    diagnostics
+}
+
+/// The symbols bound within a scope, in order.
+fn scope(scope: &Scope<'_>, syms: &mut Vec<Symbol>) {
+    for stmt in &scope.stmts {
+        match stmt {
+            Stmt::Decl(Decl::Let(v)) => {
+                syms.push(v.sym);
+                expr(&v.expr, syms);
+            }
+            Stmt::Decl(Decl::Var(v)) => {
+                syms.push(v.sym);
+                expr(&v.expr, syms);
+            }
+            Stmt::Decl(Decl::TypeAlias(_)) => {}
+            Stmt::Expr(v) => expr(v, syms),
+        }
+    }
+    expr(&scope.expr, syms);
+}
+
+fn expr(e: &Expr<'_>, syms: &mut Vec<Symbol>) {
+    match e {
+        Expr::Nat(_) | Expr::Symbol(_) | Expr::Builtin(_) => {}
+        Expr::Record(v) => {
+            for field in &v.fields {
+                expr(&field.expr, syms);
+            }
+        }
+        Expr::Variant(v) => {
+            if let Some(payload) = &v.payload {
+                expr(payload, syms);
+            }
+        }
+        Expr::Apply(v) => {
+            expr(&v.func, syms);
+            for field in &v.params.fields {
+                expr(&field.expr, syms);
+            }
+        }
+        Expr::Select(v) => expr(&v.expr, syms),
+        Expr::Block(v) => scope(v, syms),
+        Expr::Func(v) => {
+            syms.extend(&v.params);
+            scope(&v.body, syms);
+        }
+        Expr::Match(v) => {
+            expr(&v.expr, syms);
+            for case in &v.cases {
+                pattern(&case.pattern, syms);
+                scope(&case.expr, syms);
+            }
+        }
+        Expr::Assign(v) => expr(&v.expr, syms),
+        Expr::While(v) => {
+            expr(&v.cond, syms);
+            scope(&v.body, syms);
+        }
+    }
+}
+
+fn pattern(p: &Pattern, syms: &mut Vec<Symbol>) {
+    match p {
+        Pattern::Wildcard(_) | Pattern::Nat(_) | Pattern::Range(_) => {}
+        Pattern::Bind(v) => {
+            if !syms.contains(&v.sym) {
+                syms.push(v.sym);
+            }
+        }
+        Pattern::Variant(v) => {
+            if let Some(payload) = &v.payload {
+                pattern(payload, syms);
+            }
+        }
+        Pattern::Record(v) => {
+            for field in &v.fields {
+                pattern(&field.pattern, syms);
+            }
+        }
+        Pattern::Or(v) => {
+            for alt in &v.alts {
+                pattern(alt, syms);
+            }
+        }
+    }
+}
@@ -2,4 +2,14 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
+pub mod check;
+pub mod cse;
+pub mod dataflow;
+pub mod eval;
+pub mod layout;
+pub mod lower;
+pub mod matching;
+pub mod mono;
+pub mod parallelism;
+pub mod resolve;
 pub mod sym;
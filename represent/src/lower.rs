@@ -0,0 +1,470 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Lowering of resolved `sem` trees to the rich IR.
+//!
+//! Every declaration, parameter and pattern binding becomes a [`Symbol`], and every name the
+//! resolver bound becomes a reference to one, or to a builtin. Blocks become [`Scope`]s, binary
+//! and unary operators become applications of builtins, and `a |> f` becomes `f(a)`.
+
+use crate::resolve::{Binding, Resolution, resolve};
+use crate::sym::*;
+use paracell_parser_sem::diag::Diagnostic;
+use paracell_parser_sem::module;
+use paracell_parser_sem::sem;
+use paracell_parser_sem::span::Span;
+use paracell_util_struct::map::OrderedHashMap;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use typed_arena::Arena;
+
+/// Resolves and lowers a file on its own, as the root module of a program.
+pub fn lower<'a>(file: &sem::SourceFile, arena: &'a Arena<Decl<'a>>) -> Result<Program<'a>, Vec<(usize, Diagnostic)>> {
+    let resolution = resolve(file);
+    if resolution.has_errors() {
+        return Err(resolution.diagnostics);
+    }
+    Lowerer::new(arena, &resolution).run(vec![(String::new(), file)])
+}
+
+/// Lowers every module of a program. Fails with the diagnostics of the resolution if it has errors.
+pub fn lower_program<'a, 's>(
+    program: &'s module::Program,
+    resolution: &Resolution<'s>,
+    arena: &'a Arena<Decl<'a>>,
+) -> Result<Program<'a>, Vec<(usize, Diagnostic)>> {
+    if resolution.has_errors() {
+        return Err(resolution.diagnostics.clone());
+    }
+    Lowerer::new(arena, resolution).run(program.modules.iter().map(|m| (m.path.to_string(), &m.file)).collect())
+}
+
+/// Identifies a `sem` node that binds a name by its address.
+fn key<T>(node: &T) -> *const () {
+    node as *const T as *const ()
+}
+
+/// What a binding that is not a value is, for diagnostics.
+fn describe(binding: Binding) -> &'static str {
+    match binding {
+        Binding::Decl(sem::Decl::TypeAlias(_)) => "a type",
+        Binding::Builtin(builtin) if builtin.kind().is_some() => "a type",
+        Binding::Generic(sem::GenericParam::Type(_)) => "a type",
+        Binding::Generic(sem::GenericParam::Width(_)) => "a width",
+        Binding::Module(_) => "a module",
+        _ => "a value",
+    }
+}
+
+struct Lowerer<'a, 'r, 's> {
+    arena: &'a Arena<Decl<'a>>,
+    resolution: &'r Resolution<'s>,
+    module: usize,
+    symbols: Vec<SymbolInfo<'a>>,
+    keys: HashMap<*const (), Symbol>,
+    vars: usize,
+    errors: Vec<(usize, Diagnostic)>,
+}
+
+impl<'a, 'r, 's> Lowerer<'a, 'r, 's> {
+    fn new(arena: &'a Arena<Decl<'a>>, resolution: &'r Resolution<'s>) -> Self {
+        Lowerer { arena, resolution, module: 0, symbols: Vec::new(), keys: HashMap::new(), vars: 0, errors: Vec::new() }
+    }
+
+    fn run(mut self, files: Vec<(String, &'s sem::SourceFile)>) -> Result<Program<'a>, Vec<(usize, Diagnostic)>> {
+        // Top-level declarations may be used before they are declared, even from other modules.
+        for (module, (_, file)) in files.iter().enumerate() {
+            self.module = module;
+            for decl in &file.decls {
+                self.symbol(key(decl), decl.ident(), SymbolKind::Decl, decl.span());
+            }
+        }
+        let mut modules = Vec::new();
+        for (module, (path, file)) in files.into_iter().enumerate() {
+            self.module = module;
+            let mut decls = OrderedHashMap::new();
+            for decl in &file.decls {
+                let decl = self.decl(decl);
+                decls.insert(decl.ident(), decl);
+            }
+            modules.push(Module { path, decls });
+        }
+        if self.errors.is_empty() {
+            Ok(Program { symbols: self.symbols, modules, vars: self.vars })
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn report(&mut self, diag: Diagnostic) {
+        self.errors.push((self.module, diag));
+    }
+
+    /// The symbol of the node at `key`, bound the first time it is asked for.
+    fn symbol(&mut self, key: *const (), ident: &str, kind: SymbolKind, span: Span) -> Symbol {
+        if let Some(sym) = self.keys.get(&key) {
+            return *sym;
+        }
+        let sym = Symbol(self.symbols.len());
+        self.symbols.push(SymbolInfo { ident: ident.to_string(), kind, module: self.module, span, decl: None });
+        self.keys.insert(key, sym);
+        sym
+    }
+
+    /// The symbol of a node whose binding was lowered already.
+    fn bound(&self, key: *const ()) -> Symbol {
+        *self.keys.get(&key).expect("names are lowered after what binds them")
+    }
+
+    fn binding(&self, span: Span) -> Binding<'s> {
+        self.resolution.binding(self.module, span).expect("resolution without errors binds every name")
+    }
+
+    fn decl(&mut self, decl: &'s sem::Decl) -> &'a Decl<'a> {
+        // Functions and types may refer to themselves, so the symbol comes before the body.
+        let sym = self.symbol(key(decl), decl.ident(), SymbolKind::Decl, decl.span());
+        let lowered = match decl {
+            sem::Decl::Let(v) => {
+                let generics = self.generics(&v.generics);
+                let ty = v.ty.as_ref().map(|ty| self.ty(ty));
+                Decl::Let(LetDecl { sym, ident: v.ident.clone(), generics, ty, expr: self.expr(&v.expr), span: v.span })
+            }
+            sem::Decl::Var(v) => {
+                let ty = v.ty.as_ref().map(|ty| self.ty(ty));
+                Decl::Var(VarDecl { sym, ident: v.ident.clone(), ty, expr: self.expr(&v.expr), span: v.span })
+            }
+            sem::Decl::TypeAlias(v) => {
+                let generics = self.generics(&v.generics);
+                Decl::TypeAlias(TypeAliasDecl { sym, ident: v.ident.clone(), generics, ty: self.ty(&v.ty), span: v.span })
+            }
+        };
+        let arena = self.arena;
+        let decl = &*arena.alloc(lowered);
+        self.symbols[sym.0].decl = Some(decl);
+        decl
+    }
+
+    fn generics(&mut self, generics: &'s [sem::GenericParam]) -> Vec<GenericParam> {
+        generics.iter().map(|param| {
+            let sym = self.symbol(key(param), &param.ident().lit, SymbolKind::Generic, param.span());
+            match param {
+                sem::GenericParam::Type(_) => GenericParam::Type(sym),
+                sem::GenericParam::Width(_) => GenericParam::Width(sym),
+            }
+        }).collect()
+    }
+
+    fn ty(&mut self, ty: &'s sem::Type) -> Type<'a> {
+        match ty {
+            sem::Type::Ident(v) => match self.binding(v.span) {
+                Binding::Builtin(builtin) if let Some(kind) = builtin.kind() => {
+                    Type::Primitive(PrimitiveType::Nat(NatType { kind, width: Width::Unbounded }))
+                }
+                Binding::Decl(decl @ sem::Decl::TypeAlias(alias)) if alias.generics.is_empty() => {
+                    Type::Alias(self.bound(key(decl)), Vec::new())
+                }
+                Binding::Decl(sem::Decl::TypeAlias(alias)) => {
+                    self.report(Diagnostic::error("E1008", format!(
+                        "`{}` takes {} parameters but 0 were given", v.lit, alias.generics.len(),
+                    )).with_primary(v.span, "expected parameters after it"));
+                    Type::unit()
+                }
+                Binding::Generic(param @ sem::GenericParam::Type(_)) => Type::Param(self.bound(key(param))),
+                binding => {
+                    self.report(Diagnostic::error("E1004", format!("expected a type, found `{}`", v.lit))
+                        .with_primary(v.span, format!("`{}` is {}", v.lit, describe(binding))));
+                    Type::unit()
+                }
+            },
+            sem::Type::Apply(v) => self.apply_type(v),
+            sem::Type::Record(v) => Type::Record(RefCell::new(self.record_type(v))),
+            sem::Type::Union(v) => {
+                let variants = v.variants.iter().map(|variant| Variant { ident: variant.ident.clone(), ty: self.ty(&variant.ty) }).collect();
+                Type::Union(RefCell::new(UnionType::new(variants)))
+            }
+            sem::Type::Func(v) => Type::Func(Box::from(self.func_type(v))),
+            sem::Type::Infer(_) => Type::Var(TypeVar(self.var())),
+        }
+    }
+
+    /// A variable for a `_` in the source.
+    fn var(&mut self) -> usize {
+        self.vars += 1;
+        self.vars - 1
+    }
+
+    /// `Nat[8]`, `Bits[_]` to have the width inferred, or `Vec[Nat, 8]` of a generic alias.
+    fn apply_type(&mut self, ty: &'s sem::ApplyType) -> Type<'a> {
+        let kind = match self.binding(ty.ty.span) {
+            Binding::Builtin(builtin) if let Some(kind) = builtin.kind() => kind,
+            Binding::Decl(decl @ sem::Decl::TypeAlias(alias)) if !alias.generics.is_empty() => {
+                return self.instance(ty, self.bound(key(decl)), &alias.generics);
+            }
+            binding => {
+                let diag = match binding {
+                    Binding::Decl(sem::Decl::TypeAlias(_)) => Diagnostic::error("E1008", format!("`{}` takes no parameters", ty.ty.lit))
+                        .with_primary(ty.span, "given parameters here"),
+                    binding => Diagnostic::error("E1004", format!("expected a type, found `{}`", ty.ty.lit))
+                        .with_primary(ty.ty.span, format!("`{}` is {}", ty.ty.lit, describe(binding))),
+                };
+                self.report(diag);
+                return Type::unit();
+            }
+        };
+        let width = match ty.params.as_slice() {
+            [param] => self.width(&ty.ty.lit, param),
+            params => {
+                self.report(Diagnostic::error("E1008", format!("`{}` takes 1 parameter but {} were given", ty.ty.lit, params.len()))
+                    .with_primary(ty.span, "expected a width"));
+                Width::Unbounded
+            }
+        };
+        Type::Primitive(PrimitiveType::Nat(NatType { kind, width }))
+    }
+
+    /// A width given to the type `name`: a number, `_`, or a width parameter.
+    fn width(&mut self, name: &str, param: &'s sem::TypeParam) -> Width {
+        match param {
+            sem::TypeParam::Nat(v) if v.width.is_none() && let Ok(width) = u32::try_from(&v.val) => Width::Const(width),
+            sem::TypeParam::Type(sem::Type::Infer(_)) => Width::Var(WidthVar(self.var())),
+            sem::TypeParam::Type(sem::Type::Ident(v)) if let Binding::Generic(param @ sem::GenericParam::Width(_)) = self.binding(v.span) => {
+                Width::Param(self.bound(key(param)))
+            }
+            param => {
+                self.report(Diagnostic::error("E1007", "expected a width")
+                    .with_primary(param.span(), "not a number of bits")
+                    .with_help(format!("write `{}[8]`, or `{}[_]` to have the width inferred", name, name)));
+                Width::Unbounded
+            }
+        }
+    }
+
+    /// A generic alias given one argument per parameter, in order.
+    fn instance(&mut self, ty: &'s sem::ApplyType, alias: Symbol, generics: &'s [sem::GenericParam]) -> Type<'a> {
+        if ty.params.len() != generics.len() {
+            self.report(Diagnostic::error("E1008", format!(
+                "`{}` takes {} parameters but {} were given", ty.ty.lit, generics.len(), ty.params.len(),
+            )).with_primary(ty.span, format!("expected `{}`", ty.ty.lit)));
+            return Type::unit();
+        }
+        let args = generics.iter().zip(&ty.params).map(|(generic, param)| match (generic, param) {
+            (sem::GenericParam::Width(_), param) => GenericArg::Width(self.width(&ty.ty.lit, param)),
+            (sem::GenericParam::Type(_), sem::TypeParam::Type(param)) => GenericArg::Type(self.ty(param)),
+            (sem::GenericParam::Type(generic), sem::TypeParam::Nat(v)) => {
+                self.report(Diagnostic::error("E1004", format!("expected a type, found `{}`", v))
+                    .with_primary(v.span, format!("`{}` of `{}` is a type", generic.lit, ty.ty.lit)));
+                GenericArg::Type(Type::unit())
+            }
+        }).collect();
+        Type::Alias(alias, args)
+    }
+
+    fn record_type(&mut self, record: &'s sem::RecordType) -> RecordType<'a> {
+        RecordType::new(record.fields.iter().map(|field| Field { ident: field.ident.clone(), ty: self.ty(&field.ty) }).collect())
+    }
+
+    fn func_type(&mut self, ty: &'s sem::FuncType) -> FuncType<'a> {
+        FuncType { params: self.record_type(&ty.params), results: self.ty(&ty.result) }
+    }
+
+    /// A name used as a value.
+    fn value(&mut self, binding: Binding<'s>, name: &str, span: Span) -> Expr<'a> {
+        let sym = match binding {
+            Binding::Decl(decl @ (sem::Decl::Let(_) | sem::Decl::Var(_))) => self.bound(key(decl)),
+            Binding::Param(field) => self.bound(key(field)),
+            Binding::Pattern(ident) => self.bound(key(ident)),
+            Binding::Builtin(builtin) if builtin.kind().is_none() => return Expr::Builtin(BuiltinExpr { builtin, span }),
+            binding => {
+                self.report(Diagnostic::error("E1005", format!("expected a value, found `{}`", name))
+                    .with_primary(span, format!("`{}` is {}", name, describe(binding))));
+                return Expr::Record(RecordExpr::unit(span));
+            }
+        };
+        Expr::Symbol(SymbolExpr { sym, span })
+    }
+
+    /// The alias and the variant `Op::Add` names, if it names one.
+    fn variant(&self, path: &sem::Path) -> Option<(Symbol, String)> {
+        match (self.binding(path.span), path.segments.as_slice()) {
+            (Binding::Decl(decl @ sem::Decl::TypeAlias(_)), [ty, variant]) if ty == decl.ident() => {
+                Some((self.bound(key(decl)), variant.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    fn path(&mut self, path: &'s sem::Path) -> Expr<'a> {
+        if let Some((ty, ident)) = self.variant(path) {
+            return Expr::Variant(VariantExpr { ty, ident, payload: None, span: path.span });
+        }
+        let name = path.segments.join("::");
+        match self.binding(path.span) {
+            // Through modules, as in `alu::ALU`.
+            Binding::Decl(decl) if path.segments.last().is_some_and(|last| last == decl.ident()) => {
+                self.value(Binding::Decl(decl), &name, path.span)
+            }
+            Binding::Module(module) => self.value(Binding::Module(module), &name, path.span),
+            _ => {
+                self.report(Diagnostic::error("E1005", format!("expected a value, found `{}`", name))
+                    .with_primary(path.span, format!("`{}` has no variants", path.segments[0])));
+                Expr::Record(RecordExpr::unit(path.span))
+            }
+        }
+    }
+
+    fn record(&mut self, record: &'s sem::RecordExpr) -> RecordExpr<'a> {
+        RecordExpr {
+            fields: record.fields.iter().map(|field| FieldExpr { ident: field.ident.clone(), expr: self.expr(&field.expr) }).collect(),
+            span: record.span,
+        }
+    }
+
+    /// Applying a variant without a payload gives it one: the only positional field, or the record.
+    fn apply(func: Expr<'a>, params: RecordExpr<'a>, span: Span) -> Expr<'a> {
+        match func {
+            Expr::Variant(variant) if variant.payload.is_none() => {
+                let payload = match params.fields.as_slice() {
+                    [field] if field.ident == "0" => params.fields.into_iter().next().unwrap().expr,
+                    _ => Expr::Record(params),
+                };
+                Expr::Variant(VariantExpr { payload: Some(Box::from(payload)), span, ..variant })
+            }
+            func => Expr::Apply(Box::from(ApplyExpr { func, params, span })),
+        }
+    }
+
+    fn expr(&mut self, expr: &'s sem::Expr) -> Expr<'a> {
+        match expr {
+            sem::Expr::Nat(v) => Expr::Nat(NatExpr { val: v.val.clone(), width: v.width, span: v.span }),
+            sem::Expr::Ident(v) => {
+                let binding = self.binding(v.span);
+                self.value(binding, &v.lit, v.span)
+            }
+            sem::Expr::Path(v) => self.path(v),
+            sem::Expr::Block(v) => Expr::Block(Box::from(self.block(v))),
+            sem::Expr::Func(v) => {
+                let ty = self.func_type(&v.ty);
+                let params = v.ty.params.fields.iter()
+                    .map(|field| self.symbol(key(field), &field.ident, SymbolKind::Param, field.span))
+                    .collect();
+                let body = self.block(&v.block);
+                Expr::Func(Box::from(FuncExpr { ty, params, body, span: v.span }))
+            }
+            sem::Expr::Record(v) => Expr::Record(self.record(v)),
+            sem::Expr::Apply(v) => {
+                let func = self.expr(&v.func);
+                let params = self.record(&v.params);
+                Lowerer::apply(func, params, v.span)
+            }
+            sem::Expr::Match(v) => {
+                let expr = self.expr(&v.expr);
+                let cases = v.cases.iter()
+                    .map(|case| Case { pattern: self.pattern(&case.pattern), expr: self.scope(&case.expr) })
+                    .collect();
+                Expr::Match(Box::from(Match { expr, cases, span: v.span }))
+            }
+            sem::Expr::Select(v) => Expr::Select(Box::from(SelectExpr { expr: self.expr(&v.expr), ident: v.ident.clone(), span: v.span })),
+            sem::Expr::Pipe(v) => {
+                let from = self.expr(&v.from);
+                let to = self.expr(&v.to);
+                let params = RecordExpr { fields: vec![FieldExpr { ident: 0.to_string(), expr: from }], span: v.from.span() };
+                Lowerer::apply(to, params, v.span)
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &'s sem::Pattern) -> Pattern {
+        match pattern {
+            sem::Pattern::Wildcard(v) => Pattern::Wildcard(v.span),
+            sem::Pattern::Nat(v) => Pattern::Nat(NatPattern { val: v.val.clone(), width: v.width, span: v.span }),
+            sem::Pattern::Range(v) => Pattern::Range(RangePattern { lo: v.lo.val.clone(), hi: v.hi.val.clone(), inclusive: v.inclusive, span: v.span }),
+            sem::Pattern::Bind(v) => {
+                let sym = match self.resolution.binding(self.module, v.span) {
+                    // A later alternative of an or-pattern.
+                    Some(Binding::Pattern(first)) => self.bound(key(first)),
+                    _ => self.symbol(key(v), &v.lit, SymbolKind::Pattern, v.span),
+                };
+                Pattern::Bind(BindPattern { sym, span: v.span })
+            }
+            sem::Pattern::Variant(v) => {
+                let payload = v.payload.as_ref().map(|payload| Box::from(self.pattern(payload)));
+                match self.variant(&v.path) {
+                    Some((ty, ident)) => Pattern::Variant(VariantPattern { ty, ident, payload, span: v.span }),
+                    None => {
+                        let name = v.path.segments.join("::");
+                        self.report(Diagnostic::error("E1006", format!("expected a union variant, found `{}`", name))
+                            .with_primary(v.path.span, "not a variant")
+                            .with_help("variants are written `Type::Variant`"));
+                        Pattern::Wildcard(v.span)
+                    }
+                }
+            }
+            sem::Pattern::Record(v) => Pattern::Record(RecordPattern {
+                fields: v.fields.iter().map(|field| FieldPattern { ident: field.ident.clone(), pattern: self.pattern(&field.pattern) }).collect(),
+                span: v.span,
+            }),
+            sem::Pattern::Or(v) => Pattern::Or(OrPattern { alts: v.alts.iter().map(|alt| self.pattern(alt)).collect(), span: v.span }),
+        }
+    }
+
+    /// The scope of a `match` arm, which is a block or a single expression.
+    fn scope(&mut self, expr: &'s sem::Expr) -> Scope<'a> {
+        match expr {
+            sem::Expr::Block(v) => self.block(v),
+            expr => Scope { decls: OrderedHashMap::new(), stmts: Vec::new(), expr: self.expr(expr), span: expr.span() },
+        }
+    }
+
+    fn block(&mut self, block: &'s sem::Block) -> Scope<'a> {
+        // Types may be used before they are declared in their block.
+        for stmt in &block.stmts {
+            if let sem::Stmt::Decl(decl @ sem::Decl::TypeAlias(_)) = stmt {
+                self.symbol(key(decl), decl.ident(), SymbolKind::Decl, decl.span());
+            }
+        }
+        let mut decls = OrderedHashMap::new();
+        let mut stmts = Vec::new();
+        for stmt in &block.stmts {
+            match stmt {
+                sem::Stmt::Decl(v) => {
+                    let decl = self.decl(v);
+                    decls.insert(decl.ident(), decl);
+                    stmts.push(Stmt::Decl(decl));
+                }
+                sem::Stmt::Expr(v) => stmts.push(Stmt::Expr(self.expr(v))),
+                sem::Stmt::Assign(v) => {
+                    let expr = self.expr(&v.expr);
+                    let target = match self.binding(v.target.span) {
+                        Binding::Decl(decl @ sem::Decl::Var(_)) => self.bound(key(decl)),
+                        binding => {
+                            let mut diag = Diagnostic::error("E1003", format!("cannot assign to `{}`", v.target.lit))
+                                .with_primary(v.target.span, "not a `var`");
+                            if let Binding::Decl(decl) = binding {
+                                diag = diag.with_secondary(decl.span(), "declared here")
+                                    .with_help("declare it with `var` to assign to it");
+                            }
+                            self.report(diag);
+                            continue;
+                        }
+                    };
+                    stmts.push(Stmt::Expr(Expr::Assign(Box::from(AssignExpr { target, expr, span: v.span }))));
+                }
+                sem::Stmt::While(v) => {
+                    let cond = self.expr(&v.cond);
+                    let body = self.block(&v.block);
+                    stmts.push(Stmt::Expr(Expr::While(Box::from(WhileExpr { cond, body, span: v.span }))));
+                }
+            }
+        }
+        let result = matches!(block.stmts.last(), Some(sem::Stmt::Expr(_)));
+        let expr = match stmts.pop() {
+            Some(Stmt::Expr(expr)) if result => expr,
+            stmt => {
+                stmts.extend(stmt);
+                Expr::Record(RecordExpr::unit(block.span))
+            }
+        };
+        Scope { decls, stmts, expr, span: block.span }
+    }
+}
@@ -0,0 +1,412 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Exhaustiveness and reachability of `match` arms.
+//!
+//! A `match` synthesizes to a mux, and a value no arm matches leaves it driving nothing in
+//! particular. The arms are taken as a matrix of patterns, one row per arm, and a pattern is
+//! useful below some rows if some value matches it and none of them. An arm that is not useful
+//! below the arms before it never runs, and the values a `_` below every arm would match are
+//! those the `match` does not cover. Columns are split by the constructors of their type: the
+//! variants of a union, the one constructor of a record, and for a number the runs of values the
+//! literals and ranges of the arms cut its values into, all of them for `Nat[8]` but never for
+//! a number without a width.
+
+use crate::check::Typing;
+use crate::sym::*;
+use num_bigint::BigUint;
+use paracell_parser_sem::diag::Diagnostic;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// How many values a `match` reports as not covered.
+const WITNESSES: usize = 3;
+
+/// Checks every `match` of a program, reporting arms that never run and values no arm matches.
+pub fn check<'a>(program: &Program<'a>, typing: &Typing<'a>) -> Vec<(usize, Diagnostic)> {
+    let mut matcher = Matcher { program, typing, module: 0, diagnostics: Vec::new() };
+    for (module, v) in program.modules.iter().enumerate() {
+        matcher.module = module;
+        for decl in &v.decls.vals {
+            matcher.decl(decl);
+        }
+    }
+    matcher.diagnostics
+}
+
+/// A pattern as the matrix sees it, with bindings as wildcards and numbers as ranges.
+#[derive(Clone, Debug)]
+enum Pat {
+    Wild,
+    /// A variant, with a wildcard for the payload of one written without.
+    Variant(String, Box<Pat>),
+    Record(Vec<(String, Pat)>),
+    /// The values from the first to the second, inclusive.
+    Range(BigUint, BigUint),
+    /// Matching nothing if empty, as an empty range does.
+    Or(Vec<Pat>),
+}
+
+impl Pat {
+    fn new(pattern: &Pattern) -> Pat {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Bind(_) => Pat::Wild,
+            Pattern::Nat(v) => Pat::Range(v.val.clone(), v.val.clone()),
+            Pattern::Range(v) if v.inclusive && v.lo <= v.hi => Pat::Range(v.lo.clone(), v.hi.clone()),
+            Pattern::Range(v) if !v.inclusive && v.lo < v.hi => Pat::Range(v.lo.clone(), &v.hi - 1u8),
+            Pattern::Range(_) => Pat::Or(Vec::new()),
+            Pattern::Variant(v) => Pat::Variant(v.ident.clone(), Box::from(v.payload.as_deref().map_or(Pat::Wild, Pat::new))),
+            Pattern::Record(v) => Pat::Record(v.fields.iter().map(|field| (field.ident.clone(), Pat::new(&field.pattern))).collect()),
+            Pattern::Or(v) => Pat::Or(v.alts.iter().map(Pat::new).collect()),
+        }
+    }
+}
+
+/// What the values of a column are built from.
+enum Shape<'a> {
+    /// The union an alias declares, by the name of the alias.
+    Union(String, Vec<Variant<'a>>),
+    Record(Vec<Field<'a>>),
+    /// Numbers up to the greatest value of their width, or without end.
+    Nat(Option<BigUint>),
+    /// A type whose values only wildcards match.
+    Opaque,
+}
+
+/// One way of building the values of a column.
+#[derive(Clone, Debug)]
+enum Ctor {
+    Variant(usize),
+    Record,
+    /// The values from the first up to the second, inclusive, or without end.
+    Values(BigUint, Option<BigUint>),
+}
+
+/// A value no arm matches, with wildcards for the parts that do not matter.
+#[derive(Clone, Debug)]
+enum Witness {
+    Wild,
+    /// The alias, the variant, and the payload unless it is `()`.
+    Variant(String, String, Option<Box<Witness>>),
+    Record(Vec<(String, Witness)>),
+    Nat(BigUint),
+}
+
+impl fmt::Display for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = |f: &mut fmt::Formatter<'_>, fields: &[(String, Witness)]| {
+            let positional = fields.iter().enumerate().all(|(i, (ident, _))| *ident == i.to_string());
+            for (i, (ident, w)) in fields.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ", ")?;
+                }
+                if !positional {
+                    write!(f, "{}: ", ident)?;
+                }
+                write!(f, "{}", w)?;
+            }
+            Ok(())
+        };
+        match self {
+            Witness::Wild => write!(f, "_"),
+            Witness::Variant(ty, ident, None) => write!(f, "{}::{}", ty, ident),
+            Witness::Variant(ty, ident, Some(payload)) => match payload.as_ref() {
+                Witness::Record(v) => {
+                    write!(f, "{}::{}(", ty, ident)?;
+                    fields(f, v)?;
+                    write!(f, ")")
+                }
+                payload => write!(f, "{}::{}({})", ty, ident, payload),
+            },
+            Witness::Record(v) => {
+                write!(f, "(")?;
+                fields(f, v)?;
+                write!(f, ")")
+            }
+            Witness::Nat(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+struct Matcher<'p, 'a> {
+    program: &'p Program<'a>,
+    typing: &'p Typing<'a>,
+    module: usize,
+    diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl<'p, 'a> Matcher<'p, 'a> {
+    fn decl(&mut self, decl: &Decl<'a>) {
+        match decl {
+            Decl::Let(v) => self.expr(&v.expr),
+            Decl::Var(v) => self.expr(&v.expr),
+            Decl::TypeAlias(_) => {}
+        }
+    }
+
+    fn scope(&mut self, scope: &Scope<'a>) {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(v) => self.decl(v),
+                Stmt::Expr(v) => self.expr(v),
+            }
+        }
+        self.expr(&scope.expr);
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) {
+        match e {
+            Expr::Nat(_) | Expr::Symbol(_) | Expr::Builtin(_) => {}
+            Expr::Record(v) => {
+                for field in &v.fields {
+                    self.expr(&field.expr);
+                }
+            }
+            Expr::Variant(v) => {
+                if let Some(payload) = &v.payload {
+                    self.expr(payload);
+                }
+            }
+            Expr::Apply(v) => {
+                self.expr(&v.func);
+                for field in &v.params.fields {
+                    self.expr(&field.expr);
+                }
+            }
+            Expr::Select(v) => self.expr(&v.expr),
+            Expr::Block(v) => self.scope(v),
+            Expr::Func(v) => self.scope(&v.body),
+            Expr::Match(v) => {
+                self.expr(&v.expr);
+                for case in &v.cases {
+                    self.scope(&case.expr);
+                }
+                self.check(v);
+            }
+            Expr::Assign(v) => self.expr(&v.expr),
+            Expr::While(v) => {
+                self.expr(&v.cond);
+                self.scope(&v.body);
+            }
+        }
+    }
+
+    fn check(&mut self, v: &Match<'a>) {
+        let Some(ty) = self.typing.scrutinees.get(&(self.module, v.span)) else { return };
+        let tys = [ty.clone()];
+        let mut rows = Vec::new();
+        for case in &v.cases {
+            let row = vec![Pat::new(&case.pattern)];
+            if self.witnesses(&rows, &row, &tys, 1).is_empty() {
+                let diag = Diagnostic::warning("W2001", "unreachable arm")
+                    .with_primary(case.pattern.span(), "every value this matches is matched by an arm above");
+                self.diagnostics.push((self.module, diag));
+            }
+            rows.push(row);
+        }
+        let missing = self.witnesses(&rows, &[Pat::Wild], &tys, WITNESSES + 1);
+        if missing.is_empty() {
+            return;
+        }
+        let mut names: Vec<_> = missing.iter().take(WITNESSES).map(|w| format!("`{}`", w[0])).collect();
+        let label = match names.len() {
+            1 => format!("{} not covered", names[0]),
+            _ => {
+                let last = names.pop().unwrap();
+                let more = if missing.len() > WITNESSES { " and more" } else { "" };
+                format!("{} and {}{} not covered", names.join(", "), last, more)
+            }
+        };
+        let diag = Diagnostic::error("E2013", "non-exhaustive match")
+            .with_primary(v.expr.span(), label)
+            .with_help("a mux given a value no arm matches drives nothing in particular; add arms for these, or a `_` arm");
+        self.diagnostics.push((self.module, diag));
+    }
+
+    fn shape(&self, ty: &Type<'a>) -> Shape<'a> {
+        match ty {
+            Type::Primitive(PrimitiveType::Nat(v)) => match (v.kind, v.width.fold()) {
+                (NatKind::Nat | NatKind::Bits, Width::Const(width)) => Shape::Nat(Some((BigUint::from(1u8) << width) - 1u8)),
+                _ => Shape::Nat(None),
+            },
+            Type::Record(v) => Shape::Record(v.borrow().fields.clone()),
+            Type::Union(v) => Shape::Union(String::new(), v.borrow().variants.clone()),
+            Type::Alias(sym, args) => match self.program.decl(*sym) {
+                Some(Decl::TypeAlias(alias)) => {
+                    let args = alias.generics.iter().map(|param| param.sym()).zip(args.iter().cloned()).collect();
+                    match self.shape(&alias.ty.subst(&args)) {
+                        Shape::Union(_, variants) => Shape::Union(alias.ident.clone(), variants),
+                        shape => shape,
+                    }
+                }
+                _ => Shape::Opaque,
+            },
+            Type::Func(_) | Type::Param(_) | Type::Var(_) => Shape::Opaque,
+        }
+    }
+
+    /// The constructors of a column, with the runs of numbers cut where the given heads
+    /// start and end.
+    fn ctors(&self, shape: &Shape<'a>, heads: &[&Pat]) -> Vec<Ctor> {
+        match shape {
+            Shape::Union(_, variants) => (0..variants.len()).map(Ctor::Variant).collect(),
+            Shape::Record(_) => vec![Ctor::Record],
+            Shape::Nat(max) => {
+                let mut cuts = BTreeSet::from([BigUint::ZERO]);
+                for head in heads {
+                    if let Pat::Range(lo, hi) = head {
+                        cuts.insert(lo.clone());
+                        cuts.insert(hi + 1u8);
+                    }
+                }
+                let cuts: Vec<_> = cuts.into_iter().filter(|cut| max.as_ref().is_none_or(|max| cut <= max)).collect();
+                let ends = cuts.iter().skip(1).map(|cut| Some(cut - 1u8)).chain([max.clone()]);
+                cuts.iter().cloned().zip(ends).map(|(lo, hi)| Ctor::Values(lo, hi)).collect()
+            }
+            Shape::Opaque => Vec::new(),
+        }
+    }
+
+    fn arity(&self, ctor: &Ctor, shape: &Shape<'a>) -> Vec<Type<'a>> {
+        match (ctor, shape) {
+            (Ctor::Variant(i), Shape::Union(_, variants)) => vec![variants[*i].ty.clone()],
+            (Ctor::Record, Shape::Record(fields)) => fields.iter().map(|field| field.ty.clone()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The row for the values `ctor` builds, with its head replaced by the parts of the value,
+    /// or `None` if the head matches none of them.
+    fn specialize(&self, row: &[Pat], ctor: &Ctor, shape: &Shape<'a>) -> Option<Vec<Pat>> {
+        let (head, tail) = row.split_first()?;
+        let wild = || vec![Pat::Wild; self.arity(ctor, shape).len()];
+        let mut fields = match (ctor, shape, head) {
+            (_, _, Pat::Wild) => wild(),
+            (Ctor::Variant(i), Shape::Union(_, variants), Pat::Variant(ident, payload)) => {
+                if variants[*i].ident != *ident {
+                    return None;
+                }
+                vec![payload.as_ref().clone()]
+            }
+            (Ctor::Record, Shape::Record(fields), Pat::Record(pats)) => fields.iter()
+                .map(|field| pats.iter().find(|(ident, _)| *ident == field.ident).map_or(Pat::Wild, |(_, pat)| pat.clone()))
+                .collect(),
+            (Ctor::Values(lo, hi), _, Pat::Range(a, b)) => {
+                if !(a <= lo && hi.as_ref().is_some_and(|hi| hi <= b)) {
+                    return None;
+                }
+                Vec::new()
+            }
+            // A pattern of another type, which checking has reported.
+            _ => wild(),
+        };
+        fields.extend(tail.iter().cloned());
+        Some(fields)
+    }
+
+    /// Any value of a type, spelled with a wildcard per field of a record.
+    fn wild(&self, ty: &Type<'a>) -> Witness {
+        match self.shape(ty) {
+            Shape::Record(fields) if !fields.is_empty() => {
+                Witness::Record(fields.into_iter().map(|field| (field.ident, Witness::Wild)).collect())
+            }
+            _ => Witness::Wild,
+        }
+    }
+
+    /// The value `ctor` builds from the first parts of a witness, in place of them.
+    fn wrap(&self, ctor: &Ctor, shape: &Shape<'a>, mut w: Vec<Witness>) -> Vec<Witness> {
+        let rest = w.split_off(self.arity(ctor, shape).len());
+        let head = match (ctor, shape) {
+            (Ctor::Variant(i), Shape::Union(ty, variants)) => {
+                let unit = matches!(self.shape(&variants[*i].ty), Shape::Record(fields) if fields.is_empty());
+                let payload = w.pop().filter(|_| !unit).map(Box::from);
+                Witness::Variant(ty.clone(), variants[*i].ident.clone(), payload)
+            }
+            (Ctor::Record, Shape::Record(fields)) => {
+                Witness::Record(fields.iter().map(|field| field.ident.clone()).zip(w).collect())
+            }
+            (Ctor::Values(lo, _), _) => Witness::Nat(lo.clone()),
+            _ => Witness::Wild,
+        };
+        std::iter::once(head).chain(rest).collect()
+    }
+
+    /// Up to `limit` values the row `q` matches and none of `rows` do, each a witness per
+    /// column of types `tys`. There are none if `q` is not useful below `rows`.
+    fn witnesses(&self, rows: &[Vec<Pat>], q: &[Pat], tys: &[Type<'a>], limit: usize) -> Vec<Vec<Witness>> {
+        let Some((head, tail)) = q.split_first() else {
+            return if rows.is_empty() { vec![Vec::new()] } else { Vec::new() };
+        };
+        let mut found = Vec::new();
+        if let Pat::Or(alts) = head {
+            for alt in alts {
+                if found.len() >= limit {
+                    break;
+                }
+                let q: Vec<_> = std::iter::once(alt.clone()).chain(tail.iter().cloned()).collect();
+                found.extend(self.witnesses(rows, &q, tys, limit - found.len()));
+            }
+            return found;
+        }
+        let rows = expand(rows);
+        let shape = self.shape(&tys[0]);
+        let heads: Vec<_> = rows.iter().map(|row| &row[0]).chain([head]).collect();
+        let ctors = self.ctors(&shape, &heads);
+        let ctors = match head {
+            Pat::Wild => {
+                let missing: Vec<_> = ctors.iter()
+                    .filter(|ctor| !matches!(ctor, Ctor::Record))
+                    .filter(|ctor| !rows.iter().any(|row| !matches!(row[0], Pat::Wild) && self.specialize(row, ctor, &shape).is_some()))
+                    .collect();
+                if ctors.is_empty() || !missing.is_empty() {
+                    // When no row names a constructor here, `_` stands for every one of them.
+                    let named = missing.len() < ctors.iter().filter(|ctor| !matches!(ctor, Ctor::Record)).count();
+                    let default: Vec<_> = rows.iter().filter(|row| matches!(row[0], Pat::Wild)).map(|row| row[1..].to_vec()).collect();
+                    for w in self.witnesses(&default, tail, &tys[1..], limit) {
+                        if !named {
+                            found.push(std::iter::once(Witness::Wild).chain(w.iter().cloned()).collect());
+                            continue;
+                        }
+                        for ctor in &missing {
+                            let wild = self.arity(ctor, &shape).iter().map(|ty| self.wild(ty)).collect::<Vec<_>>();
+                            found.push(self.wrap(ctor, &shape, wild.into_iter().chain(w.iter().cloned()).collect()));
+                        }
+                    }
+                    found.truncate(limit);
+                    return found;
+                }
+                ctors
+            }
+            _ => ctors.into_iter().filter(|ctor| self.specialize(q, ctor, &shape).is_some()).collect(),
+        };
+        for ctor in ctors {
+            if found.len() >= limit {
+                break;
+            }
+            let rows: Vec<_> = rows.iter().filter_map(|row| self.specialize(row, &ctor, &shape)).collect();
+            let q = self.specialize(q, &ctor, &shape).unwrap();
+            let tys: Vec<_> = self.arity(&ctor, &shape).into_iter().chain(tys[1..].iter().cloned()).collect();
+            for w in self.witnesses(&rows, &q, &tys, limit - found.len()) {
+                found.push(self.wrap(&ctor, &shape, w));
+            }
+        }
+        found
+    }
+}
+
+/// The rows with an or-pattern at their head split into one row per alternative.
+fn expand(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut expanded = Vec::new();
+    for row in rows {
+        match &row[0] {
+            Pat::Or(alts) => {
+                let alts: Vec<_> = alts.iter().map(|alt| std::iter::once(alt.clone()).chain(row[1..].iter().cloned()).collect()).collect();
+                expanded.extend(expand(&alts));
+            }
+            _ => expanded.push(row.clone()),
+        }
+    }
+    expanded
+}
@@ -0,0 +1,776 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Monomorphization of generic declarations.
+//!
+//! Starting from the top-level declarations that are not generic, every use of a generic `let`
+//! is given an instance: a copy of the declaration with each generic parameter replaced by what
+//! checking found it stands for there, under a mangled name that is the same on every run. The
+//! uses within an instance give instances of their own in turn, as do the generic aliases its
+//! types name. A generic declared within another is also given the arguments of the one it is
+//! declared in, which its body may refer to.
+//!
+//! A generic used within a `let` that checking generalized, as `Id` in
+//! `let g = fun (z) { Id(z) }`, is used at a type each use of the `let` picks, so no one
+//! instance of it is the one used there, and that is reported.
+//!
+//! Instantiating ends unless a generic is used within its own cycle of uses at arguments that
+//! grow: `fun F[T](x: T) -> Nat { F((x, x)) }` would need `F[Nat]`, `F[(Nat, Nat)]` and so on
+//! without end. So before any instance is made, each parameter is linked to the parameters it
+//! is passed as, by a growing link where it is passed inside a type or a sum of widths, and a
+//! growing link within a cycle is reported. Passing a parameter inside `max` does not grow it
+//! past its other operands, so that is not a growing link.
+
+use crate::check::Typing;
+use crate::sym::*;
+use paracell_parser_sem::diag::Diagnostic;
+use paracell_parser_sem::span::Span;
+use paracell_util_struct::map::OrderedHashMap;
+use std::collections::{HashMap, HashSet};
+use typed_arena::Arena;
+
+/// A generic declaration specialized to its arguments.
+#[derive(Clone, Debug)]
+pub struct Instance<'a> {
+    /// The generic declaration.
+    pub sym: Symbol,
+    /// For its generic parameters in order, then for those of the declarations it is within.
+    pub args: Vec<GenericArg<'a>>,
+    pub name: String,
+    /// The copy, named `name`, with no generic parameters left.
+    pub decl: &'a Decl<'a>,
+    /// The instance each generic declaration named in the copy refers to, by the span of the name.
+    pub uses: HashMap<Span, usize>,
+}
+
+/// Every instance needed by the declarations that are not generic.
+#[derive(Clone, Debug, Default)]
+pub struct Mono<'a> {
+    pub instances: Vec<Instance<'a>>,
+    /// The instance each generic declaration named outside of generic ones refers to, by the
+    /// module and span of the name.
+    pub uses: HashMap<(usize, Span), usize>,
+}
+
+impl<'a> Mono<'a> {
+    /// The instance named `name`.
+    pub fn instance(&self, name: &str) -> Option<&Instance<'a>> {
+        self.instances.iter().find(|instance| instance.name == name)
+    }
+}
+
+/// Instantiates the generic declarations a checked program uses, allocating the copies in
+/// `arena`. Fails if a generic is used at arguments that grow without end.
+pub fn monomorphize<'a>(
+    program: &Program<'a>,
+    typing: &Typing<'a>,
+    arena: &'a Arena<Decl<'a>>,
+) -> Result<Mono<'a>, Vec<(usize, Diagnostic)>> {
+    let mut scan = Scan {
+        program,
+        typing,
+        module: 0,
+        outer: Vec::new(),
+        enclosing: HashMap::new(),
+        within: Vec::new(),
+        siblings: HashMap::new(),
+        names: HashMap::new(),
+        uses: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    for (module, m) in program.modules.iter().enumerate() {
+        scan.module = module;
+        for decl in &m.decls.vals {
+            scan.decl(decl);
+        }
+    }
+    let mut diagnostics = std::mem::take(&mut scan.diagnostics);
+    diagnostics.extend(scan.growing());
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let mangler = Mangler { program, names: scan.names };
+    let mut mono = Monomorphizer { program, typing, arena, mangler, enclosing: scan.enclosing, index: HashMap::new(), mono: Mono::default() };
+    let mut queue = Vec::new();
+    for (module, m) in program.modules.iter().enumerate() {
+        for decl in m.decls.vals.iter().filter(|decl| decl.generics().is_empty()) {
+            let mut uses = HashMap::new();
+            mono.walk(decl, module, &HashMap::new(), &mut uses, &mut queue);
+            mono.mono.uses.extend(uses.into_iter().map(|(span, i)| ((module, span), i)));
+        }
+    }
+    while let Some(i) = queue.pop() {
+        let instance = &mono.mono.instances[i];
+        let (decl, module) = (instance.decl, program.symbol(instance.sym).module);
+        let subst = mono.subst(instance.sym, &instance.args);
+        let mut uses = HashMap::new();
+        mono.walk(decl, module, &subst, &mut uses, &mut queue);
+        mono.mono.instances[i].uses = uses;
+    }
+    Ok(mono.mono)
+}
+
+/// Where a generic is used at some arguments.
+struct Use<'a> {
+    module: usize,
+    span: Span,
+    /// The declaration the generic is used within, for an alias naming it.
+    within: Option<Symbol>,
+    sym: Symbol,
+    args: Vec<GenericArg<'a>>,
+}
+
+/// Finds the uses of generics, the generic parameters each declaration is within, and the name
+/// each declaration is mangled by.
+struct Scan<'p, 'a> {
+    program: &'p Program<'a>,
+    typing: &'p Typing<'a>,
+    module: usize,
+    /// The generic parameters of the declarations being scanned.
+    outer: Vec<Symbol>,
+    enclosing: HashMap<Symbol, Vec<Symbol>>,
+    /// The declarations being scanned, each with the mangled segment of its name.
+    within: Vec<(Symbol, String)>,
+    /// How many declarations of each name were met within each declaration, or at the top level
+    /// of each module.
+    siblings: HashMap<(usize, Option<Symbol>, String), usize>,
+    names: HashMap<Symbol, String>,
+    uses: Vec<Use<'a>>,
+    diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl<'p, 'a> Scan<'p, 'a> {
+    fn decl(&mut self, decl: &Decl<'a>) {
+        self.enclosing.insert(decl.sym(), self.outer.clone());
+        let depth = self.outer.len();
+        self.outer.extend(decl.generics().iter().map(|param| param.sym()));
+        let segment = self.name(decl);
+        self.within.push((decl.sym(), segment));
+        match decl {
+            Decl::Let(v) => self.expr(&v.expr),
+            Decl::Var(v) => self.expr(&v.expr),
+            Decl::TypeAlias(v) => self.ty(&v.ty, v.sym),
+        }
+        self.within.pop();
+        self.outer.truncate(depth);
+    }
+
+    /// Names a declaration by the path of its module and the declarations it is within, as
+    /// `NM3alu3Add2IdE` for `Id` within `Add` in `alu`, or by its ident alone at the top level
+    /// of the root module. Declarations of the same name met earlier within the same one are
+    /// counted, as in `2IdD1_`. Returns the segment the declaration adds to the names of those
+    /// within it.
+    fn name(&mut self, decl: &Decl<'a>) -> String {
+        let key = (self.module, self.within.last().map(|(sym, _)| *sym), decl.ident().to_string());
+        let count = self.siblings.entry(key).or_default();
+        let mut segment = String::new();
+        ident(&mut segment, decl.ident());
+        if *count > 0 {
+            segment.push_str(&format!("D{}_", count));
+        }
+        *count += 1;
+        let mut segments: Vec<_> = self.program.modules[self.module].path.split("::")
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let mut out = String::from("M");
+                ident(&mut out, s);
+                out
+            })
+            .collect();
+        segments.extend(self.within.iter().map(|(_, s)| s.clone()));
+        segments.push(segment.clone());
+        let name = match segments.as_slice() {
+            [only] => only.clone(),
+            _ => format!("N{}E", segments.concat()),
+        };
+        self.names.insert(decl.sym(), name);
+        segment
+    }
+
+    fn ty(&mut self, ty: &Type<'a>, within: Symbol) {
+        match ty {
+            Type::Primitive(_) | Type::Param(_) | Type::Var(_) => {}
+            Type::Record(v) => {
+                for field in &v.borrow().fields {
+                    self.ty(&field.ty, within);
+                }
+            }
+            Type::Union(v) => {
+                for variant in &v.borrow().variants {
+                    self.ty(&variant.ty, within);
+                }
+            }
+            Type::Func(v) => {
+                for field in &v.params.fields {
+                    self.ty(&field.ty, within);
+                }
+                self.ty(&v.results, within);
+            }
+            Type::Alias(sym, args) => {
+                if !args.is_empty() {
+                    let info = self.program.symbol(within);
+                    self.uses.push(Use { module: info.module, span: info.span, within: Some(within), sym: *sym, args: args.clone() });
+                }
+                for arg in args {
+                    if let GenericArg::Type(ty) = arg {
+                        self.ty(ty, within);
+                    }
+                }
+            }
+        }
+    }
+
+    fn scope(&mut self, scope: &Scope<'a>) {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(v) => self.decl(v),
+                Stmt::Expr(v) => self.expr(v),
+            }
+        }
+        self.expr(&scope.expr);
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) {
+        match e {
+            Expr::Nat(_) | Expr::Builtin(_) => {}
+            Expr::Symbol(v) => {
+                let Some(args) = self.typing.instances.get(&(self.module, v.span)) else { return };
+                if args.iter().any(var_in_arg) {
+                    let ident = &self.program.symbol(v.sym).ident;
+                    let name = self.program.type_name(&Type::Alias(v.sym, args.clone()));
+                    let diag = Diagnostic::error("E2015", format!("cannot tell which instance of `{}` is used here", ident))
+                        .with_primary(v.span, format!("`{}` is used here at `{}`", ident, name))
+                        .with_help("the `let` this is within was generalized, so each of its uses picks the type anew")
+                        .with_help("annotate the types of the `let`, or declare it as a generic `fun`");
+                    self.diagnostics.push((self.module, diag));
+                    return;
+                }
+                self.uses.push(Use { module: self.module, span: v.span, within: None, sym: v.sym, args: args.clone() });
+            }
+            Expr::Record(v) => {
+                for field in &v.fields {
+                    self.expr(&field.expr);
+                }
+            }
+            Expr::Variant(v) => {
+                if let Some(payload) = &v.payload {
+                    self.expr(payload);
+                }
+            }
+            Expr::Apply(v) => {
+                self.expr(&v.func);
+                for field in &v.params.fields {
+                    self.expr(&field.expr);
+                }
+            }
+            Expr::Select(v) => self.expr(&v.expr),
+            Expr::Block(v) => self.scope(v),
+            Expr::Func(v) => self.scope(&v.body),
+            Expr::Match(v) => {
+                self.expr(&v.expr);
+                for case in &v.cases {
+                    self.scope(&case.expr);
+                }
+            }
+            Expr::Assign(v) => self.expr(&v.expr),
+            Expr::While(v) => {
+                self.expr(&v.cond);
+                self.scope(&v.body);
+            }
+        }
+    }
+
+    /// Reports each use passing a parameter on grown to one that leads back to it.
+    fn growing(&self) -> Vec<(usize, Diagnostic)> {
+        // Which parameters each one is passed as, and whether grown.
+        let mut links: HashMap<Symbol, Vec<Symbol>> = HashMap::new();
+        let mut grown = Vec::new();
+        for (i, u) in self.uses.iter().enumerate() {
+            let generics = self.program.decl(u.sym).map_or(&[][..], Decl::generics);
+            for (param, arg) in generics.iter().zip(&u.args) {
+                let mut found = Vec::new();
+                match arg {
+                    GenericArg::Type(ty) => {
+                        params_in_type(ty, &mut found);
+                        let inside = !matches!(ty, Type::Param(_));
+                        found.iter_mut().for_each(|(_, grows)| *grows = inside);
+                    }
+                    GenericArg::Width(width) => params_in_width(width, false, &mut found),
+                }
+                for (from, grows) in found {
+                    links.entry(from).or_default().push(param.sym());
+                    if grows {
+                        grown.push((i, from, param.sym()));
+                    }
+                }
+            }
+        }
+        let mut reported = HashSet::new();
+        let mut diagnostics = Vec::new();
+        for (i, from, to) in grown {
+            if !reaches(&links, to, from) || !reported.insert(i) {
+                continue;
+            }
+            let u = &self.uses[i];
+            let name = self.program.type_name(&Type::Alias(u.sym, u.args.clone()));
+            let ident = &self.program.symbol(u.sym).ident;
+            let label = match u.within {
+                Some(within) => format!("`{}` refers to `{}`", self.program.symbol(within).ident, name),
+                None => format!("`{}` is used here at `{}`", ident, name),
+            };
+            let diag = Diagnostic::error("E2014", format!("instantiating `{}` would never end", ident))
+                .with_primary(u.span, label)
+                .with_help(format!("`{}` grows `{}` on every turn of a cycle leading back to it", name, self.program.symbol(from).ident))
+                .with_help("within its own cycle of uses, a generic may only be used at its own parameters or at fixed types");
+            diagnostics.push((u.module, diag));
+        }
+        diagnostics
+    }
+}
+
+/// The generic parameters a type names.
+fn params_in_type(ty: &Type<'_>, found: &mut Vec<(Symbol, bool)>) {
+    match ty {
+        Type::Primitive(PrimitiveType::Nat(v)) => params_in_width(&v.width, false, found),
+        Type::Record(v) => {
+            for field in &v.borrow().fields {
+                params_in_type(&field.ty, found);
+            }
+        }
+        Type::Union(v) => {
+            for variant in &v.borrow().variants {
+                params_in_type(&variant.ty, found);
+            }
+        }
+        Type::Func(v) => {
+            for field in &v.params.fields {
+                params_in_type(&field.ty, found);
+            }
+            params_in_type(&v.results, found);
+        }
+        Type::Alias(_, args) => {
+            for arg in args {
+                match arg {
+                    GenericArg::Type(ty) => params_in_type(ty, found),
+                    GenericArg::Width(width) => params_in_width(width, false, found),
+                }
+            }
+        }
+        Type::Param(sym) => found.push((*sym, false)),
+        Type::Var(_) => {}
+    }
+}
+
+/// The generic parameters a width names, each with whether a sum grows it.
+fn params_in_width(width: &Width, sum: bool, found: &mut Vec<(Symbol, bool)>) {
+    match width {
+        Width::Param(sym) => found.push((*sym, sum)),
+        Width::Max(a, b) => {
+            params_in_width(a, sum, found);
+            params_in_width(b, sum, found);
+        }
+        Width::Sum(a, b) => {
+            params_in_width(a, true, found);
+            params_in_width(b, true, found);
+        }
+        Width::Unbounded | Width::Const(_) | Width::Var(_) => {}
+    }
+}
+
+/// Whether a generic argument is left to inference, naming a variable.
+fn var_in_arg(arg: &GenericArg<'_>) -> bool {
+    match arg {
+        GenericArg::Type(ty) => var_in_type(ty),
+        GenericArg::Width(width) => var_in_width(width),
+    }
+}
+
+fn var_in_type(ty: &Type<'_>) -> bool {
+    match ty {
+        Type::Primitive(PrimitiveType::Nat(v)) => matches!(v.kind, NatKind::Var(_)) || var_in_width(&v.width),
+        Type::Record(v) => v.borrow().fields.iter().any(|field| var_in_type(&field.ty)),
+        Type::Union(v) => v.borrow().variants.iter().any(|variant| var_in_type(&variant.ty)),
+        Type::Func(v) => v.params.fields.iter().any(|field| var_in_type(&field.ty)) || var_in_type(&v.results),
+        Type::Alias(_, args) => args.iter().any(var_in_arg),
+        Type::Param(_) => false,
+        Type::Var(_) => true,
+    }
+}
+
+fn var_in_width(width: &Width) -> bool {
+    match width {
+        Width::Var(_) => true,
+        Width::Max(a, b) | Width::Sum(a, b) => var_in_width(a) || var_in_width(b),
+        Width::Unbounded | Width::Const(_) | Width::Param(_) => false,
+    }
+}
+
+/// Whether `from` is passed on, through any number of links, as `to`.
+fn reaches(links: &HashMap<Symbol, Vec<Symbol>>, from: Symbol, to: Symbol) -> bool {
+    let mut seen = HashSet::from([from]);
+    let mut stack = vec![from];
+    while let Some(sym) = stack.pop() {
+        if sym == to {
+            return true;
+        }
+        for &next in links.get(&sym).into_iter().flatten() {
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+struct Monomorphizer<'p, 'a> {
+    program: &'p Program<'a>,
+    typing: &'p Typing<'a>,
+    arena: &'a Arena<Decl<'a>>,
+    mangler: Mangler<'p, 'a>,
+    enclosing: HashMap<Symbol, Vec<Symbol>>,
+    /// The instance of each declaration by its mangled name.
+    index: HashMap<(Symbol, String), usize>,
+    mono: Mono<'a>,
+}
+
+impl<'p, 'a> Monomorphizer<'p, 'a> {
+    /// The generic parameters of the declarations `sym` is within.
+    fn enclosing(&self, sym: Symbol) -> &[Symbol] {
+        self.enclosing.get(&sym).map_or(&[], Vec::as_slice)
+    }
+
+    /// What each parameter an instance of `sym` is given stands for.
+    fn subst(&self, sym: Symbol, args: &[GenericArg<'a>]) -> HashMap<Symbol, GenericArg<'a>> {
+        let generics = self.program.decl(sym).map_or(&[][..], Decl::generics);
+        let params = generics.iter().map(|param| param.sym()).chain(self.enclosing(sym).iter().copied());
+        params.zip(args.iter().cloned()).collect()
+    }
+
+    /// The instance of `sym` at `args`, made and queued if new. `subst` is what the parameters
+    /// of the declarations it is within stand for.
+    fn instance(&mut self, sym: Symbol, args: Vec<GenericArg<'a>>, subst: &HashMap<Symbol, GenericArg<'a>>, queue: &mut Vec<usize>) -> usize {
+        let mut args = args;
+        for param in self.enclosing(sym) {
+            args.push(subst.get(param).cloned().unwrap_or(GenericArg::Type(Type::Param(*param))));
+        }
+        let name = self.mangler.mangle(sym, &args);
+        if let Some(&i) = self.index.get(&(sym, name.clone())) {
+            return i;
+        }
+        let subst = self.subst(sym, &args);
+        let decl = match self.program.decl(sym) {
+            Some(Decl::Let(v)) => Decl::Let(LetDecl {
+                sym: v.sym,
+                ident: name.clone(),
+                generics: Vec::new(),
+                ty: v.ty.as_ref().map(|ty| ty.subst(&subst)),
+                expr: self.expr(&v.expr, &subst),
+                span: v.span,
+            }),
+            Some(Decl::TypeAlias(v)) => Decl::TypeAlias(TypeAliasDecl {
+                sym: v.sym,
+                ident: name.clone(),
+                generics: Vec::new(),
+                ty: v.ty.subst(&subst),
+                span: v.span,
+            }),
+            _ => unreachable!("only `let`s and aliases are generic"),
+        };
+        let i = self.mono.instances.len();
+        self.mono.instances.push(Instance { sym, args, name: name.clone(), decl: self.arena.alloc(decl), uses: HashMap::new() });
+        self.index.insert((sym, name), i);
+        queue.push(i);
+        i
+    }
+
+    /// Instantiates what a declaration, whose generic parameters stand for `subst`, uses.
+    /// Declarations within it that are generic themselves are left to their own instances.
+    fn walk(&mut self, decl: &Decl<'a>, module: usize, subst: &HashMap<Symbol, GenericArg<'a>>, uses: &mut HashMap<Span, usize>, queue: &mut Vec<usize>) {
+        let mut exprs = Vec::new();
+        let mut types = Vec::new();
+        // The types checking found, with those the source leaves out filled in.
+        match decl {
+            Decl::Let(v) => exprs.push(&v.expr),
+            Decl::Var(v) => exprs.push(&v.expr),
+            Decl::TypeAlias(v) => types.push(&v.ty),
+        }
+        if let Some(ty) = self.typing.types.get(&decl.sym()) {
+            self.ty(&ty.subst(subst), subst, queue);
+        }
+        for ty in types {
+            self.ty(ty, subst, queue);
+        }
+        while let Some(e) = exprs.pop() {
+            match e {
+                Expr::Nat(_) | Expr::Builtin(_) => {}
+                Expr::Symbol(v) => {
+                    if let Some(args) = self.typing.instances.get(&(module, v.span)) {
+                        let args = args.iter().map(|arg| arg.subst(subst)).collect();
+                        let i = self.instance(v.sym, args, subst, queue);
+                        uses.insert(v.span, i);
+                    }
+                }
+                Expr::Record(v) => exprs.extend(v.fields.iter().map(|field| &field.expr)),
+                Expr::Variant(v) => exprs.extend(v.payload.as_deref()),
+                Expr::Apply(v) => {
+                    exprs.push(&v.func);
+                    exprs.extend(v.params.fields.iter().map(|field| &field.expr));
+                }
+                Expr::Select(v) => exprs.push(&v.expr),
+                Expr::Block(v) => self.scope(v, &mut exprs),
+                Expr::Func(v) => {
+                    for param in &v.params {
+                        if let Some(ty) = self.typing.types.get(param) {
+                            self.ty(&ty.subst(subst), subst, queue);
+                        }
+                    }
+                    self.ty(&v.ty.results, subst, queue);
+                    self.scope(&v.body, &mut exprs);
+                }
+                Expr::Match(v) => {
+                    exprs.push(&v.expr);
+                    for case in &v.cases {
+                        self.scope(&case.expr, &mut exprs);
+                    }
+                }
+                Expr::Assign(v) => exprs.push(&v.expr),
+                Expr::While(v) => {
+                    exprs.push(&v.cond);
+                    self.scope(&v.body, &mut exprs);
+                }
+            }
+        }
+    }
+
+    /// The expressions of a scope, with those of the declarations in it that are not generic.
+    fn scope<'s>(&self, scope: &'s Scope<'a>, exprs: &mut Vec<&'s Expr<'a>>) {
+        for stmt in &scope.stmts {
+            match stmt {
+                Stmt::Decl(Decl::Let(v)) if v.generics.is_empty() => exprs.push(&v.expr),
+                Stmt::Decl(Decl::Var(v)) => exprs.push(&v.expr),
+                Stmt::Decl(_) => {}
+                Stmt::Expr(v) => exprs.push(v),
+            }
+        }
+        exprs.push(&scope.expr);
+    }
+
+    /// Instantiates the generic aliases a type names. A type left to inference stands for no
+    /// type in particular, so an alias given one names no instance.
+    fn ty(&mut self, ty: &Type<'a>, subst: &HashMap<Symbol, GenericArg<'a>>, queue: &mut Vec<usize>) {
+        match ty {
+            Type::Primitive(_) | Type::Param(_) | Type::Var(_) => {}
+            Type::Record(v) => {
+                for field in &v.borrow().fields {
+                    self.ty(&field.ty, subst, queue);
+                }
+            }
+            Type::Union(v) => {
+                for variant in &v.borrow().variants {
+                    self.ty(&variant.ty, subst, queue);
+                }
+            }
+            Type::Func(v) => {
+                for field in &v.params.fields {
+                    self.ty(&field.ty, subst, queue);
+                }
+                self.ty(&v.results, subst, queue);
+            }
+            Type::Alias(sym, args) => {
+                if !args.is_empty() && !args.iter().any(var_in_arg) {
+                    let args = args.iter().map(|arg| arg.subst(subst)).collect();
+                    self.instance(*sym, args, subst, queue);
+                }
+                for arg in args {
+                    if let GenericArg::Type(ty) = arg {
+                        self.ty(ty, subst, queue);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A copy of an expression with its types substituted.
+    fn expr(&self, e: &Expr<'a>, subst: &HashMap<Symbol, GenericArg<'a>>) -> Expr<'a> {
+        let record = |v: &RecordExpr<'a>| RecordExpr {
+            fields: v.fields.iter().map(|field| FieldExpr { ident: field.ident.clone(), expr: self.expr(&field.expr, subst) }).collect(),
+            span: v.span,
+        };
+        match e {
+            Expr::Nat(_) | Expr::Symbol(_) | Expr::Builtin(_) => e.clone(),
+            Expr::Record(v) => Expr::Record(record(v)),
+            Expr::Variant(v) => Expr::Variant(VariantExpr {
+                ty: v.ty,
+                ident: v.ident.clone(),
+                payload: v.payload.as_ref().map(|payload| Box::from(self.expr(payload, subst))),
+                span: v.span,
+            }),
+            Expr::Apply(v) => Expr::Apply(Box::from(ApplyExpr { func: self.expr(&v.func, subst), params: record(&v.params), span: v.span })),
+            Expr::Select(v) => Expr::Select(Box::from(SelectExpr { expr: self.expr(&v.expr, subst), ident: v.ident.clone(), span: v.span })),
+            Expr::Block(v) => Expr::Block(Box::from(self.scope_copy(v, subst))),
+            Expr::Func(v) => {
+                let Type::Func(ty) = Type::Func(Box::from(v.ty.clone())).subst(subst) else { unreachable!() };
+                Expr::Func(Box::from(FuncExpr { ty: *ty, params: v.params.clone(), body: self.scope_copy(&v.body, subst), span: v.span }))
+            }
+            Expr::Match(v) => Expr::Match(Box::from(Match {
+                expr: self.expr(&v.expr, subst),
+                cases: v.cases.iter().map(|case| Case { pattern: case.pattern.clone(), expr: self.scope_copy(&case.expr, subst) }).collect(),
+                span: v.span,
+            })),
+            Expr::Assign(v) => Expr::Assign(Box::from(AssignExpr { target: v.target, expr: self.expr(&v.expr, subst), span: v.span })),
+            Expr::While(v) => Expr::While(Box::from(WhileExpr { cond: self.expr(&v.cond, subst), body: self.scope_copy(&v.body, subst), span: v.span })),
+        }
+    }
+
+    fn scope_copy(&self, scope: &Scope<'a>, subst: &HashMap<Symbol, GenericArg<'a>>) -> Scope<'a> {
+        let mut copies: HashMap<Symbol, &'a Decl<'a>> = HashMap::new();
+        let mut copy = |decl: &Decl<'a>| -> &'a Decl<'a> {
+            copies.entry(decl.sym()).or_insert_with(|| {
+                let decl = match decl {
+                    Decl::Let(v) => Decl::Let(LetDecl {
+                        ty: v.ty.as_ref().map(|ty| ty.subst(subst)),
+                        expr: self.expr(&v.expr, subst),
+                        ..v.clone()
+                    }),
+                    Decl::Var(v) => Decl::Var(VarDecl {
+                        ty: v.ty.as_ref().map(|ty| ty.subst(subst)),
+                        expr: self.expr(&v.expr, subst),
+                        ..v.clone()
+                    }),
+                    Decl::TypeAlias(v) => Decl::TypeAlias(TypeAliasDecl { ty: v.ty.subst(subst), ..v.clone() }),
+                };
+                self.arena.alloc(decl)
+            })
+        };
+        let stmts = scope.stmts.iter()
+            .map(|stmt| match stmt {
+                Stmt::Decl(decl) => Stmt::Decl(copy(decl)),
+                Stmt::Expr(e) => Stmt::Expr(self.expr(e, subst)),
+            })
+            .collect();
+        let mut decls = OrderedHashMap::new();
+        for decl in &scope.decls.vals {
+            let decl = copy(decl);
+            decls.insert(decl.ident(), decl);
+        }
+        Scope { decls, stmts, expr: self.expr(&scope.expr, subst), span: scope.span }
+    }
+}
+
+/// Spells the names of instances.
+struct Mangler<'p, 'a> {
+    program: &'p Program<'a>,
+    /// The name of each declaration, by the path of its module and the declarations it is within.
+    names: HashMap<Symbol, String>,
+}
+
+impl<'p, 'a> Mangler<'p, 'a> {
+    /// A name for the instance of `sym` at `args`, made only of letters, digits and `_`, that is
+    /// the same for the same instance on every run. Names are spelled with their length first,
+    /// and declarations with the path to them, so that no two instances share one. Arguments
+    /// left to inference have no name.
+    fn mangle(&self, sym: Symbol, args: &[GenericArg<'a>]) -> String {
+        let mut out = String::from("_P");
+        self.decl(&mut out, sym);
+        self.generic_args(&mut out, args);
+        out
+    }
+
+    fn decl(&self, out: &mut String, sym: Symbol) {
+        match self.names.get(&sym) {
+            Some(name) => out.push_str(name),
+            None => ident(out, &self.program.symbol(sym).ident),
+        }
+    }
+
+    fn generic_args(&self, out: &mut String, args: &[GenericArg<'a>]) {
+        out.push('I');
+        for arg in args {
+            match arg {
+                GenericArg::Type(ty) => self.ty(out, ty),
+                GenericArg::Width(width) => {
+                    out.push('W');
+                    self.width(out, width);
+                }
+            }
+        }
+        out.push('E');
+    }
+
+    fn fields(&self, out: &mut String, fields: &[Field<'a>]) {
+        for field in fields {
+            ident(out, &field.ident);
+            self.ty(out, &field.ty);
+        }
+        out.push('E');
+    }
+
+    fn ty(&self, out: &mut String, ty: &Type<'a>) {
+        match ty {
+            Type::Primitive(PrimitiveType::Nat(v)) => {
+                match v.kind {
+                    NatKind::Nat => out.push('n'),
+                    NatKind::Bits => out.push('b'),
+                    NatKind::Int => out.push('i'),
+                    NatKind::Var(_) => unreachable!("instances are never made at kinds left to inference"),
+                }
+                self.width(out, &v.width);
+            }
+            Type::Record(v) => {
+                out.push('R');
+                self.fields(out, &v.borrow().fields);
+            }
+            Type::Union(v) => {
+                out.push('U');
+                let variants: Vec<_> = v.borrow().variants.iter().map(|variant| Field { ident: variant.ident.clone(), ty: variant.ty.clone() }).collect();
+                self.fields(out, &variants);
+            }
+            Type::Func(v) => {
+                out.push('F');
+                self.fields(out, &v.params.fields);
+                self.ty(out, &v.results);
+            }
+            Type::Alias(sym, args) => {
+                out.push('A');
+                self.decl(out, *sym);
+                if !args.is_empty() {
+                    self.generic_args(out, args);
+                }
+            }
+            Type::Param(sym) => {
+                out.push('P');
+                ident(out, &self.program.symbol(*sym).ident);
+            }
+            Type::Var(_) => unreachable!("instances are never made at types left to inference"),
+        }
+    }
+
+    fn width(&self, out: &mut String, width: &Width) {
+        match width.fold() {
+            Width::Unbounded => out.push('u'),
+            Width::Const(v) => out.push_str(&format!("{}_", v)),
+            Width::Var(_) => unreachable!("instances are never made at widths left to inference"),
+            Width::Param(sym) => {
+                out.push('P');
+                ident(out, &self.program.symbol(sym).ident);
+            }
+            Width::Max(a, b) => {
+                out.push('M');
+                self.width(out, &a);
+                self.width(out, &b);
+            }
+            Width::Sum(a, b) => {
+                out.push('S');
+                self.width(out, &a);
+                self.width(out, &b);
+            }
+        }
+    }
+}
+
+fn ident(out: &mut String, ident: &str) {
+    out.push_str(&ident.len().to_string());
+    out.push_str(ident);
+}
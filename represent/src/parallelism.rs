@@ -0,0 +1,133 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Dataflow parallelism analysis.
+//!
+//! Operators, calls and muxes are operations, each taking one step once its inputs are ready.
+//! Parameters and literals are ready from the start, and records, selects, variants and the
+//! other nodes that only route values take no step. An operation ends at the earliest at its
+//! ASAP level, the most steps on a path from the start to it, and at the latest at its ALAP
+//! level, the last one that still ends the function within the length of its critical path.
+//! How many operations share an ASAP level is how many could run at once there. Operations
+//! that reach one another by no path, other than through values both read, form independent
+//! subgraphs, which may run on threads of their own or side by side in hardware.
+
+use crate::dataflow::{Graph, NodeId, Op};
+use crate::sym::Program;
+use serde::Serialize;
+
+/// The parallelism of a function.
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub func: String,
+    /// The ASAP level of each node, 0 for those ready from the start.
+    pub asap: Vec<usize>,
+    /// The ALAP level of each node.
+    pub alap: Vec<usize>,
+    /// How many steps the longest chain of operations takes.
+    pub critical_path: usize,
+    /// The operations on the critical path, whose ASAP and ALAP levels are the same.
+    pub critical: Vec<NodeId>,
+    /// How many operations are at each ASAP level, from level 1.
+    pub widths: Vec<usize>,
+    pub max_width: usize,
+    /// The operations of each independent subgraph, in order.
+    pub subgraphs: Vec<Vec<NodeId>>,
+}
+
+/// How many steps a node takes.
+fn steps(op: &Op) -> usize {
+    match op {
+        Op::Builtin(_) | Op::Call | Op::Match | Op::Phi(_) | Op::While => 1,
+        _ => 0,
+    }
+}
+
+/// Analyzes the graph of a function.
+pub fn analyze(program: &Program<'_>, graph: &Graph) -> Report {
+    let n = graph.nodes.len();
+    let is_op = |id: NodeId| steps(&graph.nodes[id].op) != 0;
+
+    // Nodes come after their inputs, so one pass in order finds the ASAP levels.
+    let mut asap = vec![0; n];
+    for (id, node) in graph.nodes.iter().enumerate() {
+        let ready = node.inputs.iter().map(|&input| asap[input]).max().unwrap_or(0);
+        asap[id] = ready + steps(&node.op);
+    }
+    let critical_path = asap.iter().copied().max().unwrap_or(0);
+
+    // And one pass backwards the ALAP levels.
+    let mut alap = vec![critical_path; n];
+    for (id, node) in graph.nodes.iter().enumerate().rev() {
+        let start = alap[id] - steps(&node.op);
+        for &input in &node.inputs {
+            alap[input] = alap[input].min(start);
+        }
+    }
+
+    let critical = (0..n).filter(|&id| is_op(id) && asap[id] == alap[id]).collect();
+    let mut widths = vec![0; critical_path];
+    for id in (0..n).filter(|&id| is_op(id)) {
+        widths[asap[id] - 1] += 1;
+    }
+    let max_width = widths.iter().copied().max().unwrap_or(0);
+
+    // The operations each node reads through those that take no step.
+    let mut sources: Vec<Vec<NodeId>> = Vec::with_capacity(n);
+    for (id, node) in graph.nodes.iter().enumerate() {
+        let mut found = Vec::new();
+        if is_op(id) {
+            found.push(id);
+        } else {
+            for &input in &node.inputs {
+                found.extend(sources[input].iter().copied().filter(|source| !found.contains(source)).collect::<Vec<_>>());
+            }
+        }
+        sources.push(found);
+    }
+
+    // Union-find over the operations reading one another.
+    let mut parent: Vec<_> = (0..n).collect();
+    fn find(parent: &mut [usize], mut id: usize) -> usize {
+        while parent[id] != id {
+            parent[id] = parent[parent[id]];
+            id = parent[id];
+        }
+        id
+    }
+    for (id, node) in graph.nodes.iter().enumerate().filter(|&(id, _)| is_op(id)) {
+        for &source in node.inputs.iter().flat_map(|&input| &sources[input]) {
+            let (a, b) = (find(&mut parent, id), find(&mut parent, source));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+    let mut subgraphs: Vec<Vec<NodeId>> = Vec::new();
+    let mut roots = Vec::new();
+    for id in (0..n).filter(|&id| is_op(id)) {
+        let root = find(&mut parent, id);
+        match roots.iter().position(|&r| r == root) {
+            Some(i) => subgraphs[i].push(id),
+            None => {
+                roots.push(root);
+                subgraphs.push(vec![id]);
+            }
+        }
+    }
+
+    Report {
+        func: program.symbol(graph.sym).ident.clone(),
+        asap,
+        alap,
+        critical_path,
+        critical,
+        widths,
+        max_width,
+        subgraphs,
+    }
+}
+
+/// The reports as a JSON array.
+pub fn json(reports: &[Report]) -> String {
+    serde_json::to_string_pretty(reports).unwrap()
+}
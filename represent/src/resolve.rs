@@ -0,0 +1,403 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+//! Name resolution over `sem` trees, binding every use of a name to what declares it.
+//!
+//! Top-level declarations are visible throughout their module, in any order. Within a block a
+//! declaration is visible from the next statement on, except that functions also see
+//! themselves, so that they may recurse, and types are visible throughout the block, so that
+//! they may refer to one another. Function parameters, `match` patterns and blocks each open a
+//! scope nested in the enclosing one, as do the generic parameters of a declaration. The
+//! alternatives of an or-pattern share the names they bind, and must all bind the same ones.
+
+use crate::sym::Builtin;
+use paracell_parser_sem::diag::{Diagnostic, Severity};
+use paracell_parser_sem::module::{Program, Target};
+use paracell_parser_sem::sem::*;
+use paracell_parser_sem::span::Span;
+use std::collections::HashMap;
+
+/// What a name stands for.
+#[derive(Clone, Copy, Debug)]
+pub enum Binding<'s> {
+    Decl(&'s Decl),
+    Param(&'s Field),
+    /// A name bound by a `match` pattern.
+    Pattern(&'s Ident),
+    /// A generic parameter of the declaration around the use.
+    Generic(&'s GenericParam),
+    Module(usize),
+    Builtin(Builtin),
+}
+
+/// Every binding found while resolving a file or a program, with the problems found on the way.
+#[derive(Clone, Debug, Default)]
+pub struct Resolution<'s> {
+    bindings: HashMap<(usize, Span), Binding<'s>>,
+    /// Each with the index of the module whose source it points into.
+    pub diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl<'s> Resolution<'s> {
+    /// The binding of the `Ident`, `Path` or type name at `span` in module `module`.
+    pub fn binding(&self, module: usize, span: Span) -> Option<Binding<'s>> {
+        self.bindings.get(&(module, span)).copied()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|(_, diag)| diag.severity == Severity::Error)
+    }
+}
+
+/// Resolves a file on its own, which sees only its declarations and the builtins.
+pub fn resolve(file: &SourceFile) -> Resolution<'_> {
+    let mut resolver = Resolver { program: None, module: 0, frames: Vec::new(), resolution: Resolution::default() };
+    resolver.source_file(file, Vec::new());
+    resolver.resolution
+}
+
+/// Resolves every module of a program, whose imports the loader has already resolved.
+pub fn resolve_program(program: &Program) -> Resolution<'_> {
+    let mut resolver = Resolver { program: Some(program), module: 0, frames: Vec::new(), resolution: Resolution::default() };
+    for (module, m) in program.modules.iter().enumerate() {
+        resolver.module = module;
+        let imported = m.scope.iter().filter_map(|(ident, target)| match *target {
+            Target::Decl { module: from, .. } if from == module => None,
+            target => Some((ident.as_str(), resolver.target(target))),
+        }).collect();
+        resolver.source_file(&m.file, imported);
+    }
+    resolver.resolution
+}
+
+/// A name in scope, with where it is declared if that is in the module being resolved.
+type Entry<'s> = (Binding<'s>, Option<Span>);
+
+struct Resolver<'s> {
+    program: Option<&'s Program>,
+    module: usize,
+    /// Innermost last. The first holds the top level of the module.
+    frames: Vec<HashMap<&'s str, Entry<'s>>>,
+    resolution: Resolution<'s>,
+}
+
+impl<'s> Resolver<'s> {
+    fn target(&self, target: Target) -> Binding<'s> {
+        let program = self.program.expect("targets come from a program");
+        match target {
+            Target::Decl { module, decl } => Binding::Decl(&program.modules[module].file.decls[decl]),
+            Target::Module(module) => Binding::Module(module),
+        }
+    }
+
+    fn report(&mut self, diag: Diagnostic) {
+        self.resolution.diagnostics.push((self.module, diag));
+    }
+
+    fn lookup(&self, ident: &str) -> Option<Entry<'s>> {
+        self.frames.iter().rev()
+            .find_map(|frame| frame.get(ident).copied())
+            .or_else(|| Builtin::from_literal(ident).map(|builtin| (Binding::Builtin(builtin), None)))
+    }
+
+    /// Binds `ident` in the innermost scope, reporting a clash within it or shadowing outside it.
+    fn declare(&mut self, ident: &'s str, binding: Binding<'s>, span: Span) {
+        let frame = self.frames.len() - 1;
+        if let Some((_, first)) = self.frames[frame].get(ident).copied() {
+            let mut diag = Diagnostic::error("E1002", format!("`{}` is declared twice in the same scope", ident))
+                .with_primary(span, "declared again here");
+            if let Some(first) = first {
+                diag = diag.with_secondary(first, "first declared here");
+            }
+            self.report(diag.with_help("rename one of them, or assign to a `var` instead"));
+            return;
+        }
+        if frame > 0
+            && let Some((outer, outer_span)) = self.lookup(ident)
+        {
+            let mut diag = Diagnostic::warning("W1001", format!("`{}` shadows an outer binding", ident))
+                .with_primary(span, "shadows it from here on");
+            if let Some(outer_span) = outer_span {
+                diag = diag.with_secondary(outer_span, "outer binding");
+            }
+            if let Binding::Builtin(_) = outer {
+                diag = diag.with_help("this hides a builtin");
+            }
+            self.report(diag);
+        }
+        self.frames[frame].insert(ident, (binding, Some(span)));
+    }
+
+    fn bind(&mut self, span: Span, binding: Binding<'s>) {
+        self.resolution.bindings.insert((self.module, span), binding);
+    }
+
+    fn not_found(&mut self, name: &str, span: Span, label: String) {
+        self.report(Diagnostic::error("E1001", format!("cannot find `{}` in this scope", name)).with_primary(span, label));
+    }
+
+    fn ident(&mut self, ident: &Ident) {
+        match self.lookup(&ident.lit) {
+            Some((binding, _)) => self.bind(ident.span, binding),
+            None => self.not_found(&ident.lit, ident.span, "not declared".to_string()),
+        }
+    }
+
+    /// `alu::ALU` names a declaration through modules. `Op::Add` names a variant of the type `Op`,
+    /// which is bound here and checked against the type later.
+    fn path(&mut self, path: &Path) {
+        let name = path.segments.join("::");
+        match self.lookup(&path.segments[0]) {
+            Some((Binding::Module(module), _)) => {
+                let program = self.program.expect("modules come from a program");
+                let mut segments = program.modules[module].path.segments.clone();
+                segments.extend_from_slice(&path.segments[1..]);
+                match program.lookup(&segments) {
+                    Ok(target) => self.bind(path.span, self.target(target)),
+                    Err(label) => self.not_found(&name, path.span, label),
+                }
+            }
+            Some((binding, _)) => self.bind(path.span, binding),
+            None => self.not_found(&name, path.span, format!("`{}` is not declared", path.segments[0])),
+        }
+    }
+
+    fn source_file(&mut self, file: &'s SourceFile, imported: Vec<(&'s str, Binding<'s>)>) {
+        self.frames.push(imported.into_iter().map(|(ident, binding)| (ident, (binding, None))).collect());
+        for decl in &file.decls {
+            // Imports clashing with declarations are the loader's to report.
+            self.frames[0].remove(decl.ident());
+        }
+        for decl in &file.decls {
+            self.declare(decl.ident(), Binding::Decl(decl), decl.span());
+        }
+        for decl in &file.decls {
+            self.decl_body(decl);
+        }
+        self.frames.pop();
+    }
+
+    fn decl_body(&mut self, decl: &'s Decl) {
+        let generics = match decl {
+            Decl::Let(v) => v.generics.as_slice(),
+            Decl::Var(_) => &[],
+            Decl::TypeAlias(v) => v.generics.as_slice(),
+        };
+        if !generics.is_empty() {
+            self.frames.push(HashMap::new());
+            for param in generics {
+                self.declare(&param.ident().lit, Binding::Generic(param), param.span());
+            }
+        }
+        match decl {
+            Decl::Let(v) => {
+                if let Some(ty) = &v.ty {
+                    self.ty(ty);
+                }
+                self.expr(&v.expr)
+            }
+            Decl::Var(v) => {
+                if let Some(ty) = &v.ty {
+                    self.ty(ty);
+                }
+                self.expr(&v.expr)
+            }
+            Decl::TypeAlias(v) => self.ty(&v.ty),
+        }
+        if !generics.is_empty() {
+            self.frames.pop();
+        }
+    }
+
+    fn ty(&mut self, ty: &'s Type) {
+        match ty {
+            Type::Ident(v) => self.ident(v),
+            Type::Apply(v) => {
+                self.ident(&v.ty);
+                for param in &v.params {
+                    if let TypeParam::Type(ty) = param {
+                        self.ty(ty);
+                    }
+                }
+            }
+            Type::Record(v) => {
+                for field in &v.fields {
+                    self.ty(&field.ty);
+                }
+            }
+            Type::Union(v) => {
+                for variant in &v.variants {
+                    self.ty(&variant.ty);
+                }
+            }
+            Type::Func(v) => self.func_type(v),
+            Type::Infer(_) => {}
+        }
+    }
+
+    fn func_type(&mut self, ty: &'s FuncType) {
+        for field in &ty.params.fields {
+            self.ty(&field.ty);
+        }
+        self.ty(&ty.result);
+    }
+
+    fn expr(&mut self, expr: &'s Expr) {
+        match expr {
+            Expr::Nat(_) => {}
+            Expr::Ident(v) => self.ident(v),
+            Expr::Path(v) => self.path(v),
+            Expr::Block(v) => self.block(v),
+            Expr::Func(v) => {
+                self.func_type(&v.ty);
+                self.frames.push(HashMap::new());
+                for param in &v.ty.params.fields {
+                    self.declare(&param.ident, Binding::Param(param), param.span);
+                }
+                self.block(&v.block);
+                self.frames.pop();
+            }
+            Expr::Record(v) => self.record(v),
+            Expr::Apply(v) => {
+                self.expr(&v.func);
+                self.record(&v.params);
+            }
+            Expr::Match(v) => {
+                self.expr(&v.expr);
+                for case in &v.cases {
+                    self.frames.push(HashMap::new());
+                    self.pattern(&case.pattern, false);
+                    self.expr(&case.expr);
+                    self.frames.pop();
+                }
+            }
+            Expr::Select(v) => self.expr(&v.expr),
+            Expr::Pipe(v) => {
+                self.expr(&v.from);
+                self.expr(&v.to);
+            }
+        }
+    }
+
+    fn record(&mut self, record: &'s RecordExpr) {
+        for field in &record.fields {
+            self.expr(&field.expr);
+        }
+    }
+
+    /// Declares the names a pattern binds. Every alternative after the first of an or-pattern
+    /// binds the same names again, so `alt` lets them reuse the first binding.
+    fn pattern(&mut self, pattern: &'s Pattern, alt: bool) {
+        match pattern {
+            Pattern::Wildcard(_) | Pattern::Nat(_) | Pattern::Range(_) => {}
+            Pattern::Bind(v) => {
+                let frame = self.frames.len() - 1;
+                match self.frames[frame].get(v.lit.as_str()).copied() {
+                    Some((binding @ Binding::Pattern(_), _)) if alt => self.bind(v.span, binding),
+                    _ => self.declare(&v.lit, Binding::Pattern(v), v.span),
+                }
+            }
+            Pattern::Variant(v) => {
+                self.path(&v.path);
+                if let Some(payload) = &v.payload {
+                    self.pattern(payload, alt);
+                }
+            }
+            Pattern::Record(v) => {
+                for field in &v.fields {
+                    self.pattern(&field.pattern, alt);
+                }
+            }
+            Pattern::Or(v) => {
+                for (i, pattern) in v.alts.iter().enumerate() {
+                    self.pattern(pattern, alt || i > 0);
+                }
+                self.same_names(&v.alts);
+            }
+        }
+    }
+
+    /// Reports each alternative of an or-pattern that leaves out a name another one binds.
+    fn same_names(&mut self, alts: &[Pattern]) {
+        let names: Vec<_> = alts.iter().map(|alt| {
+            let mut names = Vec::new();
+            bound(alt, &mut names);
+            names
+        }).collect();
+        let mut all: Vec<&str> = Vec::new();
+        for name in names.iter().flatten() {
+            if !all.contains(name) {
+                all.push(name);
+            }
+        }
+        for (alt, names) in alts.iter().zip(&names) {
+            let missing: Vec<_> = all.iter().filter(|name| !names.contains(name)).map(|name| format!("`{}`", name)).collect();
+            if !missing.is_empty() {
+                let verb = if missing.len() == 1 { "is" } else { "are" };
+                self.report(Diagnostic::error("E1009", format!("{} {} not bound in every alternative", missing.join(", "), verb))
+                    .with_primary(alt.span(), format!("does not bind {}", missing.join(", ")))
+                    .with_help("every alternative of an or-pattern must bind the same names"));
+            }
+        }
+    }
+
+    fn block(&mut self, block: &'s Block) {
+        self.frames.push(HashMap::new());
+        for stmt in &block.stmts {
+            if let Stmt::Decl(decl @ Decl::TypeAlias(_)) = stmt {
+                self.declare(decl.ident(), Binding::Decl(decl), decl.span());
+            }
+        }
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Decl(decl @ Decl::TypeAlias(_)) => self.decl_body(decl),
+                Stmt::Decl(decl @ Decl::Let(LetDecl { expr: Expr::Func(_), .. })) => {
+                    self.declare(decl.ident(), Binding::Decl(decl), decl.span());
+                    self.decl_body(decl);
+                }
+                Stmt::Decl(decl) => {
+                    self.decl_body(decl);
+                    self.declare(decl.ident(), Binding::Decl(decl), decl.span());
+                }
+                Stmt::Expr(v) => self.expr(v),
+                Stmt::Assign(v) => {
+                    self.ident(&v.target);
+                    self.expr(&v.expr);
+                }
+                Stmt::While(v) => {
+                    self.expr(&v.cond);
+                    self.block(&v.block);
+                }
+            }
+        }
+        self.frames.pop();
+    }
+}
+
+/// The names a pattern binds, in the order written.
+fn bound<'s>(pattern: &'s Pattern, found: &mut Vec<&'s str>) {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Nat(_) | Pattern::Range(_) => {}
+        Pattern::Bind(v) => {
+            if !found.contains(&v.lit.as_str()) {
+                found.push(&v.lit);
+            }
+        }
+        Pattern::Variant(v) => {
+            if let Some(payload) = &v.payload {
+                bound(payload, found);
+            }
+        }
+        Pattern::Record(v) => {
+            for field in &v.fields {
+                bound(&field.pattern, found);
+            }
+        }
+        Pattern::Or(v) => {
+            for alt in &v.alts {
+                bound(alt, found);
+            }
+        }
+    }
+}
@@ -2,10 +2,156 @@
 // This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
 // that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
 
+//! The rich IR, where every name stands for the symbol it was resolved to.
+
+use num_bigint::BigUint;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write;
+use paracell_parser_sem::span::Span;
+use paracell_util_macro::ToLiteral;
 use paracell_util_struct::map::OrderedHashMap;
 
+/// Operators and types every program sees without declaring them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ToLiteral)]
+pub enum Builtin {
+    #[literal = "Nat"]
+    Nat,
+    #[literal = "Bits"]
+    Bits,
+    #[literal = "Int"]
+    Int,
+
+    /// Drops the high bits of a number, down to the width it is used at.
+    #[literal = "trunc"]
+    Trunc,
+    /// Widens a number to the width it is used at, with zeros or, for `Int`, copies of its sign.
+    #[literal = "ext"]
+    Ext,
+
+    #[literal = "~"]
+    Invert,
+    #[literal = "!"]
+    Not,
+
+    #[literal = "+"]
+    Add,
+    #[literal = "-"]
+    Sub,
+    #[literal = "*"]
+    Mul,
+    #[literal = "/"]
+    Div,
+    #[literal = "%"]
+    Mod,
+    #[literal = "&"]
+    And,
+    #[literal = "|"]
+    Or,
+    #[literal = "^"]
+    Xor,
+    #[literal = "<<"]
+    Shl,
+    #[literal = ">>"]
+    Shr,
+    #[literal = "=="]
+    Eq,
+    #[literal = "!="]
+    Ne,
+    #[literal = "<"]
+    Lt,
+    #[literal = "<="]
+    Le,
+    #[literal = ">"]
+    Gt,
+    #[literal = ">="]
+    Ge,
+    #[literal = "&&"]
+    LogicAnd,
+    #[literal = "||"]
+    LogicOr,
+}
+
+impl Builtin {
+    pub const ALL: &[Builtin] = &[
+        Builtin::Nat,
+        Builtin::Bits,
+        Builtin::Int,
+        Builtin::Trunc,
+        Builtin::Ext,
+        Builtin::Invert,
+        Builtin::Not,
+        Builtin::Add,
+        Builtin::Sub,
+        Builtin::Mul,
+        Builtin::Div,
+        Builtin::Mod,
+        Builtin::And,
+        Builtin::Or,
+        Builtin::Xor,
+        Builtin::Shl,
+        Builtin::Shr,
+        Builtin::Eq,
+        Builtin::Ne,
+        Builtin::Lt,
+        Builtin::Le,
+        Builtin::Gt,
+        Builtin::Ge,
+        Builtin::LogicAnd,
+        Builtin::LogicOr,
+    ];
+
+    pub fn from_literal(lit: &str) -> Option<Builtin> {
+        Builtin::ALL.iter().copied().find(|builtin| builtin.to_literal() == lit)
+    }
+
+    /// The kind of numbers a builtin type names, or `None` for operators.
+    pub fn kind(self) -> Option<NatKind> {
+        match self {
+            Builtin::Nat => Some(NatKind::Nat),
+            Builtin::Bits => Some(NatKind::Bits),
+            Builtin::Int => Some(NatKind::Int),
+            _ => None,
+        }
+    }
+}
+
+/// A name bound by a declaration, a parameter or a pattern, unique within a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Decl,
+    Param,
+    Pattern,
+    Generic,
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolInfo<'a> {
+    pub ident: String,
+    pub kind: SymbolKind,
+    /// The module whose source `span` points into.
+    pub module: usize,
+    pub span: Span,
+    /// Set once a declaration is lowered.
+    pub decl: Option<&'a Decl<'a>>,
+}
+
+/// A type to be found by inference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TypeVar(pub usize);
+
+/// A width to be found by inference, numbered along with type variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WidthVar(pub usize);
+
+/// A kind of number to be found by inference, numbered along with type variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KindVar(pub usize);
+
 #[derive(Clone, Debug)]
 pub struct Field<'a> {
     pub ident: String,
@@ -18,6 +164,18 @@ pub struct RecordType<'a> {
     pub names: HashMap<String, usize>,
 }
 
+impl<'a> RecordType<'a> {
+    pub fn new(fields: Vec<Field<'a>>) -> RecordType<'a> {
+        let names = fields.iter().enumerate().map(|(i, field)| (field.ident.clone(), i)).collect();
+        RecordType { fields, names }
+    }
+
+    /// `()`, the type of blocks without a result.
+    pub fn unit() -> RecordType<'a> {
+        RecordType::new(Vec::new())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Variant<'a> {
     pub ident: String,
@@ -30,8 +188,143 @@ pub struct UnionType<'a> {
     pub names: HashMap<String, usize>,
 }
 
+impl<'a> UnionType<'a> {
+    pub fn new(variants: Vec<Variant<'a>>) -> UnionType<'a> {
+        let names = variants.iter().enumerate().map(|(i, variant)| (variant.ident.clone(), i)).collect();
+        UnionType { variants, names }
+    }
+}
+
+/// How the bits of a number are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NatKind {
+    /// Unsigned.
+    Nat,
+    /// Unsigned, for values read bit by bit rather than as numbers.
+    Bits,
+    /// Two's complement.
+    Int,
+    Var(KindVar),
+}
+
+/// A number of bits, as written or as found from the widths of operands.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Width {
+    /// Of a type written without a width, with as many bits as its values need.
+    Unbounded,
+    Const(u32),
+    Var(WidthVar),
+    /// A width parameter of a generic declaration.
+    Param(Symbol),
+    /// The wider of two widths.
+    Max(Box<Width>, Box<Width>),
+    Sum(Box<Width>, Box<Width>),
+}
+
+impl Width {
+    /// The width of `a + b`, one bit wider than its widest operand for the carry.
+    pub fn carry(a: Width, b: Width) -> Width {
+        Width::Sum(Box::from(Width::Max(Box::from(a), Box::from(b))), Box::from(Width::Const(1)))
+    }
+
+    /// Computes what can be computed without knowing the variables. Anything with an
+    /// unbounded operand is unbounded.
+    pub fn fold(&self) -> Width {
+        let op = |a: &Width, b: &Width, f: fn(u32, u32) -> u32, rebuild: fn(Box<Width>, Box<Width>) -> Width| {
+            match (a.fold(), b.fold()) {
+                (Width::Unbounded, _) | (_, Width::Unbounded) => Width::Unbounded,
+                (Width::Const(a), Width::Const(b)) => Width::Const(f(a, b)),
+                (a, b) => rebuild(Box::from(a), Box::from(b)),
+            }
+        };
+        match self {
+            Width::Max(a, b) => match op(a, b, u32::max, Width::Max) {
+                Width::Max(a, b) if a == b => *a,
+                max => max,
+            },
+            Width::Sum(a, b) => op(a, b, u32::saturating_add, Width::Sum),
+            _ => self.clone(),
+        }
+    }
+
+    /// Whether the width is a number, a parameter, or unbounded.
+    pub fn is_known(&self) -> bool {
+        matches!(self, Width::Unbounded | Width::Const(_) | Width::Param(_))
+    }
+
+    /// Whether this width is never less than `other`, for any value of the parameters in them.
+    pub fn covers(&self, other: &Width) -> bool {
+        match (self, other) {
+            (a, b) if a == b => true,
+            (Width::Unbounded, _) => true,
+            (Width::Const(a), Width::Const(b)) => a >= b,
+            (this, Width::Max(a, b)) => this.covers(a) && this.covers(b),
+            (Width::Max(a, b), other) => a.covers(other) || b.covers(other),
+            (Width::Sum(a, b), other) => a.covers(other) || b.covers(other),
+            _ => false,
+        }
+    }
+
+    /// The fewest bits the width may stand for, taking each parameter as at least 1 bit.
+    pub fn least(&self) -> u32 {
+        match self {
+            Width::Unbounded => u32::MAX,
+            Width::Const(v) => *v,
+            Width::Var(_) | Width::Param(_) => 1,
+            Width::Max(a, b) => a.least().max(b.least()),
+            Width::Sum(a, b) => a.least().saturating_add(b.least()),
+        }
+    }
+
+    /// Replaces the width parameters `args` binds.
+    pub fn subst(&self, args: &HashMap<Symbol, GenericArg<'_>>) -> Width {
+        match self {
+            Width::Param(sym) => match args.get(sym) {
+                Some(GenericArg::Width(width)) => width.clone(),
+                _ => self.clone(),
+            },
+            Width::Max(a, b) => Width::Max(Box::from(a.subst(args)), Box::from(b.subst(args))).fold(),
+            Width::Sum(a, b) => Width::Sum(Box::from(a.subst(args)), Box::from(b.subst(args))).fold(),
+            Width::Unbounded | Width::Const(_) | Width::Var(_) => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Width {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Width::Unbounded => f.write_char('_'),
+            Width::Const(v) => write!(f, "{}", v),
+            Width::Var(var) => write!(f, "?{}", var.0),
+            Width::Param(sym) => write!(f, "${}", sym.0),
+            Width::Max(a, b) => write!(f, "max({}, {})", a, b),
+            Width::Sum(a, b) => write!(f, "{} + {}", a, b),
+        }
+    }
+}
+
+/// `Nat`, `Bits[8]` or `Int[16]`.
 #[derive(Clone, Debug)]
-pub struct NatType {}
+pub struct NatType {
+    pub kind: NatKind,
+    pub width: Width,
+}
+
+impl NatType {
+    /// `Nat` without a width.
+    pub fn unbounded() -> NatType {
+        NatType { kind: NatKind::Nat, width: Width::Unbounded }
+    }
+
+    /// The fewest bits a number of this kind needs to hold `val`.
+    pub fn bits(kind: NatKind, val: &BigUint) -> u32 {
+        let bits = u32::try_from(val.bits()).unwrap_or(u32::MAX).max(1);
+        match kind {
+            NatKind::Int => bits.saturating_add(1),
+            _ => bits,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub enum PrimitiveType {
@@ -49,46 +342,314 @@ pub enum Type<'a> {
     Primitive(PrimitiveType),
     Record(RefCell<RecordType<'a>>),
     Union(RefCell<UnionType<'a>>),
+    Func(Box<FuncType<'a>>),
+    /// The type declared by a `type` alias, given one argument per generic parameter.
+    Alias(Symbol, Vec<GenericArg<'a>>),
+    /// A type parameter of a generic declaration.
+    Param(Symbol),
+    Var(TypeVar),
+}
+
+impl<'a> Type<'a> {
+    pub fn unit() -> Type<'a> {
+        Type::Record(RefCell::new(RecordType::unit()))
+    }
+
+    /// Replaces the type and width parameters `args` binds.
+    pub fn subst(&self, args: &HashMap<Symbol, GenericArg<'a>>) -> Type<'a> {
+        let fields = |fields: &[Field<'a>]| -> Vec<Field<'a>> {
+            fields.iter().map(|field| Field { ident: field.ident.clone(), ty: field.ty.subst(args) }).collect()
+        };
+        match self {
+            Type::Param(sym) => match args.get(sym) {
+                Some(GenericArg::Type(ty)) => ty.clone(),
+                _ => self.clone(),
+            },
+            Type::Primitive(PrimitiveType::Nat(v)) => {
+                Type::Primitive(PrimitiveType::Nat(NatType { kind: v.kind, width: v.width.subst(args) }))
+            }
+            Type::Record(v) => Type::Record(RefCell::new(RecordType::new(fields(&v.borrow().fields)))),
+            Type::Union(v) => {
+                let variants = v.borrow().variants.iter()
+                    .map(|variant| Variant { ident: variant.ident.clone(), ty: variant.ty.subst(args) })
+                    .collect();
+                Type::Union(RefCell::new(UnionType::new(variants)))
+            }
+            Type::Func(v) => {
+                let params = RecordType::new(fields(&v.params.fields));
+                Type::Func(Box::from(FuncType { params, results: v.results.subst(args) }))
+            }
+            Type::Alias(sym, params) => Type::Alias(*sym, params.iter().map(|arg| arg.subst(args)).collect()),
+            Type::Var(_) => self.clone(),
+        }
+    }
+}
+
+/// A parameter of a generic `let` or type alias.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenericParam {
+    Type(Symbol),
+    Width(Symbol),
+}
+
+impl GenericParam {
+    pub fn sym(self) -> Symbol {
+        match self {
+            GenericParam::Type(sym) | GenericParam::Width(sym) => sym,
+        }
+    }
+}
+
+/// What a generic parameter stands for at one use of its declaration.
+#[derive(Clone, Debug)]
+pub enum GenericArg<'a> {
+    Type(Type<'a>),
+    Width(Width),
+}
+
+impl<'a> GenericArg<'a> {
+    pub fn subst(&self, args: &HashMap<Symbol, GenericArg<'a>>) -> GenericArg<'a> {
+        match self {
+            GenericArg::Type(ty) => GenericArg::Type(ty.subst(args)),
+            GenericArg::Width(width) => GenericArg::Width(width.subst(args)),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct NatExpr {
-    pub val: u128,
+    pub val: BigUint,
+    /// The width written after the literal, if any.
+    pub width: Option<u32>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolExpr {
+    pub sym: Symbol,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuiltinExpr {
+    pub builtin: Builtin,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldExpr<'a> {
+    pub ident: String,
+    pub expr: Expr<'a>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordExpr<'a> {
+    pub fields: Vec<FieldExpr<'a>>,
+    pub span: Span,
+}
+
+impl<'a> RecordExpr<'a> {
+    pub fn unit(span: Span) -> RecordExpr<'a> {
+        RecordExpr { fields: Vec::new(), span }
+    }
+}
+
+/// `Op::Add` or `Op::Mul((a, b))`, constructing a variant of the union `ty` is an alias of.
+#[derive(Clone, Debug)]
+pub struct VariantExpr<'a> {
+    pub ty: Symbol,
+    pub ident: String,
+    /// `None` for a variant written without arguments.
+    pub payload: Option<Box<Expr<'a>>>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct ApplyExpr<'a> {
+    pub func: Expr<'a>,
+    pub params: RecordExpr<'a>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectExpr<'a> {
+    pub expr: Expr<'a>,
+    pub ident: String,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct FuncExpr<'a> {
+    pub ty: FuncType<'a>,
+    /// One per field of `ty.params`, in order.
+    pub params: Vec<Symbol>,
+    pub body: Scope<'a>,
+    pub span: Span,
+}
+
+/// `x = ...`, where `target` is declared by a `var`.
+#[derive(Clone, Debug)]
+pub struct AssignExpr<'a> {
+    pub target: Symbol,
+    pub expr: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct WhileExpr<'a> {
+    pub cond: Expr<'a>,
+    pub body: Scope<'a>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct NatPattern {
+    pub val: BigUint,
+    pub width: Option<u32>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct RangePattern {
+    pub lo: BigUint,
+    pub hi: BigUint,
+    pub inclusive: bool,
+    pub span: Span,
+}
+
+/// Every alternative of an or-pattern binds the same symbols.
+#[derive(Clone, Debug)]
+pub struct BindPattern {
+    pub sym: Symbol,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct VariantPattern {
+    pub ty: Symbol,
+    pub ident: String,
+    pub payload: Option<Box<Pattern>>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct FieldPattern {
+    pub ident: String,
+    pub pattern: Pattern,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordPattern {
+    pub fields: Vec<FieldPattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct OrPattern {
+    pub alts: Vec<Pattern>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Wildcard(Span),
+    Nat(NatPattern),
+    Range(RangePattern),
+    Bind(BindPattern),
+    Variant(VariantPattern),
+    Record(RecordPattern),
+    Or(OrPattern),
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(span) => *span,
+            Pattern::Nat(v) => v.span,
+            Pattern::Range(v) => v.span,
+            Pattern::Bind(v) => v.span,
+            Pattern::Variant(v) => v.span,
+            Pattern::Record(v) => v.span,
+            Pattern::Or(v) => v.span,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Case<'a> {
-    pub pattern: Expr<'a>,
+    pub pattern: Pattern,
     pub expr: Scope<'a>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Match<'a> {
+    pub expr: Expr<'a>,
     pub cases: Vec<Case<'a>>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub enum Expr<'a> {
     Nat(NatExpr),
-    Match(Match<'a>),
+    Symbol(SymbolExpr),
+    Builtin(BuiltinExpr),
+    Record(RecordExpr<'a>),
+    Variant(VariantExpr<'a>),
+    Apply(Box<ApplyExpr<'a>>),
+    Select(Box<SelectExpr<'a>>),
+    Block(Box<Scope<'a>>),
+    Func(Box<FuncExpr<'a>>),
+    Match(Box<Match<'a>>),
+    Assign(Box<AssignExpr<'a>>),
+    While(Box<WhileExpr<'a>>),
+}
+
+impl Expr<'_> {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Nat(v) => v.span,
+            Expr::Symbol(v) => v.span,
+            Expr::Builtin(v) => v.span,
+            Expr::Record(v) => v.span,
+            Expr::Variant(v) => v.span,
+            Expr::Apply(v) => v.span,
+            Expr::Select(v) => v.span,
+            Expr::Block(v) => v.span,
+            Expr::Func(v) => v.span,
+            Expr::Match(v) => v.span,
+            Expr::Assign(v) => v.span,
+            Expr::While(v) => v.span,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct LetDecl<'a> {
+    pub sym: Symbol,
     pub ident: String,
+    /// Given anew wherever the declaration is used.
+    pub generics: Vec<GenericParam>,
+    pub ty: Option<Type<'a>>,
     pub expr: Expr<'a>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct VarDecl<'a> {
+    pub sym: Symbol,
     pub ident: String,
+    pub ty: Option<Type<'a>>,
     pub expr: Expr<'a>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct TypeAliasDecl<'a> {
+    pub sym: Symbol,
     pub ident: String,
+    pub generics: Vec<GenericParam>,
     pub ty: Type<'a>,
+    pub span: Span,
 }
 
 #[derive(Clone, Debug)]
@@ -98,8 +659,139 @@ pub enum Decl<'a> {
     TypeAlias(TypeAliasDecl<'a>),
 }
 
+impl Decl<'_> {
+    pub fn sym(&self) -> Symbol {
+        match self {
+            Decl::Let(v) => v.sym,
+            Decl::Var(v) => v.sym,
+            Decl::TypeAlias(v) => v.sym,
+        }
+    }
+
+    pub fn ident(&self) -> &str {
+        match self {
+            Decl::Let(v) => &v.ident,
+            Decl::Var(v) => &v.ident,
+            Decl::TypeAlias(v) => &v.ident,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Decl::Let(v) => v.span,
+            Decl::Var(v) => v.span,
+            Decl::TypeAlias(v) => v.span,
+        }
+    }
+
+    /// The generic parameters, none for a `var`.
+    pub fn generics(&self) -> &[GenericParam] {
+        match self {
+            Decl::Let(v) => &v.generics,
+            Decl::Var(_) => &[],
+            Decl::TypeAlias(v) => &v.generics,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Stmt<'a> {
+    Decl(&'a Decl<'a>),
+    Expr(Expr<'a>),
+}
+
+/// A block: its declarations by name, every statement in order, then its result.
 #[derive(Clone, Debug)]
 pub struct Scope<'a> {
     pub decls: OrderedHashMap<&'a str, &'a Decl<'a>>,
+    pub stmts: Vec<Stmt<'a>>,
+    /// `()` when the block ends in a declaration, an assignment or a loop.
     pub expr: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub struct Module<'a> {
+    /// `alu::adder`, or empty for the root module.
+    pub path: String,
+    pub decls: OrderedHashMap<&'a str, &'a Decl<'a>>,
+}
+
+/// Every module of a program, lowered, with the symbols they bind.
+#[derive(Clone, Debug)]
+pub struct Program<'a> {
+    pub symbols: Vec<SymbolInfo<'a>>,
+    pub modules: Vec<Module<'a>>,
+    /// How many variables stand for the `_`s in the source, types and widths numbered together from 0.
+    pub vars: usize,
+}
+
+impl<'a> Program<'a> {
+    pub fn symbol(&self, sym: Symbol) -> &SymbolInfo<'a> {
+        &self.symbols[sym.0]
+    }
+
+    pub fn decl(&self, sym: Symbol) -> Option<&'a Decl<'a>> {
+        self.symbols[sym.0].decl
+    }
+
+    /// The top-level declaration `ident` of the root module.
+    pub fn root(&self, ident: &str) -> Option<&'a Decl<'a>> {
+        self.modules.first()?.decls.get(&ident).copied()
+    }
+
+    /// Spells a type as flow source, naming aliases instead of expanding them.
+    pub fn type_name(&self, ty: &Type<'a>) -> String {
+        let fields = |fields: &[Field<'a>]| {
+            fields.iter().map(|field| format!("{}: {}", field.ident, self.type_name(&field.ty))).collect::<Vec<_>>().join(", ")
+        };
+        match ty {
+            Type::Primitive(PrimitiveType::Nat(v)) => {
+                let kind = match v.kind {
+                    NatKind::Nat => Builtin::Nat.to_literal().to_string(),
+                    NatKind::Bits => Builtin::Bits.to_literal().to_string(),
+                    NatKind::Int => Builtin::Int.to_literal().to_string(),
+                    NatKind::Var(var) => format!("?{}", var.0),
+                };
+                match v.width {
+                    Width::Unbounded => kind,
+                    ref width => format!("{}[{}]", kind, self.width_name(width)),
+                }
+            }
+            Type::Record(v) => {
+                let v = v.borrow();
+                if v.fields.iter().enumerate().all(|(i, field)| field.ident == i.to_string()) {
+                    format!("({})", v.fields.iter().map(|field| self.type_name(&field.ty)).collect::<Vec<_>>().join(", "))
+                } else {
+                    format!("record {{ {} }}", fields(&v.fields))
+                }
+            }
+            Type::Union(v) => {
+                let v = v.borrow();
+                let variants = v.variants.iter().map(|variant| format!("{}: {}", variant.ident, self.type_name(&variant.ty)));
+                format!("union {{ {} }}", variants.collect::<Vec<_>>().join(", "))
+            }
+            Type::Func(v) => format!("({}) -> {}", fields(&v.params.fields), self.type_name(&v.results)),
+            Type::Alias(sym, args) if args.is_empty() => self.symbol(*sym).ident.clone(),
+            Type::Alias(sym, args) => {
+                let args = args.iter().map(|arg| match arg {
+                    GenericArg::Type(ty) => self.type_name(ty),
+                    GenericArg::Width(width) => self.width_name(width),
+                });
+                format!("{}[{}]", self.symbol(*sym).ident, args.collect::<Vec<_>>().join(", "))
+            }
+            Type::Param(sym) => self.symbol(*sym).ident.clone(),
+            Type::Var(var) => format!("?{}", var.0),
+        }
+    }
+
+    /// Spells a width as flow source, naming parameters.
+    pub fn width_name(&self, width: &Width) -> String {
+        match width {
+            Width::Param(sym) => self.symbol(*sym).ident.clone(),
+            Width::Max(a, b) => format!("max({}, {})", self.width_name(a), self.width_name(b)),
+            Width::Sum(a, b) => format!("{} + {}", self.width_name(a), self.width_name(b)),
+            width => width.to_string(),
+        }
+    }
 }
@@ -0,0 +1,322 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::check::check;
+use paracell_represent::lower::lower;
+use paracell_represent::sym::Type;
+use typed_arena::Arena;
+
+#[test]
+fn test_check() {
+    let src = "
+        type Op = union { Add: Nat, Pair: (Nat, Nat), Nop: () };
+        type Point = record { x: Nat, y: Nat };
+
+        fun ALU(a: Nat, b: Nat, op: Op) -> Nat {
+            match op {
+                Op::Add(c) => a + c,
+                Op::Pair(c, d) => c * d,
+                Op::Nop => b
+            }
+        }
+
+        fun Norm(p: Point) -> Nat { p.x * p.x + p.y * p.y }
+
+        let r = ALU(b: 1, a: 2, op: Op::Pair(3, 4));
+        let n = Norm((x: r, y: 1)) |> fun (v: Nat) -> Nat { v };
+        let s = Op::Nop;
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let ty = |ident| program.type_name(&typing.types[&program.root(ident).unwrap().sym()]);
+
+    assert_eq!(ty("ALU"), "(a: Nat, b: Nat, op: Op) -> Nat");
+    assert_eq!(ty("r"), "Nat");
+    assert_eq!(ty("n"), "Nat");
+    assert_eq!(ty("s"), "Op");
+}
+
+#[test]
+fn test_check_errors() {
+    let src = "
+        type Op = union { Add: Nat, Pair: (Nat, Nat), Nop: () };
+        fun f(a: Nat, op: Op) -> Nat {
+            let p = (q: 1, r: 2);
+            var v = p.q;
+            v = op;
+            let x = p.s;
+            let y = Op::Mul;
+            let z = Op::Pair(1);
+            let w = f(1);
+            let u = a(1);
+            match op {
+                Op::Add(n) => n,
+                Op::Pair(b, c) => (b, c),
+                _ => 0
+            }
+        }
+        let k = f(a: 1, o: Op::Nop);
+        let l = m;
+        let m = l;
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E2001", "E2003", "E2005", "E2001", "E2002", "E2006", "E2001", "E2002", "E2002", "E2007"]);
+
+    let labels: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    assert_eq!(labels[0], "expected `Nat`, found `Op`");
+    assert_eq!(labels[3], "expected `(Nat, Nat)`, found `Nat`");
+    assert_eq!(labels[4], "expected `(a: Nat, op: Op) -> Nat`");
+    assert_eq!(labels[6], "expected `Nat`, found `(Nat, Nat)`");
+    assert_eq!(typing.diagnostics[6].1.labels[1].message, "expected because of the first arm");
+    assert_eq!(typing.diagnostics[2].1.help, ["the variants of `Op` are `Add`, `Pair`, `Nop`"]);
+    assert_eq!(typing.diagnostics[8].1.message, "missing argument `op`");
+    assert_eq!(typing.diagnostics[9].1.message, "cannot find the type of `l`, which depends on itself");
+}
+
+#[test]
+fn test_infer() {
+    let src = "
+        type Point = record { x: Nat, y: Nat };
+
+        let id = fun (x) { x };
+        let add = fun (a, b) { a + b };
+        let twice = fun (f, v) { f(f(v)) };
+
+        let n: Nat = id(1);
+        let p = id((x: 1, y: 2));
+        let m = twice(fun (v) { add(v, 1) }, n);
+        let q: Point = p;
+        let first = fun (p: Point) { p.x };
+
+        let b: Nat[8] = 200;
+        let sum = add(b, b);
+        let product = b * 3;
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let ty = |ident| program.type_name(&typing.types[&program.root(ident).unwrap().sym()]);
+
+    assert_eq!(ty("sum"), "Nat[9]");
+    assert_eq!(ty("product"), "Nat[10]");
+    assert_eq!(ty("p"), "record { x: Nat, y: Nat }");
+    assert_eq!(ty("m"), "Nat");
+    assert_eq!(ty("first"), "(p: Point) -> Nat");
+
+    // Generalized, so still a variable after both uses.
+    let Type::Func(id) = &typing.types[&program.root("id").unwrap().sym()] else { panic!() };
+
+    assert!(matches!((&id.params.fields[0].ty, &id.results), (Type::Var(a), Type::Var(b)) if a == b));
+}
+
+#[test]
+fn test_infer_errors() {
+    let src = "
+        let n: Nat = (1, 2);
+        let get = fun (p) { p.x };
+        let call = fun (f) { f(a: 1) };
+        let loop = fun (x) { x(x) };
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E2001", "E2008", "E2008", "E2001"]);
+    assert_eq!(typing.diagnostics[0].1.labels[0].message, "expected `Nat`, found `(Nat, Nat)`");
+    assert_eq!(typing.diagnostics[0].1.labels[1].message, "expected because of the annotation");
+    assert_eq!(typing.diagnostics[1].1.labels[0].message, "the type of this must be known to select `x`");
+}
+
+#[test]
+fn test_check_widths() {
+    let src = "
+        fun Inc(v: Nat[8]) -> Nat[8] { trunc(v + 1) }
+        fun Widen(v: Bits[4]) -> Bits[_] { ext(v) }
+
+        let a: Int[4] = 7;
+        let c = Inc(255);
+        let d = 3 < 4;
+        let e: Bits[12] = Widen(0xF);
+        let f = 0x1FF;
+        var g: Nat[8] = 0;
+        let h = g + c * c;
+
+        let x: Nat[4] = 16;
+        let y: Int[4] = 8;
+        fun Carry(v: Nat[8]) -> Nat[8] { v + 1 }
+        fun Narrow(v: Nat[8]) -> Nat[4] { ext(v) }
+        fun Sign(v: Int[8]) -> Nat[8] { v }
+        let z = trunc(1);
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    let ty = |ident| program.type_name(&typing.types[&program.root(ident).unwrap().sym()]);
+
+    assert_eq!(ty("a"), "Int[4]");
+    assert_eq!(ty("c"), "Nat[8]");
+    assert_eq!(ty("d"), "Nat[1]");
+    assert_eq!(ty("Widen"), "(v: Bits[4]) -> Bits[12]");
+    assert_eq!(ty("f"), "Nat[9]");
+    assert_eq!(ty("h"), "Nat[17]");
+
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+    let labels: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    // A width left to a literal is only known once the whole program is checked.
+    assert_eq!(codes, ["E2009", "E2009", "E2010", "E2001", "E2001", "E2008"], "{:?}", labels);
+    assert_eq!(typing.diagnostics[0].1.message, "literal out of range for `Nat[4]`");
+    assert_eq!(labels[0], "`16` needs 5 bits");
+    assert_eq!(labels[1], "`8` needs 5 bits");
+    assert_eq!(typing.diagnostics[2].1.message, "cannot extend `Nat[8]` to `Nat[4]`");
+    assert_eq!(labels[3], "expected `Nat[8]`, found `Int[8]`");
+    assert_eq!(labels[4], "expected `Nat[8]`, found `Nat[9]`");
+    assert_eq!(labels[5], "cannot find the width `trunc` gives");
+}
+
+#[test]
+fn test_check_param_widths() {
+    let src = "
+        fun X[N: Nat](a: Nat[N], b: Nat[N]) -> Nat[N] { ext(a and b) }
+        fun Y[N: Nat](a: Nat[N], b: Nat[4]) -> Nat[N] { ext(a and b) }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+
+    // `N` covers `max(N, N)`, which is `N`, but not `max(N, 4)`.
+    assert_eq!(codes, ["E2010"]);
+    assert_eq!(typing.diagnostics[0].1.message, "cannot extend `Nat[max(N, 4)]` to `Nat[N]`");
+}
+
+#[test]
+fn test_check_big_nat() {
+    let src = "
+        let p = 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF;
+        let q: Bits[256] = p;
+        let r = 0xFF'12;
+        let s = 0xFF'8 + 1'8;
+
+        let t: Bits[255] = p;
+        let u = 0x100'8;
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    let ty = |ident| program.type_name(&typing.types[&program.root(ident).unwrap().sym()]);
+
+    assert_eq!(ty("p"), "Bits[256]");
+    assert_eq!(ty("r"), "Nat[12]");
+    assert_eq!(ty("s"), "Nat[9]");
+
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+    let labels: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    assert_eq!(codes, ["E2001", "E2009"], "{:?}", labels);
+    assert_eq!(labels[0], "expected `Bits[255]`, found `Bits[256]`");
+    assert_eq!(typing.diagnostics[1].1.message, "literal out of range for `Nat[8]`");
+    assert_eq!(labels[1], "`256` needs 9 bits");
+}
+
+#[test]
+fn test_check_generics() {
+    let src = "
+        type Pair[T] = (T, T);
+        type Word[N: Nat] = record { lo: Bits[N], hi: Bits[N] };
+        type Option[T] = union { Some: T, None: () };
+
+        fun Swap[T](p: Pair[T]) -> Pair[T] { match p { (x, y) => (y, x) } }
+        fun Join[N: Nat](w: Word[N]) -> Bits[_] { w.hi * w.lo }
+        fun Low[N: Nat](v: Nat[N]) -> Nat[N] { trunc(v + 1) }
+        fun Get[T](o: Option[T], d: T) -> T {
+            match o {
+                Option::Some(v) => v,
+                Option::None => d
+            }
+        }
+
+        let a = Swap((1, 2));
+        let b = Swap((a, a));
+        let c = Join((lo: 0xF'4, hi: 0'4));
+        let d = Low(0xFF'8);
+        let e = Get(Option::Some(3'4), 0);
+
+        fun Rigid[T](v: T) -> T { 1 }
+        fun Wide[N: Nat](v: Nat[N]) -> Nat[N] { v + 3 }
+        let f = Swap((1, (2, 3)));
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    let ty = |ident| program.type_name(&typing.types[&program.root(ident).unwrap().sym()]);
+
+    assert_eq!(ty("Swap"), "(p: Pair[T]) -> Pair[T]");
+    assert_eq!(ty("Join"), "(w: Word[N]) -> Bits[N + N]");
+    assert_eq!(ty("a"), "Pair[Nat[2]]");
+    assert_eq!(ty("b"), "Pair[Pair[Nat[2]]]");
+    assert_eq!(ty("c"), "Bits[8]");
+    assert_eq!(ty("d"), "Nat[8]");
+    assert_eq!(ty("e"), "Nat[4]");
+
+    // Each use of a generic declaration records what its parameters stand for.
+    assert_eq!(typing.instances.len(), 6);
+
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+    let labels: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    assert_eq!(codes, ["E2001", "E2001", "E2001"], "{:?}", labels);
+    assert_eq!(labels[0], "expected `T`, found `Nat`");
+    assert_eq!(labels[1], "expected `Pair[Nat]`, found `(Nat, (Nat, Nat))`");
+    // Widths left to compare are compared once the whole program is checked.
+    assert_eq!(labels[2], "expected `Nat[N]`, found `Nat[max(N, 2) + 1]`");
+}
+
+#[test]
+fn test_check_or_pattern() {
+    let src = "
+        type E = union { A: Nat[4], B: (Nat[4], Nat[4]), C: Nat[4] };
+
+        fun F(e: E) -> Nat[4] { match e { E::A(x) | E::C(x) => x, _ => 0 } }
+        fun H(e: E) -> (Nat[4], Nat[4]) { match e { E::A(x) | E::B(x) => x, _ => (0, 0) } }
+        fun G(e: E) -> Nat[4] { match e { E::A(x) | E::B(x) => x, _ => 0 } }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+    let labels: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    // Reported at the binding, after which `x` is `Nat[4]`, so that `H` then returns the wrong type.
+    assert_eq!(codes, ["E2001", "E2001", "E2001", "E2001"], "{:?}", labels);
+    assert_eq!(labels[0], "`x` is `Nat[4]` in an earlier alternative, but `(Nat[4], Nat[4])` here");
+    assert_eq!(labels[2], "expected `(Nat[4], Nat[4])`, found `Nat[4]`");
+    assert_eq!(labels[3], labels[0]);
+}
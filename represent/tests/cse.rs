@@ -0,0 +1,62 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::cse::{Node, cse, report};
+use paracell_represent::lower::lower;
+use paracell_represent::sym::*;
+use typed_arena::Arena;
+
+#[test]
+fn test_cse() {
+    let src = "
+        type Op = union { Add: (), Nop: () };
+
+        fun F(a: Nat[8], b: Nat[8], op: Op) -> Nat[_] {
+            let s = a + b;
+            let t = (a + b) * 2;
+            let r = match op {
+                Op::Add => (a + b) * 2 + s,
+                Op::Nop => s * 2
+            };
+            var v = a;
+            v = trunc(v + 1);
+            let u = v + 1;
+            r + t + u
+        }
+
+        type Word = record { lo: Nat[8], hi: Nat[8] };
+        fun G(w: Word) -> Nat[_] { w.lo + w.hi + w.lo }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let dags = cse(&program);
+
+    assert_eq!(dags.len(), 2);
+    let f = &dags[0];
+    assert_eq!(program.symbol(f.sym).ident, "F");
+    assert_eq!(f.merged, 4);
+
+    let Some(Decl::Let(LetDecl { expr: Expr::Func(func), .. })) = program.root("F") else { panic!() };
+    let stmts = &func.body.stmts;
+    let value = |i: usize| match stmts[i] {
+        Stmt::Decl(Decl::Let(v)) => &v.expr,
+        Stmt::Decl(Decl::Var(v)) => &v.expr,
+        _ => panic!(),
+    };
+    // `a + b` within `t` is the node of `s`.
+    let Expr::Apply(t) = value(1) else { panic!() };
+    assert_eq!(f.node(&t.params.fields[0].expr), f.node(value(0)));
+    // `v + 1` reads a `var`, so is not merged with the one assigned before.
+    let Stmt::Expr(Expr::Assign(assign)) = &stmts[4] else { panic!() };
+    let Expr::Apply(trunc) = &assign.expr else { panic!() };
+    assert_ne!(f.node(&trunc.params.fields[0].expr), f.node(value(5)));
+    assert!(matches!(f.nodes[f.node(value(2)).unwrap()], Node::Opaque(_)));
+    assert!(matches!(f.nodes[f.result], Node::Apply(..)));
+
+    let g = &dags[1];
+    assert_eq!(g.merged, 1);
+    assert_eq!(report(&program, &dags), format!("F: 4 merged, {} nodes\nG: 1 merged, {} nodes\n", f.nodes.len(), g.nodes.len()));
+}
@@ -0,0 +1,142 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::dataflow::{Op, graph, graphs};
+use paracell_represent::lower::lower;
+use paracell_represent::sym::*;
+use typed_arena::Arena;
+
+#[test]
+fn test_dataflow() {
+    let src = "
+        fun Divide(dividend: Nat, divisor: Nat) -> (Nat, Nat) {
+            var quotient = 0;
+            var remainder = dividend;
+            while divisor < remainder {
+                quotient = quotient + 1;
+                remainder = remainder - divisor;
+            };
+            (quotient, remainder)
+        }
+
+        fun Pick(a: Nat, b: Nat, c: Nat) -> Nat {
+            let s = a + b;
+            var v = s;
+            let r = match c {
+                0 => { v = s * 2; s },
+                n => { let d = Divide(a, b); n + c }
+            };
+            r + v
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let sym = |ident| program.root(ident).unwrap().sym();
+
+    assert_eq!(graphs(&program).len(), 2);
+
+    let divide = graph(&program, sym("Divide")).unwrap();
+    let ops: Vec<_> = divide.nodes.iter().map(|node| &node.op).collect();
+    assert_eq!(ops.iter().filter(|op| matches!(op, Op::Loop(_))).count(), 2);
+    assert_eq!(ops.iter().filter(|op| matches!(op, Op::Exit(_))).count(), 2);
+    let (w, node) = divide.nodes.iter().enumerate().find(|(_, node)| node.op == Op::While).unwrap();
+    assert_eq!(node.inputs.len(), 3);
+    // Every node comes after its inputs, so the graph has no cycle.
+    assert!(divide.nodes.iter().enumerate().all(|(id, node)| node.inputs.iter().all(|&input| input < id)));
+    // The result reads the versions the loop exits with.
+    let result = &divide.nodes[divide.result];
+    assert!(matches!(&result.op, Op::Record(fields) if fields.len() == 2));
+    assert!(result.inputs.iter().all(|&input| matches!(divide.nodes[input].op, Op::Exit(_)) && divide.nodes[input].inputs == [w]));
+    // `quotient` starts at `0`, enters the loop, is incremented, and exits.
+    let Some(Decl::Let(LetDecl { expr: Expr::Func(func), .. })) = program.root("Divide") else { panic!() };
+    let Stmt::Decl(quotient) = func.body.stmts[0] else { panic!() };
+    assert_eq!(divide.versions[&quotient.sym()].len(), 4);
+
+    let pick = graph(&program, sym("Pick")).unwrap();
+    let phis: Vec<_> = pick.nodes.iter().filter(|node| matches!(node.op, Op::Phi(_))).collect();
+    assert_eq!(phis.len(), 1);
+    assert_eq!(phis[0].inputs.len(), 3);
+    // `let`s are dissolved: `s` is the `+` node itself.
+    assert!(pick.nodes.iter().all(|node| !matches!(&node.op, Op::Global(sym) if program.symbol(*sym).ident == "s")));
+    assert_eq!(pick.nodes.iter().filter(|node| node.op == Op::Builtin(Builtin::Add)).count(), 3);
+    assert_eq!(pick.nodes.iter().filter(|node| node.op == Op::Call).count(), 1);
+
+    let dot = pick.dot(&program);
+    assert!(dot.starts_with("digraph \"Pick\" {\n"));
+    assert!(dot.contains("[label=\"φ v\\nv.2\"]"), "{}", dot);
+    assert!(dot.contains("[label=\"Divide\"]"));
+    assert!(dot.contains("shape=doublecircle"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn test_dataflow_captures() {
+    let src = "
+        fun F(a: Nat, b: Nat) -> Nat {
+            let s = a * b;
+            let f = fun (x: Nat) -> Nat { x + s };
+            f(1)
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let f = graph(&program, program.root("F").unwrap().sym()).unwrap();
+
+    // The function takes the `*` it captures, and the call takes the function.
+    let (mul, _) = f.nodes.iter().enumerate().find(|(_, node)| node.op == Op::Builtin(Builtin::Mul)).unwrap();
+    let (func, node) = f.nodes.iter().enumerate().find(|(_, node)| node.op == Op::Func).unwrap();
+    assert_eq!(node.inputs, [mul]);
+    assert_eq!(f.nodes[f.result].op, Op::Call);
+    assert_eq!(f.nodes[f.result].inputs[0], func);
+
+    let report = paracell_represent::parallelism::analyze(&program, &f);
+    assert_eq!(report.critical_path, 2);
+    assert_eq!(report.subgraphs, [vec![mul, f.result]]);
+}
+
+#[test]
+fn test_dataflow_globals() {
+    let src = "
+        var count = 0;
+        var g = 0;
+
+        fun Tick(n: Nat) -> Nat {
+            while count < n {
+                count = count + 1;
+            };
+            count
+        }
+
+        fun P(c: Nat) -> Nat {
+            match c {
+                0 => { g = 5; 0 },
+                _ => 1
+            };
+            g
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let sym = |ident| program.root(ident).unwrap().sym();
+
+    // `count` enters the loop from its declaration, and the result is the version it exits with.
+    let tick = graph(&program, sym("Tick")).unwrap();
+    let (global, _) = tick.nodes.iter().enumerate().find(|(_, node)| node.op == Op::Global(sym("count"))).unwrap();
+    let (w, _) = tick.nodes.iter().enumerate().find(|(_, node)| node.op == Op::While).unwrap();
+    assert!(tick.nodes.iter().any(|node| node.op == Op::Loop(sym("count")) && node.inputs == [global]));
+    assert_eq!(tick.nodes[tick.result].op, Op::Exit(sym("count")));
+    assert_eq!(tick.nodes[tick.result].inputs, [w]);
+
+    // `g` is muxed between the `5` one arm assigns and its declaration.
+    let p = graph(&program, sym("P")).unwrap();
+    let (global, _) = p.nodes.iter().enumerate().find(|(_, node)| node.op == Op::Global(sym("g"))).unwrap();
+    let result = &p.nodes[p.result];
+    assert_eq!(result.op, Op::Phi(sym("g")));
+    assert!(matches!(p.nodes[result.inputs[1]].op, Op::Nat(_)));
+    assert_eq!(result.inputs[2], global);
+}
@@ -0,0 +1,47 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use num_bigint::BigUint;
+use paracell_parser_lalrpop::flow;
+use paracell_represent::eval::constants;
+use paracell_represent::lower::lower;
+use typed_arena::Arena;
+
+#[test]
+fn test_eval() {
+    let src = "
+        let p = 0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF;
+        let q = p * p + 1;
+        let r = p - (q >> 256);
+        let s = { let half = p >> 1; half or half >> 1 };
+        let t = p % 0x1_0000 == 0xFFFF && !0;
+
+        let under = 1 - 2;
+        let zero = p / 0;
+        let inverted = ~p;
+        let shifted: Nat[8] = 1 << 9;
+        let far = 1 << 0xFFFF_FFFF;
+        var v = 1;
+        let w = v + 1;
+        let f = fun (x: Nat) -> Nat { x };
+        let loop = loop + 1;
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let values = constants(&program);
+
+    let value = |ident| values.get(&program.root(ident).unwrap().sym()).cloned();
+    let p = (BigUint::from(1u8) << 256u32) - 1u8;
+
+    assert_eq!(value("p"), Some(p.clone()));
+    assert_eq!(value("q"), Some(&p * &p + 1u8));
+    assert_eq!(value("r"), Some(BigUint::from(1u8)));
+    assert_eq!(value("s"), Some(&p >> 1u8 | &p >> 2u8));
+    assert_eq!(value("t"), Some(BigUint::from(1u8)));
+
+    for ident in ["under", "zero", "inverted", "shifted", "far", "v", "w", "f", "loop"] {
+        assert_eq!(value(ident), None, "{}", ident);
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::check::check;
+use paracell_represent::layout::{Layout, Size, synthesizable};
+use paracell_represent::lower::lower;
+use paracell_represent::sym::*;
+use typed_arena::Arena;
+
+#[test]
+fn test_layout() {
+    let src = "
+        type List = union { Nil: (), Cons: (Nat[8], List) };
+        type Tree = union { Leaf: Nat[8], Node: Branch };
+        type Branch = record { left: Tree, right: Tree };
+        type Boxed = (List, Nat[8]);
+        type Op = union { Add: Nat[4], Sub: Nat[4], Nop: () };
+        type Word[N: Nat] = record { lo: Bits[N], hi: Bits[N] };
+
+        fun Len(l: List) -> Nat {
+            match l {
+                List::Nil => 0,
+                List::Cons(_, rest) => 1 + Len(rest)
+            }
+        }
+
+        fun Sum(w: Word[8]) -> Bits[9] { w.lo + w.hi }
+
+        fun Depth(t: Tree) -> Nat {
+            type Path = union { End: (), Step: Turn };
+            type Turn = record { left: Nat[1], next: Path };
+            let p = Path::End;
+            match t {
+                Tree::Leaf(_) => 0,
+                Tree::Node(b) => 1 + Depth(b.left)
+            }
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let layout = Layout::new(&program);
+    let sym = |ident| program.root(ident).unwrap().sym();
+    let alias = |ident| Type::Alias(sym(ident), Vec::new());
+
+    assert!(layout.is_recursive(sym("List")));
+    assert!(layout.is_recursive(sym("Tree")));
+    assert!(layout.is_recursive(sym("Branch")));
+    assert!(!layout.is_recursive(sym("Boxed")));
+    assert_eq!(layout.size(&alias("Boxed")), Size::Recursive(sym("List")));
+    assert_eq!(layout.size(&alias("Op")), Size::Bits(6));
+    assert_eq!(layout.size(&Type::Alias(sym("Word"), vec![GenericArg::Width(Width::Const(8))])), Size::Bits(16));
+
+    // Types in a block may refer to those declared after them.
+    let Some(Decl::Let(LetDecl { expr: Expr::Func(depth), .. })) = program.root("Depth") else { panic!() };
+    let path = depth.body.decls.get(&"Path").unwrap().sym();
+
+    assert!(layout.is_recursive(path));
+
+    let errors = synthesizable(&program, &typing, sym("Len"));
+    let labels: Vec<_> = errors.iter().map(|(_, diag)| diag.labels[0].message.as_str()).collect();
+
+    assert_eq!(errors[0].1.code, "E2012");
+    assert_eq!(errors[0].1.message, "`Len` cannot be synthesized, as `List` is recursive");
+    assert_eq!(labels, ["`l` is `List`, which has no finite size", "`rest` is `List`, which has no finite size"]);
+    assert!(synthesizable(&program, &typing, sym("Sum")).is_empty());
+    assert_eq!(synthesizable(&program, &typing, sym("Depth")).len(), 3);
+}
+
+#[test]
+fn test_alias_cycle() {
+    let src = "
+        type A = B;
+        type B = A;
+        type C[T] = C[T];
+        type D = record { d: D };
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    let codes: Vec<_> = typing.diagnostics.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E2011", "E2011", "E2011"]);
+    assert_eq!(typing.diagnostics[0].1.message, "`A` stands for itself");
+}
@@ -0,0 +1,209 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_parser_sem::diag::Source;
+use paracell_parser_sem::module::load;
+use paracell_represent::lower::{lower, lower_program};
+use paracell_represent::resolve::resolve_program;
+use paracell_represent::sym::*;
+use typed_arena::Arena;
+
+const SRC: &str = "
+    type Op = union { Add: Nat, Sub: Nat, Mul: () };
+
+    fun ALU(a: Nat, b: Nat, op: Op) -> Nat {
+        match op {
+            Op::Add(c) | Op::Sub(c) => a + c,
+            Op::Mul => a * b
+        }
+    }
+
+    fun Divide(dividend: Nat, divisor: Nat) -> (Nat, Nat) {
+        var quotient = 0;
+        var remainder = dividend;
+        while divisor < remainder {
+            quotient = quotient + 1;
+            remainder = remainder - divisor;
+        };
+        (quotient, remainder)
+    }
+
+    let r = Op::Add(1) |> fun (op: Op) -> Nat { ALU(2, 3, op) };
+";
+
+fn func<'a>(decl: &'a Decl<'a>) -> &'a FuncExpr<'a> {
+    match decl {
+        Decl::Let(LetDecl { expr: Expr::Func(v), .. }) => v,
+        decl => panic!("not a function: {:?}", decl),
+    }
+}
+
+fn sym(expr: &Expr) -> Symbol {
+    match expr {
+        Expr::Symbol(v) => v.sym,
+        expr => panic!("not a symbol: {:?}", expr),
+    }
+}
+
+#[test]
+fn test_lower() {
+    let file = flow::parse(SRC).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+
+    let op = program.root("Op").unwrap().sym();
+    let alu = func(program.root("ALU").unwrap());
+
+    assert_eq!(program.symbol(alu.params[2]).ident, "op");
+    assert!(matches!(alu.ty.params.fields[2].ty, Type::Alias(ty, _) if ty == op));
+
+    let Expr::Match(m) = &alu.body.expr else { panic!("{:?}", alu.body.expr) };
+
+    assert_eq!(sym(&m.expr), alu.params[2]);
+
+    let Pattern::Or(or) = &m.cases[0].pattern else { panic!("{:?}", m.cases[0].pattern) };
+    let [Pattern::Variant(add), Pattern::Variant(sub)] = or.alts.as_slice() else { panic!("{:?}", or.alts) };
+    let (Some(Pattern::Bind(c)), Some(Pattern::Bind(c2))) = (add.payload.as_deref(), sub.payload.as_deref()) else { panic!() };
+
+    assert_eq!((add.ty, add.ident.as_str(), c.sym), (op, "Add", c2.sym));
+
+    let Expr::Apply(sum) = &m.cases[0].expr.expr else { panic!("{:?}", m.cases[0].expr.expr) };
+
+    assert!(matches!(sum.func, Expr::Builtin(BuiltinExpr { builtin: Builtin::Add, .. })));
+    assert_eq!((sym(&sum.params.fields[0].expr), sym(&sum.params.fields[1].expr)), (alu.params[0], c.sym));
+    assert!(matches!(&m.cases[1].pattern, Pattern::Variant(v) if v.ident == "Mul" && v.payload.is_none()));
+
+    let divide = func(program.root("Divide").unwrap());
+    let quotient = divide.body.decls.get(&"quotient").unwrap().sym();
+
+    assert!(matches!(divide.body.decls.get(&"remainder"), Some(Decl::Var(v)) if sym(&v.expr) == divide.params[0]));
+    assert_eq!(divide.body.stmts.len(), 3);
+
+    let Stmt::Expr(Expr::While(w)) = &divide.body.stmts[2] else { panic!("{:?}", divide.body.stmts[2]) };
+
+    assert!(matches!(&w.body.stmts[0], Stmt::Expr(Expr::Assign(v)) if v.target == quotient));
+    assert!(matches!(w.body.expr, Expr::Record(ref v) if v.fields.is_empty()));
+    assert!(matches!(&divide.body.expr, Expr::Record(v) if sym(&v.fields[0].expr) == quotient));
+
+    // `Op::Add(1) |> f` is `f(Op::Add(1))`.
+    let Some(Decl::Let(r)) = program.root("r") else { panic!() };
+    let Expr::Apply(apply) = &r.expr else { panic!("{:?}", r.expr) };
+
+    assert!(matches!(&apply.params.fields[0].expr, Expr::Variant(v) if v.ty == op && matches!(v.payload.as_deref(), Some(Expr::Nat(n)) if n.val == 1u8.into())));
+
+    let Expr::Func(f) = &apply.func else { panic!("{:?}", apply.func) };
+
+    assert!(matches!(&f.body.expr, Expr::Apply(v) if sym(&v.func) == program.root("ALU").unwrap().sym()));
+}
+
+#[test]
+fn test_lower_errors() {
+    let src = "
+        let a = 1;
+        type T = Nat;
+        fun f(x: a) -> Nat {
+            a = 2;
+            let y = T;
+            let z = a::b;
+            match x { a::b(w) => w, _ => Nat }
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let errors = lower(&file, &arena).unwrap_err();
+    let codes: Vec<_> = errors.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E1004", "E1003", "E1005", "E1005", "E1006", "E1005"]);
+    assert_eq!(errors[0].1.labels[0].message, "`a` is a value");
+    assert_eq!(errors[1].1.message, "cannot assign to `a`");
+    assert_eq!(errors[2].1.labels[0].message, "`T` is a type");
+    assert_eq!(errors[3].1.labels[0].message, "`a` has no variants");
+
+    // Resolution errors stop lowering before it starts.
+    let file = flow::parse("let a = b").unwrap();
+
+    assert_eq!(lower(&file, &arena).unwrap_err()[0].1.code, "E1001");
+}
+
+#[test]
+fn test_lower_program() {
+    let main = "mod alu; import alu::ALU; let a = ALU(1) + alu::Two";
+    let alu = "fun ALU(v: Nat) -> Nat { v + Two }; let Two = 2";
+    let program = load(
+        Source::new("main.flow", main),
+        |_| Some(Source::new("alu.flow", alu)),
+        |source| flow::parse(&source.text).map_err(|errors| errors.to_diagnostics()),
+    ).unwrap();
+    let resolution = resolve_program(&program);
+    let arena = Arena::new();
+    let lowered = lower_program(&program, &resolution, &arena).unwrap();
+
+    assert_eq!(lowered.modules[1].path, "alu");
+
+    let alu = lowered.modules[1].decls.get(&"ALU").unwrap().sym();
+    let two = lowered.modules[1].decls.get(&"Two").unwrap().sym();
+    let Some(Decl::Let(a)) = lowered.root("a") else { panic!() };
+    let Expr::Apply(sum) = &a.expr else { panic!("{:?}", a.expr) };
+
+    assert!(matches!(&sum.params.fields[0].expr, Expr::Apply(v) if sym(&v.func) == alu));
+    assert_eq!(sym(&sum.params.fields[1].expr), two);
+    assert_eq!(lowered.symbol(two).module, 1);
+    assert!(matches!(lowered.decl(two), Some(Decl::Let(v)) if v.ident == "Two"));
+}
+
+#[test]
+fn test_lower_widths() {
+    let src = "type T = Nat; let a: Bits[8] = 1; let b: Nat[Nat] = 1; let c: T[8] = 1; let d: Int[1, 2] = 1;";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let errors = lower(&file, &arena).unwrap_err();
+    let codes: Vec<_> = errors.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E1007", "E1008", "E1008"]);
+    assert_eq!(errors[1].1.message, "`T` takes no parameters");
+    assert_eq!(errors[2].1.message, "`Int` takes 1 parameter but 2 were given");
+
+    let program = lower(&flow::parse("let a: Int[_] = 1").unwrap(), &arena).unwrap();
+    let Some(Decl::Let(LetDecl { ty: Some(Type::Primitive(PrimitiveType::Nat(ty))), .. })) = program.root("a") else { panic!() };
+
+    assert_eq!((ty.kind, &ty.width), (NatKind::Int, &Width::Var(WidthVar(0))));
+}
+
+#[test]
+fn test_lower_generics() {
+    let src = "
+        type Vec[T, N: Nat] = record { elems: T, len: Nat[N] };
+        fun First[T, N: Nat](v: Vec[T, N]) -> T { v.elems }
+
+        let a: Vec = 1;
+        let b: Vec[Nat] = 1;
+        let c: Vec[8, Nat] = 1;
+        fun d[T, N: Nat](x: N, y: Bits[T]) -> T { T }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let errors = lower(&file, &arena).unwrap_err();
+    let codes: Vec<_> = errors.iter().map(|(_, diag)| diag.code).collect();
+
+    assert_eq!(codes, ["E1008", "E1008", "E1004", "E1007", "E1004", "E1007", "E1005"]);
+    assert_eq!(errors[0].1.message, "`Vec` takes 2 parameters but 0 were given");
+    assert_eq!(errors[1].1.message, "`Vec` takes 2 parameters but 1 were given");
+    assert_eq!(errors[2].1.labels[0].message, "`T` of `Vec` is a type");
+    assert_eq!(errors[4].1.labels[0].message, "`N` is a width");
+    assert_eq!(errors[6].1.labels[0].message, "`T` is a type");
+
+    let file = flow::parse(&src[..src.find("let a").unwrap()]).unwrap();
+    let program = lower(&file, &arena).unwrap();
+    let Some(Decl::Let(first)) = program.root("First") else { panic!() };
+    let [GenericParam::Type(t), GenericParam::Width(n)] = first.generics.as_slice() else { panic!("{:?}", first.generics) };
+    let Expr::Func(func) = &first.expr else { panic!() };
+
+    assert_eq!(program.symbol(*t).kind, SymbolKind::Generic);
+    assert!(matches!(&func.ty.results, Type::Param(sym) if sym == t));
+    assert!(matches!(
+        &func.ty.params.fields[0].ty,
+        Type::Alias(_, args) if matches!(args.as_slice(), [GenericArg::Type(Type::Param(a)), GenericArg::Width(Width::Param(b))] if a == t && b == n)
+    ));
+}
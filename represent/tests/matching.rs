@@ -0,0 +1,90 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::check::check;
+use paracell_represent::lower::lower;
+use paracell_represent::matching;
+use typed_arena::Arena;
+
+#[test]
+fn test_matching() {
+    let src = "
+        type Op = union { Add: Nat[4], Pair: (Nat[1], Nat[1]), Nop: () };
+        type Opt = union { Some: Op, None: () };
+
+        fun Full(o: Opt, n: Nat[2], p: (Nat[1], Nat[1])) -> Nat[_] {
+            let a = match o {
+                Opt::Some(Op::Add(_) | Op::Nop) => 0,
+                Opt::Some(Op::Pair(0, _)) => 1,
+                Opt::Some(Op::Pair(1, _)) => 3,
+                Opt::None => 2
+            };
+            let b = match n { 0 | 1 => 0, 2..4 => 1 };
+            let c = match p { (0, _) => 0, (_, 0) => 1, (1, 1) => 2 };
+            a + b + c
+        }
+
+        fun Partial(o: Opt, n: Nat[2], m: Nat, p: (Nat[1], Nat[1])) -> Nat[_] {
+            let a = match o {
+                Opt::Some(Op::Add(_)) => 0,
+                Opt::None => 1
+            };
+            let b = match n { 0 => 0, 1..=2 => 1 };
+            let c = match m { 0 | 1 => 0, 3 => 1 };
+            let d = match p { (0, _) => 0, (_, 0) => 1 };
+            a + b + c + d
+        }
+
+        fun Dead(o: Op, n: Nat[2]) -> Nat[_] {
+            let a = match o {
+                _ => 0,
+                Op::Nop => 1
+            };
+            let b = match n { 0..=3 => 0, 2 => 1 };
+            a + b
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let diagnostics = matching::check(&program, &typing);
+    let found: Vec<_> = diagnostics.iter().map(|(_, diag)| (diag.code, diag.labels[0].message.as_str())).collect();
+    assert_eq!(found, [
+        ("E2013", "`Opt::Some(Op::Pair(_, _))` and `Opt::Some(Op::Nop)` not covered"),
+        ("E2013", "`3` not covered"),
+        ("E2013", "`2` and `4` not covered"),
+        ("E2013", "`(1, 1)` not covered"),
+        ("W2001", "every value this matches is matched by an arm above"),
+        ("W2001", "every value this matches is matched by an arm above"),
+    ]);
+    let help = &diagnostics[0].1.help;
+    assert!(help[0].contains("`_` arm"));
+}
+
+#[test]
+fn test_matching_wild() {
+    let src = "
+        type L = union { Nil: (), Cons: (Nat[1], L) };
+
+        fun Head(l: L) -> Nat[_] {
+            match l { L::Nil => 0, L::Cons(0, _) => 1 }
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    // The tail is never matched against, so any tail is left out, rather than each kind of list.
+    let diagnostics = matching::check(&program, &typing);
+    let found: Vec<_> = diagnostics.iter().map(|(_, diag)| (diag.code, diag.labels[0].message.as_str())).collect();
+    assert_eq!(found, [("E2013", "`L::Cons(1, _)` not covered")]);
+}
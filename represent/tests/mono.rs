@@ -0,0 +1,186 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_parser_sem::diag::Source;
+use paracell_parser_sem::module::load;
+use paracell_represent::check::check;
+use paracell_represent::lower::{lower, lower_program};
+use paracell_represent::mono::monomorphize;
+use paracell_represent::resolve::resolve_program;
+use paracell_represent::sym::*;
+use std::collections::HashSet;
+use typed_arena::Arena;
+
+#[test]
+fn test_monomorphize() {
+    let src = "
+        type Pair[T] = (T, T);
+        type List[T] = union { Nil: (), Cons: (T, List[T]) };
+
+        fun Swap[T](p: Pair[T]) -> Pair[T] { match p { (x, y) => (y, x) } }
+        fun Twice[T](p: Pair[T]) -> Pair[T] { Swap(Swap(p)) }
+        fun Low[N: Nat](v: Nat[N]) -> Nat[N] { trunc(v + 1) }
+        fun Len[T](l: List[T]) -> Nat {
+            match l {
+                List::Nil => 0,
+                List::Cons(_, rest) => 1 + Len(rest)
+            }
+        }
+        fun Fixed[T](x: T, n: Nat) -> Nat {
+            match n {
+                0 => 0,
+                _ => Fixed((1, 2), n - 1)
+            }
+        }
+
+        let a: Pair[Bits[4]] = Twice((1, 2));
+        let b: Nat[8] = Low(3);
+        let l: List[Bits[4]] = List::Nil;
+        let n = Len(l);
+        let f = Fixed(b, 2);
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let copies = Arena::new();
+    let mono = monomorphize(&program, &typing, &copies).unwrap();
+    let mut names: Vec<_> = mono.instances.iter().map(|instance| instance.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, [
+        "_P3LenIb4_E",
+        "_P3LowIW8_E",
+        "_P4ListIb4_E",
+        "_P4PairIb4_E",
+        "_P4SwapIb4_E",
+        "_P5FixedIR10n1_11n2_EE",
+        "_P5FixedIn8_E",
+        "_P5TwiceIb4_E",
+    ]);
+
+    let swap = mono.instance("_P4SwapIb4_E").unwrap();
+    let Decl::Let(LetDecl { ident, generics, expr: Expr::Func(func), .. }) = swap.decl else { panic!() };
+    assert_eq!(ident, "_P4SwapIb4_E");
+    assert!(generics.is_empty());
+    assert_eq!(program.type_name(&func.ty.params.fields[0].ty), "Pair[Bits[4]]");
+    let Decl::TypeAlias(pair) = mono.instance("_P4PairIb4_E").unwrap().decl else { panic!() };
+    assert_eq!(program.type_name(&pair.ty), "(Bits[4], Bits[4])");
+
+    // Both uses of `Swap` within `Twice` share one instance, and `Len` uses itself.
+    let index = |name| mono.instances.iter().position(|instance| instance.name == name).unwrap();
+    let twice = &mono.instances[index("_P5TwiceIb4_E")];
+    assert_eq!(twice.uses.len(), 2);
+    assert!(twice.uses.values().all(|&i| i == index("_P4SwapIb4_E")));
+    let len = index("_P3LenIb4_E");
+    assert_eq!(mono.instances[len].uses.values().collect::<Vec<_>>(), [&len]);
+    let Some(Decl::Let(a)) = program.root("a") else { panic!() };
+    let Expr::Apply(apply) = &a.expr else { panic!() };
+    assert_eq!(mono.uses[&(0, apply.func.span())], index("_P5TwiceIb4_E"));
+}
+
+#[test]
+fn test_polymorphic_recursion() {
+    let src = "
+        type Nest[T] = union { Leaf: T, Node: Nest[(T, T)] };
+
+        fun Grow[T](x: T) -> Nat { Grow((x, x)) }
+        fun Ping[T](x: T) -> Nat { Pong(x) }
+        fun Pong[T](x: T) -> Nat { Ping((x, 1)) }
+        fun Wide[N: Nat](v: Nat[N]) -> Nat { Wide(v + v) }
+        fun Keep[N: Nat](v: Nat[N]) -> Nat { Keep(v) }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let copies = Arena::new();
+    let diagnostics = monomorphize(&program, &typing, &copies).unwrap_err();
+    let found: Vec<_> = diagnostics.iter().map(|(_, diag)| (diag.code, diag.labels[0].message.as_str())).collect();
+    assert_eq!(found, [
+        ("E2014", "`Nest` refers to `Nest[(T, T)]`"),
+        ("E2014", "`Grow` is used here at `Grow[(T, T)]`"),
+        ("E2014", "`Ping` is used here at `Ping[(T, Nat[1])]`"),
+        ("E2014", "`Wide` is used here at `Wide[N + 1]`"),
+    ]);
+    assert_eq!(diagnostics[1].1.message, "instantiating `Grow` would never end");
+}
+
+#[test]
+fn test_mangle_paths() {
+    let main = "
+        mod m;
+
+        fun Id[T](v: T) -> T { v }
+        fun A(x: Nat[8]) -> Nat[8] { fun Id[T](v: T) -> T { v }; Id(x) }
+        fun B(x: Nat[8]) -> Nat[8] { fun Id[T](v: T) -> T { v }; Id(x) }
+        fun C(x: Nat[8]) -> Nat[9] {
+            { fun Id[T](v: T) -> T { v }; Id(x) } + { fun Id[T](v: T) -> T { v }; Id(x) }
+        }
+
+        let a: Nat[8] = Id(1);
+        let b: Nat[8] = m::Id(1);
+        type Pair[T] = (T, T);
+        let p: Pair[Nat[8]] = (1, 2);
+    ";
+    let program = load(
+        Source::new("main.flow", main),
+        |_| Some(Source::new("m.flow", "fun Id[T](v: T) -> T { v }; type Pair[T] = (T, T); let p: Pair[Nat[8]] = (1, 2)")),
+        |source| flow::parse(&source.text).map_err(|errors| errors.to_diagnostics()),
+    ).unwrap();
+    let resolution = resolve_program(&program);
+    let arena = Arena::new();
+    let program = lower_program(&program, &resolution, &arena).unwrap();
+    let typing = check(&program);
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let copies = Arena::new();
+    let mono = monomorphize(&program, &typing, &copies).unwrap();
+    let mut names: Vec<_> = mono.instances.iter().map(|instance| instance.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, [
+        "_P2IdIn8_E",
+        "_P4PairIn8_E",
+        "_PN1A2IdEIn8_E",
+        "_PN1B2IdEIn8_E",
+        "_PN1C2IdD1_EIn8_E",
+        "_PN1C2IdEIn8_E",
+        "_PNM1m2IdEIn8_E",
+        "_PNM1m4PairEIn8_E",
+    ]);
+    // Each is the copy of a declaration of its own.
+    let syms: HashSet<_> = mono.instances.iter().map(|instance| instance.sym).collect();
+    assert_eq!(syms.len(), names.len());
+}
+
+#[test]
+fn test_generalized_use() {
+    let src = "
+        fun Id[T](v: T) -> T { v }
+        fun M(a: Nat[8]) -> Nat[8] { let g = fun (z) { Id(z) }; g(a) }
+        fun K[T](x: T, n: Nat) -> Nat {
+            match n {
+                0 => 0,
+                _ => { let f = fun (z) { K((z, z), n - 1) }; f(x) }
+            }
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let typing = check(&program);
+    assert!(!typing.has_errors(), "{:?}", typing.diagnostics);
+
+    let copies = Arena::new();
+    let diagnostics = monomorphize(&program, &typing, &copies).unwrap_err();
+    let found: Vec<_> = diagnostics.iter().map(|(_, diag)| (diag.code, diag.message.as_str())).collect();
+    assert_eq!(found, [
+        ("E2015", "cannot tell which instance of `Id` is used here"),
+        ("E2015", "cannot tell which instance of `K` is used here"),
+    ]);
+}
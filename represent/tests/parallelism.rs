@@ -0,0 +1,47 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_represent::dataflow::graphs;
+use paracell_represent::lower::lower;
+use paracell_represent::parallelism::{analyze, json};
+use typed_arena::Arena;
+
+#[test]
+fn test_parallelism() {
+    let src = "
+        fun F(a: Nat, b: Nat, c: Nat) -> (Nat, Nat) {
+            let x = (a + b) * c;
+            let y = a - c;
+            (x, y)
+        }
+
+        fun G(a: Nat) -> Nat { a }
+    ";
+    let file = flow::parse(src).unwrap();
+    let arena = Arena::new();
+    let program = lower(&file, &arena).unwrap();
+    let reports: Vec<_> = graphs(&program).iter().map(|graph| analyze(&program, graph)).collect();
+
+    // a, b, c, +, *, -, and the record.
+    let f = &reports[0];
+    assert_eq!(f.func, "F");
+    assert_eq!(f.asap, [0, 0, 0, 1, 2, 1, 2]);
+    assert_eq!(f.alap, [0, 0, 1, 1, 2, 2, 2]);
+    assert_eq!(f.critical_path, 2);
+    assert_eq!(f.critical, [3, 4]);
+    assert_eq!(f.widths, [2, 1]);
+    assert_eq!(f.max_width, 2);
+    // The record only gathers `x` and `y`, which are computed apart.
+    assert_eq!(f.subgraphs, [vec![3, 4], vec![5]]);
+
+    let g = &reports[1];
+    assert_eq!(g.critical_path, 0);
+    assert!(g.widths.is_empty() && g.subgraphs.is_empty());
+
+    let json = json(&reports);
+    assert!(json.starts_with('['));
+    assert!(json.contains("\"func\": \"F\""));
+    assert!(json.contains("\"critical_path\": 2"));
+}
@@ -0,0 +1,138 @@
+// Copyright 2025 Jelly Terra <jellyterra@symboltics.com>
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0
+// that can be found in the LICENSE file and https://mozilla.org/MPL/2.0/.
+
+use paracell_parser_lalrpop::flow;
+use paracell_parser_sem::diag::Source;
+use paracell_parser_sem::module::load;
+use paracell_parser_sem::span::Span;
+use paracell_represent::resolve::{Binding, Resolution, resolve, resolve_program};
+use paracell_represent::sym::Builtin;
+
+/// Span of the `nth` occurrence of `needle` in `src` that is not part of a longer name.
+fn span(src: &str, needle: &str, nth: usize) -> Span {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+    let start = src.match_indices(needle)
+        .map(|(start, _)| start)
+        .filter(|start| !word(src[..*start].chars().last()) && !word(src[start + needle.len()..].chars().next()))
+        .nth(nth)
+        .unwrap();
+    Span::new(start, start + needle.len())
+}
+
+fn codes(resolution: &Resolution) -> Vec<&'static str> {
+    resolution.diagnostics.iter().map(|(_, diag)| diag.code).collect()
+}
+
+#[test]
+fn test_resolve_bindings() {
+    let src = "
+        fun f(a: Nat) -> Nat {
+            let b = a + g(1);
+            match b {
+                Op::Add(x) | Op::Sub(x) => x,
+                _ => b
+            }
+        }
+        fun g(n: Nat) -> Nat { f(n) }
+        type Op = union { Add: Nat, Sub: Nat };
+    ";
+    let file = flow::parse(src).unwrap();
+    let resolution = resolve(&file);
+
+    assert!(codes(&resolution).is_empty(), "{:?}", resolution.diagnostics);
+    assert!(matches!(resolution.binding(0, span(src, "a", 1)), Some(Binding::Param(v)) if v.ident == "a"));
+    assert!(matches!(resolution.binding(0, span(src, "a + g(1)", 0)), Some(Binding::Builtin(Builtin::Add))));
+    assert!(matches!(resolution.binding(0, span(src, "Nat", 0)), Some(Binding::Builtin(Builtin::Nat))));
+    // Top-level declarations see each other in any order.
+    assert!(matches!(resolution.binding(0, span(src, "g", 0)), Some(Binding::Decl(v)) if v.ident() == "g"));
+    assert!(matches!(resolution.binding(0, span(src, "Op::Add", 0)), Some(Binding::Decl(v)) if v.ident() == "Op"));
+    assert!(matches!(resolution.binding(0, span(src, "b", 2)), Some(Binding::Decl(v)) if v.ident() == "b"));
+
+    let x = span(src, "x", 0);
+
+    assert!(matches!(resolution.binding(0, span(src, "x", 1)), Some(Binding::Pattern(v)) if v.span == x));
+    assert!(matches!(resolution.binding(0, span(src, "x", 2)), Some(Binding::Pattern(v)) if v.span == x));
+}
+
+#[test]
+fn test_resolve_errors() {
+    let src = "
+        let a = 1;
+        let a = 2;
+        fun f(a: Nat) -> Nat {
+            let c = d;
+            let c = c;
+            let d = 1;
+            fun loop(n: Nat) -> Nat { loop(n) };
+            let e = fun (m: Nat) -> Nat { e(m) };
+            c
+        };
+        let p = match 1 { (x, x) => x };
+        type Nat = Nat;
+    ";
+    let file = flow::parse(src).unwrap();
+    let resolution = resolve(&file);
+
+    assert_eq!(codes(&resolution), ["E1002", "W1001", "E1001", "E1002", "E1002"]);
+    assert!(resolution.has_errors());
+
+    let shadow = &resolution.diagnostics[1].1;
+
+    assert_eq!(shadow.message, "`a` shadows an outer binding");
+    assert_eq!(shadow.labels[1].span, span(src, "let a = 1", 0));
+
+    let undefined = &resolution.diagnostics[2].1;
+
+    assert_eq!(undefined.message, "cannot find `d` in this scope");
+
+    // Functions and types see themselves, other declarations in blocks do not.
+    assert!(matches!(resolution.binding(0, span(src, "e", 1)), Some(Binding::Decl(v)) if v.ident() == "e"));
+    assert!(matches!(resolution.binding(0, span(src, "Nat", 7)), Some(Binding::Decl(v)) if v.ident() == "Nat"));
+}
+
+#[test]
+fn test_resolve_or_pattern() {
+    let src = "
+        type E = union { A: Nat, B: Nat, C: (Nat, Nat) };
+        fun f(e: E) -> Nat {
+            match e {
+                E::A(x) | E::B(y) => x,
+                E::C(x, y) | E::A(x) | E::B(_) => x,
+                E::C(x, _) | E::C(_, x) => x
+            }
+        }
+    ";
+    let file = flow::parse(src).unwrap();
+    let resolution = resolve(&file);
+
+    assert_eq!(codes(&resolution), ["E1009", "E1009", "E1009", "E1009"]);
+
+    let diags: Vec<_> = resolution.diagnostics.iter().map(|(_, diag)| diag).collect();
+
+    assert_eq!(diags[0].message, "`y` is not bound in every alternative");
+    assert_eq!(diags[0].labels[0].span, span(src, "E::A(x)", 0));
+    assert_eq!(diags[1].message, "`x` is not bound in every alternative");
+    assert_eq!(diags[1].labels[0].span, span(src, "E::B(y)", 0));
+    assert_eq!(diags[2].message, "`y` is not bound in every alternative");
+    assert_eq!(diags[3].message, "`x`, `y` are not bound in every alternative");
+    assert_eq!(diags[3].labels[0].message, "does not bind `x`, `y`");
+}
+
+#[test]
+fn test_resolve_program() {
+    let main = "mod alu; import alu::ALU; let a = ALU(1) + alu::adder::Add(2); let b = alu::Sub(3)";
+    let files = [("alu", "mod adder; fun ALU(v: Nat) -> Nat { adder::Add(v) }"), ("alu/adder", "let Add = 1")];
+    let program = load(
+        Source::new("main.flow", main),
+        |path| files.iter().find(|(name, _)| *name == path.segments.join("/")).map(|(name, text)| Source::new(*name, *text)),
+        |source| flow::parse(&source.text).map_err(|errors| errors.to_diagnostics()),
+    ).unwrap();
+    let resolution = resolve_program(&program);
+
+    assert_eq!(codes(&resolution), ["E1001"]);
+    assert_eq!(resolution.diagnostics[0].1.labels[0].message, "no `Sub` in `alu`");
+    assert!(matches!(resolution.binding(0, span(main, "ALU", 1)), Some(Binding::Decl(v)) if v.ident() == "ALU"));
+    assert!(matches!(resolution.binding(0, span(main, "alu::adder::Add", 0)), Some(Binding::Decl(v)) if v.ident() == "Add"));
+    assert!(matches!(resolution.binding(1, span(files[0].1, "adder::Add", 0)), Some(Binding::Decl(v)) if v.ident() == "Add"));
+}
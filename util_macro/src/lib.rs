@@ -51,11 +51,10 @@ pub fn as_variant_derive(input: TokenStream) -> TokenStream {
 
 fn get_attr(attrs: &Vec<Attribute>, ident: &str) -> Expr {
     for attr in attrs {
-        if attr.path().is_ident(ident) {
-            match &attr.meta {
-                Meta::NameValue(v) => return v.value.clone(),
-                _ => {}
-            }
+        if attr.path().is_ident(ident)
+            && let Meta::NameValue(v) = &attr.meta
+        {
+            return v.value.clone();
         }
     }
     panic!("derive attribution {} not found", ident)
@@ -93,3 +92,58 @@ pub fn to_literal(input: TokenStream) -> TokenStream {
 
     expanded.into()
 }
+
+/// Implements `paracell_parser_sem::span::SpanlessEq`, comparing every field except the ones named `span`.
+#[proc_macro_derive(SpanlessEq)]
+pub fn spanless_eq_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let ident = &ast.ident;
+
+    let body = match ast.data {
+        Data::Struct(data_struct) => {
+            let fields = match &data_struct.fields {
+                Fields::Named(fields) => fields.named.iter()
+                    .map(|field| field.ident.clone().unwrap())
+                    .filter(|field| field != "span")
+                    .collect::<Vec<_>>(),
+                _ => panic!("SpanlessEq can only be applied to structures with named fields"),
+            };
+            quote! {
+                true #(&& paracell_parser_sem::span::SpanlessEq::spanless_eq(&self.#fields, &other.#fields))*
+            }
+        }
+        Data::Enum(data_enum) => {
+            let arms = data_enum.variants.iter().map(|variant| {
+                let variant_ident = &variant.ident;
+                match &variant.fields {
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                        (#ident::#variant_ident(a), #ident::#variant_ident(b)) => paracell_parser_sem::span::SpanlessEq::spanless_eq(a, b),
+                    },
+                    Fields::Unit => quote! {
+                        (#ident::#variant_ident, #ident::#variant_ident) => true,
+                    },
+                    _ => panic!("SpanlessEq can only be applied to unit or single-field variants"),
+                }
+            });
+            quote! {
+                #[allow(unreachable_patterns)]
+                match (self, other) {
+                    #(#arms)*
+                    _ => false,
+                }
+            }
+        }
+        _ => panic!("SpanlessEq can only be applied to structures and enumerations"),
+    };
+
+    let expanded = quote! {
+        impl paracell_parser_sem::span::SpanlessEq for #ident {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                #body
+            }
+        }
+    };
+
+    expanded.into()
+}
@@ -38,7 +38,16 @@ where
     pub fn get(&self, k: &K) -> Option<&V> {
         match self.map.get(k) {
             None => None,
-            Some(v) => Some(&self.vals[v.clone()]),
+            Some(v) => Some(&self.vals[*v]),
         }
     }
 }
+
+impl<K, V> Default for OrderedHashMap<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        OrderedHashMap::new()
+    }
+}